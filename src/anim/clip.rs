@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use crate::scene::model::{Scene, Transform};
+
+use super::track::Track;
+
+/// 动画作用的对象
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Target {
+  Element { id: u32 },
+  Material { name: String },
+  Mesh { name: String }, // 变形网格，由 `MorphMesh::apply_value` 处理
+}
+
+/// 动画通道，一个通道控制目标的一个属性
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "channel", rename_all = "snake_case")]
+pub enum Channel {
+  Position { track: Track<[f32; 3]> },
+  Rotation { track: Track<[f32; 3]> }, // 欧拉角（角度）
+  Scale { track: Track<[f32; 3]> },
+  Color { track: Track<[f32; 3]> },
+  /// 任意浮点属性，场景能识别的名称见 `set_float`，其他名称只在采样结果中返回
+  Float { property: String, track: Track<f32> },
+}
+
+/// 采样得到的属性值
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnimatedValue {
+  Position([f32; 3]),
+  Rotation([f32; 3]),
+  Scale([f32; 3]),
+  Color([f32; 3]),
+  Float(String, f32),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnimationTrack {
+  pub target: Target,
+  #[serde(flatten)]
+  pub channel: Channel,
+}
+
+impl AnimationTrack {
+  pub fn new(target: Target, channel: Channel) -> Self {
+    Self { target, channel }
+  }
+
+  pub fn duration(&self) -> f32 {
+    match &self.channel {
+      Channel::Position { track } | Channel::Rotation { track } | Channel::Scale { track } | Channel::Color { track } => track.duration(),
+      Channel::Float { track, .. } => track.duration(),
+    }
+  }
+
+  pub fn sample(&self, time: f32) -> Option<AnimatedValue> {
+    match &self.channel {
+      Channel::Position { track } => track.sample(time).map(AnimatedValue::Position),
+      Channel::Rotation { track } => track.sample_rotation(time).map(AnimatedValue::Rotation),
+      Channel::Scale { track } => track.sample(time).map(AnimatedValue::Scale),
+      Channel::Color { track } => track.sample(time).map(AnimatedValue::Color),
+      Channel::Float { property, track } => track.sample(time).map(|v| AnimatedValue::Float(property.clone(), v)),
+    }
+  }
+}
+
+/// 动画片段，由若干通道组成，时长为所有通道中最后一个关键帧的时间
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnimationClip {
+  pub name: String,
+  pub tracks: Vec<AnimationTrack>,
+}
+
+impl AnimationClip {
+  pub fn new(name: &str) -> Self {
+    Self { name: name.to_string(), tracks: vec![] }
+  }
+
+  pub fn track(mut self, target: Target, channel: Channel) -> Self {
+    self.tracks.push(AnimationTrack::new(target, channel));
+    self
+  }
+
+  pub fn duration(&self) -> f32 {
+    self.tracks.iter().map(|t| t.duration()).fold(0.0, f32::max)
+  }
+
+  /// 在 `time` 处对所有通道采样，结果只取决于时间，不依赖之前的状态
+  pub fn sample(&self, time: f32) -> Vec<(Target, AnimatedValue)> {
+    self.tracks.iter()
+      .filter_map(|t| t.sample(time).map(|v| (t.target.clone(), v)))
+      .collect()
+  }
+
+  /// 采样并写入场景，找不到目标时忽略
+  pub fn apply(&self, time: f32, scene: &mut Scene) {
+    for (target, value) in self.sample(time) {
+      apply_value(scene, &target, &value);
+    }
+  }
+}
+
+/// 把一个采样值写入场景，目标不存在或属性不适用时返回 false
+pub fn apply_value(scene: &mut Scene, target: &Target, value: &AnimatedValue) -> bool {
+  match target {
+    Target::Element { id } => {
+      let Some(el) = scene.element_mut(*id) else {
+        return false
+      };
+      match value {
+        AnimatedValue::Position(v) => el.transform.position = *v,
+        AnimatedValue::Rotation(v) => el.transform.rotation = *v,
+        AnimatedValue::Scale(v) => el.transform.scale = *v,
+        AnimatedValue::Float(property, v) => return set_float(&mut el.transform, property, *v),
+        AnimatedValue::Color(_) => return false,
+      }
+      true
+    },
+    Target::Material { name } => {
+      let Some(material) = scene.materials.iter_mut().find(|m| &m.name == name) else {
+        return false
+      };
+      match value {
+        AnimatedValue::Color(c) => material.color = *c,
+        AnimatedValue::Float(property, v) => {
+          let Some(i) = ["r", "g", "b"].iter().position(|c| c == property) else {
+            return false
+          };
+          material.color[i] = *v;
+        },
+        _ => return false,
+      }
+      true
+    },
+    Target::Mesh { .. } => false,
+  }
+}
+
+/// 元素变换的单个分量：`position.x`、`rotation.y`、`scale.z` 等，`scale` 表示等比缩放
+fn set_float(transform: &mut Transform, property: &str, value: f32) -> bool {
+  if property == "scale" {
+    transform.scale = [value; 3];
+    return true;
+  }
+  let Some((field, axis)) = property.split_once('.') else {
+    return false
+  };
+  let Some(i) = ["x", "y", "z"].iter().position(|a| *a == axis) else {
+    return false
+  };
+  let target = match field {
+    "position" => &mut transform.position,
+    "rotation" => &mut transform.rotation,
+    "scale" => &mut transform.scale,
+    _ => return false,
+  };
+  target[i] = value;
+  true
+}
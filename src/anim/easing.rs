@@ -0,0 +1,106 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+/// 缓动函数，把 0~1 的线性进度映射为新的进度
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+  #[default]
+  Linear,
+  QuadIn,
+  QuadOut,
+  QuadInOut,
+  CubicIn,
+  CubicOut,
+  CubicInOut,
+  SineInOut,
+  BackOut, // 先超出终点再回来
+  BounceOut,
+  ElasticOut,
+}
+
+impl Easing {
+  /// 计算缓动后的进度，`t` 会先被限制在 0~1
+  pub fn apply(self, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match self {
+      Easing::Linear => t,
+      Easing::QuadIn => t * t,
+      Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+      Easing::QuadInOut => {
+        if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 }
+      },
+      Easing::CubicIn => t * t * t,
+      Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+      Easing::CubicInOut => {
+        if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 }
+      },
+      Easing::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+      Easing::BackOut => {
+        let c1 = 1.70158;
+        let c3 = c1 + 1.0;
+        1.0 + c3 * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2)
+      },
+      Easing::BounceOut => bounce_out(t),
+      Easing::ElasticOut => {
+        if t == 0.0 || t == 1.0 {
+          t
+        } else {
+          2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+        }
+      },
+    }
+  }
+}
+
+fn bounce_out(t: f32) -> f32 {
+  let n1 = 7.5625;
+  let d1 = 2.75;
+  if t < 1.0 / d1 {
+    n1 * t * t
+  } else if t < 2.0 / d1 {
+    let t = t - 1.5 / d1;
+    n1 * t * t + 0.75
+  } else if t < 2.5 / d1 {
+    let t = t - 2.25 / d1;
+    n1 * t * t + 0.9375
+  } else {
+    let t = t - 2.625 / d1;
+    n1 * t * t + 0.984375
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const ALL: [Easing; 11] = [
+    Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut, Easing::CubicIn, Easing::CubicOut,
+    Easing::CubicInOut, Easing::SineInOut, Easing::BackOut, Easing::BounceOut, Easing::ElasticOut,
+  ];
+
+  #[test]
+  fn endpoints() {
+    for easing in ALL {
+      assert!(easing.apply(0.0).abs() < 1e-6, "{:?}(0) = {}", easing, easing.apply(0.0));
+      assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{:?}(1) = {}", easing, easing.apply(1.0));
+      // 超出 0~1 的进度先被限制到端点
+      assert_eq!(easing.apply(-1.0), easing.apply(0.0));
+      assert_eq!(easing.apply(2.0), easing.apply(1.0));
+    }
+  }
+
+  #[test]
+  fn midpoints() {
+    assert_eq!(Easing::Linear.apply(0.25), 0.25);
+    assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+    assert_eq!(Easing::QuadOut.apply(0.5), 0.75);
+    assert_eq!(Easing::CubicIn.apply(0.5), 0.125);
+    for easing in [Easing::QuadInOut, Easing::CubicInOut, Easing::SineInOut] {
+      assert!((easing.apply(0.5) - 0.5).abs() < 1e-6, "{:?}", easing);
+    }
+    // 超出终点
+    assert!(Easing::BackOut.apply(0.8) > 1.0);
+  }
+}
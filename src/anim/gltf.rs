@@ -0,0 +1,198 @@
+use std::path::Path;
+
+use base64::Engine;
+use gltf::buffer::Source;
+use gltf::mesh::Mode;
+use gltf::{Gltf, Node};
+use nalgebra::Matrix4;
+
+use crate::render::vertex::Vertex;
+
+use super::morph::{MorphMesh, MorphTarget};
+
+// 没有顶点颜色和材质时的颜色
+const DEFAULT_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+
+/// 读取 glTF 文件并导入变形网格，外部缓冲区文件相对于 `path` 所在的目录
+pub fn load_morph_meshes(path: impl AsRef<Path>) -> Result<Vec<MorphMesh>, String> {
+  let path = path.as_ref();
+  let data = std::fs::read(path).map_err(|e| format!("读取 {} 失败：{}", path.display(), e))?;
+  import_morph_meshes(&data, path.parent())
+}
+
+/// 从 glTF（.gltf 或 .glb）导入带变形目标（mesh 的 targets）的网格，每个三角形图元对应一个 `MorphMesh`
+///
+/// - 有索引时按索引展开成三角形列表，位置、法线和偏移一起展开
+/// - `transform` 为节点在默认场景中的全局变换，初始权重取节点的 weights，没有时取网格的 weights
+/// - 变形目标名称取网格 extras 中的 `targetNames`（常见导出工具的约定），没有时为 `target_N`
+/// - 颜色取 COLOR_0，没有时取材质的 baseColorFactor；有法线时绘制时按法线计算明暗
+///
+/// 缓冲区可以是 GLB 的二进制块或 data URI；外部文件从 `base_dir` 读取，为 None 时返回错误。
+pub fn import_morph_meshes(data: &[u8], base_dir: Option<&Path>) -> Result<Vec<MorphMesh>, String> {
+  let gltf = Gltf::from_slice(data).map_err(|e| format!("解析 glTF 失败：{}", e))?;
+  let buffers = gltf.buffers().map(|buffer| match buffer.source() {
+    Source::Bin => gltf.blob.clone().ok_or_else(|| "glTF 缺少二进制块".to_string()),
+    Source::Uri(uri) => load_uri(uri, base_dir),
+  }).collect::<Result<Vec<_>, String>>()?;
+
+  let mut meshes = vec![];
+  let scene = gltf.default_scene().or_else(|| gltf.scenes().next()).ok_or_else(|| "glTF 中没有场景".to_string())?;
+  for node in scene.nodes() {
+    import_node(&node, Matrix4::identity(), &buffers, &mut meshes)?;
+  }
+  Ok(meshes)
+}
+
+fn load_uri(uri: &str, base_dir: Option<&Path>) -> Result<Vec<u8>, String> {
+  if let Some(rest) = uri.strip_prefix("data:") {
+    let (_, encoded) = rest.split_once(";base64,").ok_or_else(|| "只支持 base64 编码的 data URI".to_string())?;
+    return base64::engine::general_purpose::STANDARD.decode(encoded).map_err(|e| format!("data URI 解码失败：{}", e));
+  }
+  let dir = base_dir.ok_or_else(|| format!("不能读取外部缓冲区 {}，请使用 GLB 或内嵌数据", uri))?;
+  let path = dir.join(uri);
+  std::fs::read(&path).map_err(|e| format!("读取 {} 失败：{}", path.display(), e))
+}
+
+fn import_node(node: &Node, parent: Matrix4<f32>, buffers: &[Vec<u8>], meshes: &mut Vec<MorphMesh>) -> Result<(), String> {
+  let transform = parent * Matrix4::from(node.transform().matrix());
+  if let Some(mesh) = node.mesh() {
+    let name = node.name().or(mesh.name()).map(str::to_string).unwrap_or_else(|| format!("mesh_{}", mesh.index()));
+    let target_names = mesh.extras().as_ref()
+      .and_then(|extras| serde_json::from_str::<serde_json::Value>(extras.get()).ok())
+      .and_then(|extras| serde_json::from_value::<Vec<String>>(extras["targetNames"].clone()).ok())
+      .unwrap_or_default();
+    let weights = node.weights().or(mesh.weights()).unwrap_or_default();
+    let primitives = mesh.primitives().filter(|p| p.mode() == Mode::Triangles).collect::<Vec<_>>();
+    for (i, primitive) in primitives.iter().enumerate() {
+      let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+      let positions: Vec<[f32; 3]> = reader.read_positions().ok_or_else(|| format!("网格 {} 没有顶点位置", name))?.collect();
+      let normals: Vec<[f32; 3]> = reader.read_normals().map(|n| n.collect()).unwrap_or_default();
+      let colors: Vec<[f32; 4]> = reader.read_colors(0).map(|c| c.into_rgba_f32().collect()).unwrap_or_default();
+      let indices: Vec<usize> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+        None => (0..positions.len()).collect(),
+      };
+      if let Some(i) = indices.iter().find(|&&i| i >= positions.len()) {
+        return Err(format!("网格 {} 的索引 {} 超出顶点数 {}", name, i, positions.len()));
+      }
+      let expand = |values: &[[f32; 3]]| -> Vec<[f32; 3]> {
+        if values.len() == positions.len() { indices.iter().map(|&i| values[i]).collect() } else { vec![] }
+      };
+
+      let base_color = primitive.material().pbr_metallic_roughness().base_color_factor();
+      let vertices = indices.iter().map(|&i| Vertex {
+        position: positions[i],
+        color: colors.get(i).copied().map(|c| [c[0] * base_color[0], c[1] * base_color[1], c[2] * base_color[2], c[3] * base_color[3]])
+          .unwrap_or(if primitive.material().index().is_some() { base_color } else { DEFAULT_COLOR }),
+        tex_coords: [0.0, 0.0],
+      }).collect();
+      let mesh_name = if primitives.len() > 1 { format!("{}_{}", name, i) } else { name.clone() };
+      let mut morph = MorphMesh::new(&mesh_name, vertices);
+      morph.transform = transform;
+      morph.set_normals(expand(&normals))?;
+      for (t, (target_positions, target_normals, _)) in reader.read_morph_targets().enumerate() {
+        let zeros = || vec![[0.0; 3]; indices.len()];
+        let position_deltas = target_positions.map(|p| expand(&p.collect::<Vec<_>>())).unwrap_or_else(zeros);
+        let normal_deltas = target_normals.map(|n| expand(&n.collect::<Vec<_>>())).unwrap_or_default();
+        let target_name = target_names.get(t).cloned().unwrap_or_else(|| format!("target_{}", t));
+        morph.add_target(MorphTarget { name: target_name, position_deltas, normal_deltas })?;
+      }
+      for (w, weight) in morph.weights.iter_mut().zip(weights.iter()) {
+        *w = *weight;
+      }
+      meshes.push(morph);
+    }
+  }
+  for child in node.children() {
+    import_node(&child, transform, buffers, meshes)?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn floats(values: &[[f32; 3]]) -> Vec<u8> {
+    values.iter().flatten().flat_map(|v| v.to_le_bytes()).collect()
+  }
+
+  // 一个三角形：法线朝 +z，变形目标 "raise" 把第三个顶点抬高 2、法线转向 +y
+  fn triangle_gltf() -> Vec<u8> {
+    let positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+    let mut data = floats(&positions);
+    data.extend(floats(&[[0.0, 0.0, 1.0]; 3]));
+    data.extend(floats(&[[0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 2.0, 0.0]]));
+    data.extend(floats(&[[0.0, 1.0, -1.0]; 3]));
+    data.extend([2u16, 1, 0].iter().flat_map(|i| i.to_le_bytes()));
+    let uri = format!("data:application/octet-stream;base64,{}", base64::engine::general_purpose::STANDARD.encode(&data));
+    let vec3 = |offset: usize| serde_json::json!({ "bufferView": 0, "byteOffset": offset, "componentType": 5126, "count": 3, "type": "VEC3" });
+    let mut position = vec3(0);
+    position["min"] = serde_json::json!([0.0, 0.0, 0.0]);
+    position["max"] = serde_json::json!([1.0, 1.0, 0.0]);
+    let mut target_position = vec3(72);
+    target_position["min"] = serde_json::json!([0.0, 0.0, 0.0]);
+    target_position["max"] = serde_json::json!([0.0, 2.0, 0.0]);
+    serde_json::to_vec(&serde_json::json!({
+      "asset": { "version": "2.0" },
+      "buffers": [{ "uri": uri, "byteLength": data.len() }],
+      "bufferViews": [{ "buffer": 0, "byteLength": 144 }, { "buffer": 0, "byteOffset": 144, "byteLength": 6 }],
+      "accessors": [position, vec3(36), target_position, vec3(108),
+        { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }],
+      "meshes": [{
+        "name": "triangle",
+        "primitives": [{
+          "attributes": { "POSITION": 0, "NORMAL": 1 },
+          "indices": 4,
+          "targets": [{ "POSITION": 2, "NORMAL": 3 }],
+        }],
+        "weights": [0.25],
+        "extras": { "targetNames": ["raise"] },
+      }],
+      "nodes": [{ "mesh": 0, "translation": [10.0, 0.0, 0.0] }],
+      "scenes": [{ "nodes": [0] }],
+      "scene": 0,
+    })).unwrap()
+  }
+
+  #[test]
+  fn imports_targets_weights_and_transform() {
+    let meshes = import_morph_meshes(&triangle_gltf(), None).unwrap();
+    assert_eq!(meshes.len(), 1);
+    let mesh = &meshes[0];
+    assert_eq!(mesh.name, "triangle");
+    assert_eq!(mesh.targets.len(), 1);
+    assert_eq!(mesh.find_target("raise"), Some(0));
+    assert_eq!(mesh.weights, vec![0.25]);
+    // 按索引 2、1、0 展开
+    assert_eq!(mesh.vertices.iter().map(|v| v.position).collect::<Vec<_>>(), vec![[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 0.0]]);
+    assert_eq!(mesh.targets[0].position_deltas[0], [0.0, 2.0, 0.0]);
+    assert_eq!(mesh.normals.len(), 3);
+    assert_eq!(mesh.targets[0].normal_deltas.len(), 3);
+    assert_eq!(mesh.transform.transform_point(&[0.0, 0.0, 0.0].into()), [10.0, 0.0, 0.0].into());
+  }
+
+  #[test]
+  fn morphs_positions_and_normals() {
+    let mut mesh = import_morph_meshes(&triangle_gltf(), None).unwrap().remove(0);
+    mesh.set_weight("raise", 1.0);
+    assert_eq!(mesh.morphed_vertices()[0].position, [10.0, 3.0, 0.0]);
+    // 法线 (0, 0, 1) + (0, 1, -1) = (0, 1, 0)
+    assert_eq!(mesh.morphed_normals()[0], [0.0, 1.0, 0.0]);
+    let shade = crate::scene::model::mesh_shade(&nalgebra::Vector3::y());
+    let color = mesh.morphed_vertices()[0].color;
+    assert!((color[0] - DEFAULT_COLOR[0] * shade).abs() < 1e-6);
+    assert_eq!(color[3], 1.0);
+  }
+
+  #[test]
+  fn external_buffers_need_a_directory() {
+    let gltf = serde_json::json!({
+      "asset": { "version": "2.0" },
+      "buffers": [{ "uri": "missing.bin", "byteLength": 4 }],
+      "scenes": [{ "nodes": [] }],
+    });
+    let err = import_morph_meshes(&serde_json::to_vec(&gltf).unwrap(), None).unwrap_err();
+    assert!(err.contains("missing.bin"));
+  }
+}
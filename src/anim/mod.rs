@@ -0,0 +1,7 @@
+pub mod easing;
+pub mod track;
+pub mod clip;
+pub mod player;
+pub mod skeleton;
+pub mod morph;
+pub mod gltf;
@@ -0,0 +1,127 @@
+use nalgebra::{Matrix4, Vector3};
+
+use crate::render::vertex::Vertex;
+use crate::scene::model::mesh_shade;
+
+use super::clip::AnimatedValue;
+
+/// 着色器中权重数组的长度，超出的变形目标只能在 CPU 上应用
+pub const MAX_MORPH_TARGETS: usize = 32;
+
+/// 变形目标（blend shape），保存每个顶点相对基础网格的偏移（对应 glTF mesh 的 targets）
+#[derive(Clone, Debug, Default)]
+pub struct MorphTarget {
+  pub name: String,
+  pub position_deltas: Vec<[f32; 3]>, // 长度与顶点数相同
+  pub normal_deltas: Vec<[f32; 3]>, // 可以为空
+}
+
+/// 带变形目标的网格，顶点在模型空间，`transform` 把模型空间变换到世界坐标
+///
+/// 有法线时顶点颜色是没有明暗的基础颜色，绘制时按变形后的法线计算明暗；没有法线时直接使用顶点颜色。
+#[derive(Clone, Debug)]
+pub struct MorphMesh {
+  pub name: String,
+  pub vertices: Vec<Vertex>,
+  pub normals: Vec<[f32; 3]>, // 基础网格的法线，为空或与顶点数相同
+  pub targets: Vec<MorphTarget>,
+  pub weights: Vec<f32>, // 与 `targets` 一一对应
+  pub transform: Matrix4<f32>,
+}
+
+impl MorphMesh {
+  pub fn new(name: &str, vertices: Vec<Vertex>) -> Self {
+    Self {
+      name: name.to_string(),
+      vertices,
+      normals: vec![],
+      targets: vec![],
+      weights: vec![],
+      transform: Matrix4::identity(),
+    }
+  }
+
+  /// 设置基础网格的法线，数量与顶点数不同时返回错误
+  pub fn set_normals(&mut self, normals: Vec<[f32; 3]>) -> Result<(), String> {
+    if !normals.is_empty() && normals.len() != self.vertices.len() {
+      return Err(format!("网格 {} 有 {} 条法线，{} 个顶点", self.name, normals.len(), self.vertices.len()));
+    }
+    self.normals = normals;
+    Ok(())
+  }
+
+  /// 添加变形目标，偏移数量与顶点数不同时返回错误
+  pub fn add_target(&mut self, target: MorphTarget) -> Result<usize, String> {
+    if target.position_deltas.len() != self.vertices.len() {
+      return Err(format!("变形目标 {} 有 {} 个偏移，网格有 {} 个顶点", target.name, target.position_deltas.len(), self.vertices.len()));
+    }
+    if !target.normal_deltas.is_empty() && target.normal_deltas.len() != self.vertices.len() {
+      return Err(format!("变形目标 {} 的法线偏移数量与顶点数不同", target.name));
+    }
+    self.targets.push(target);
+    self.weights.push(0.0);
+    Ok(self.targets.len() - 1)
+  }
+
+  pub fn find_target(&self, name: &str) -> Option<usize> {
+    self.targets.iter().position(|t| t.name == name)
+  }
+
+  /// 按名称设置权重，找不到变形目标时返回 false
+  pub fn set_weight(&mut self, name: &str, weight: f32) -> bool {
+    let Some(i) = self.find_target(name) else {
+      return false
+    };
+    self.weights[i] = weight;
+    true
+  }
+
+  /// 应用动画采样值，属性名为变形目标名称，或者 `weight.N` 表示第 N 个权重
+  pub fn apply_value(&mut self, value: &AnimatedValue) -> bool {
+    let AnimatedValue::Float(property, weight) = value else {
+      return false
+    };
+    if let Some(i) = property.strip_prefix("weight.").and_then(|i| i.parse::<usize>().ok()) {
+      if let Some(w) = self.weights.get_mut(i) {
+        *w = *weight;
+        return true;
+      }
+    }
+    self.set_weight(property, *weight)
+  }
+
+  /// CPU 变形：按权重叠加偏移并变换到世界坐标；有法线时按变形后的法线计算明暗，与 morph.wgsl 一致
+  pub fn morphed_vertices(&self) -> Vec<Vertex> {
+    let normals = self.morphed_normals();
+    self.vertices.iter().enumerate().map(|(i, v)| {
+      let mut p = Vector3::from(v.position);
+      for (target, weight) in self.targets.iter().zip(self.weights.iter()) {
+        if *weight != 0.0 {
+          p += Vector3::from(target.position_deltas[i]) * *weight;
+        }
+      }
+      let p = self.transform.transform_point(&p.into());
+      let mut color = v.color;
+      if let Some(n) = normals.get(i) {
+        let n = self.transform.transform_vector(&Vector3::from(*n));
+        let shade = mesh_shade(&n.try_normalize(f32::EPSILON).unwrap_or(n));
+        color = [color[0] * shade, color[1] * shade, color[2] * shade, color[3]];
+      }
+      Vertex { position: [p.x, p.y, p.z], color, ..*v }
+    }).collect()
+  }
+
+  /// CPU 变形后的法线（模型空间、已归一化），没有法线时返回空列表
+  pub fn morphed_normals(&self) -> Vec<[f32; 3]> {
+    self.normals.iter().enumerate().map(|(i, n)| {
+      let mut n = Vector3::from(*n);
+      for (target, weight) in self.targets.iter().zip(self.weights.iter()) {
+        if let Some(d) = target.normal_deltas.get(i) {
+          n += Vector3::from(*d) * *weight;
+        }
+      }
+      let n = n.try_normalize(f32::EPSILON).unwrap_or(n);
+      [n.x, n.y, n.z]
+    }).collect()
+  }
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+
+use crate::scene::model::Scene;
+
+use super::clip::AnimationClip;
+
+/// 播放到结尾后的行为
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+  #[default]
+  Once, // 停在最后一帧
+  Loop, // 回到开头
+  PingPong, // 来回播放
+}
+
+/// 动画播放器，按每帧的 `dt` 推进时间
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+  pub clip: AnimationClip,
+  pub loop_mode: LoopMode,
+  pub speed: f32, // 播放速度倍数，负数倒放
+  pub playing: bool,
+  time: f32, // 累计播放时间（秒），未按时长折返
+}
+
+impl AnimationPlayer {
+  pub fn new(clip: AnimationClip, loop_mode: LoopMode) -> Self {
+    Self { clip, loop_mode, speed: 1.0, playing: true, time: 0.0 }
+  }
+
+  /// 推进播放时间，`Once` 模式正放到结尾或倒放到开头时自动停止
+  pub fn advance(&mut self, dt: f32) {
+    if !self.playing {
+      return
+    }
+    let rate = dt * self.speed;
+    self.time += rate;
+    if self.loop_mode == LoopMode::Once {
+      let duration = self.clip.duration();
+      // 只检查播放方向上的终点，dt 为 0 或刚开始正放时停在开头不算结束
+      if (rate > 0.0 && self.time >= duration) || (rate < 0.0 && self.time <= 0.0) {
+        self.time = self.time.clamp(0.0, duration);
+        self.playing = false;
+      }
+    }
+  }
+
+  /// 跳到指定时间
+  pub fn seek(&mut self, time: f32) {
+    self.time = time;
+  }
+
+  /// 回到开头并开始播放
+  pub fn restart(&mut self) {
+    self.time = 0.0;
+    self.playing = true;
+  }
+
+  /// 按循环模式折算到片段内的时间
+  pub fn local_time(&self) -> f32 {
+    let duration = self.clip.duration();
+    if duration <= 0.0 {
+      return 0.0;
+    }
+    match self.loop_mode {
+      LoopMode::Once => self.time.clamp(0.0, duration),
+      LoopMode::Loop => self.time.rem_euclid(duration),
+      LoopMode::PingPong => {
+        let t = self.time.rem_euclid(duration * 2.0);
+        if t > duration { duration * 2.0 - t } else { t }
+      },
+    }
+  }
+
+  /// 把当前时间的动画值写入场景
+  pub fn apply(&self, scene: &mut Scene) {
+    self.clip.apply(self.local_time(), scene);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::anim::clip::{Channel, Target};
+  use crate::anim::track::{Interpolation, Keyframe, Track};
+
+  // 时长 2 秒的片段
+  fn player(loop_mode: LoopMode) -> AnimationPlayer {
+    let track = Track::new(vec![Keyframe::new(0.0, 0.0), Keyframe::new(2.0, 1.0)], Interpolation::Linear);
+    let clip = AnimationClip::new("test").track(Target::Element { id: 1 }, Channel::Float { property: "scale".to_string(), track });
+    AnimationPlayer::new(clip, loop_mode)
+  }
+
+  #[test]
+  fn once_does_not_stop_at_start() {
+    let mut p = player(LoopMode::Once);
+    p.advance(0.0);
+    assert!(p.playing);
+    p.advance(0.5);
+    assert!(p.playing);
+    assert_eq!(p.local_time(), 0.5);
+  }
+
+  #[test]
+  fn once_stops_at_the_end_in_the_playing_direction() {
+    let mut p = player(LoopMode::Once);
+    p.advance(1.5);
+    assert!(p.playing);
+    p.advance(1.0);
+    assert!(!p.playing);
+    assert_eq!(p.local_time(), 2.0);
+
+    // 从结尾倒放到开头后停止
+    p.seek(2.0);
+    p.playing = true;
+    p.speed = -2.0;
+    p.advance(0.0);
+    assert!(p.playing);
+    p.advance(0.5);
+    assert!(p.playing);
+    assert_eq!(p.local_time(), 1.0);
+    p.advance(0.75);
+    assert!(!p.playing);
+    assert_eq!(p.local_time(), 0.0);
+
+    // 在开头倒放立即停止
+    p.restart();
+    p.advance(0.1);
+    assert!(!p.playing);
+    assert_eq!(p.local_time(), 0.0);
+  }
+
+  #[test]
+  fn loop_and_ping_pong_wrap_at_the_boundaries() {
+    let mut p = player(LoopMode::Loop);
+    p.advance(2.0);
+    assert!(p.playing);
+    assert_eq!(p.local_time(), 0.0);
+    p.advance(2.5);
+    assert_eq!(p.local_time(), 0.5);
+    p.seek(-0.5);
+    assert_eq!(p.local_time(), 1.5);
+
+    let mut p = player(LoopMode::PingPong);
+    p.advance(2.0);
+    assert_eq!(p.local_time(), 2.0);
+    p.advance(0.5);
+    assert_eq!(p.local_time(), 1.5);
+    p.advance(1.5);
+    assert!(p.playing);
+    assert_eq!(p.local_time(), 0.0);
+    p.advance(0.25);
+    assert_eq!(p.local_time(), 0.25);
+  }
+
+  #[test]
+  fn apply_writes_the_sampled_value() {
+    let mut scene = Scene::new("test");
+    scene.elements.push(crate::scene::model::Element {
+      id: 1,
+      name: String::new(),
+      shape: crate::scene::model::Shape::Cube { size: [1.0; 3] },
+      transform: Default::default(),
+      material: String::new(),
+    });
+    let mut p = player(LoopMode::Once);
+    p.advance(1.0);
+    p.apply(&mut scene);
+    assert_eq!(scene.elements[0].transform.scale, [0.5; 3]);
+  }
+}
@@ -0,0 +1,259 @@
+use nalgebra::{Matrix4, UnitQuaternion, Vector3};
+
+use crate::render::vertex::{SkinnedVertex, Vertex};
+
+use super::track::Track;
+
+/// 着色器中关节矩阵数组的长度，超出的关节不参与蒙皮
+pub const MAX_JOINTS: usize = 64;
+
+/// 关节相对父关节的变换
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointTransform {
+  pub translation: Vector3<f32>,
+  pub rotation: UnitQuaternion<f32>,
+  pub scale: Vector3<f32>,
+}
+
+impl Default for JointTransform {
+  fn default() -> Self {
+    Self {
+      translation: Vector3::zeros(),
+      rotation: UnitQuaternion::identity(),
+      scale: Vector3::repeat(1.0),
+    }
+  }
+}
+
+impl JointTransform {
+  /// 平移 * 旋转 * 缩放
+  pub fn matrix(&self) -> Matrix4<f32> {
+    Matrix4::new_translation(&self.translation)
+      * self.rotation.to_homogeneous()
+      * Matrix4::new_nonuniform_scaling(&self.scale)
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct Joint {
+  pub name: String,
+  pub parent: Option<usize>, // 父关节下标，必须小于自身下标
+  pub rest: JointTransform, // 绑定姿势下相对父关节的变换
+  pub inverse_bind: Matrix4<f32>, // 逆绑定矩阵：模型空间 -> 关节空间
+}
+
+/// 骨架，关节按父关节在前的顺序排列（与 glTF skin 的 joints 一致）
+#[derive(Clone, Debug, Default)]
+pub struct Skeleton {
+  pub joints: Vec<Joint>,
+}
+
+/// 所有关节的局部变换，下标与 `Skeleton::joints` 对应
+pub type Pose = Vec<JointTransform>;
+
+impl Skeleton {
+  /// 添加关节，逆绑定矩阵按绑定姿势计算，返回关节下标
+  pub fn add_joint(&mut self, name: &str, parent: Option<usize>, rest: JointTransform) -> usize {
+    let parent_world = parent.map(|p| self.rest_world(p)).unwrap_or_else(Matrix4::identity);
+    let world = parent_world * rest.matrix();
+    let inverse_bind = world.try_inverse().unwrap_or_else(Matrix4::identity);
+    self.joints.push(Joint { name: name.to_string(), parent, rest, inverse_bind });
+    self.joints.len() - 1
+  }
+
+  // 绑定姿势下关节的模型空间矩阵
+  fn rest_world(&self, index: usize) -> Matrix4<f32> {
+    let joint = &self.joints[index];
+    let parent = joint.parent.map(|p| self.rest_world(p)).unwrap_or_else(Matrix4::identity);
+    parent * joint.rest.matrix()
+  }
+
+  pub fn find(&self, name: &str) -> Option<usize> {
+    self.joints.iter().position(|j| j.name == name)
+  }
+
+  /// 绑定姿势
+  pub fn rest_pose(&self) -> Pose {
+    self.joints.iter().map(|j| j.rest).collect()
+  }
+
+  /// 各关节在模型空间的矩阵
+  pub fn world_matrices(&self, pose: &Pose) -> Vec<Matrix4<f32>> {
+    let mut world: Vec<Matrix4<f32>> = Vec::with_capacity(self.joints.len());
+    for (i, joint) in self.joints.iter().enumerate() {
+      let local = pose.get(i).unwrap_or(&joint.rest).matrix();
+      let m = match joint.parent {
+        Some(p) if p < i => world[p] * local,
+        _ => local,
+      };
+      world.push(m);
+    }
+    world
+  }
+
+  /// 蒙皮矩阵：模型空间矩阵 * 逆绑定矩阵，绑定姿势下都是单位矩阵
+  pub fn joint_matrices(&self, pose: &Pose) -> Vec<Matrix4<f32>> {
+    self.world_matrices(pose).iter()
+      .zip(self.joints.iter())
+      .map(|(world, joint)| world * joint.inverse_bind)
+      .collect()
+  }
+}
+
+/// 单个关节的动画通道，缺少的分量使用绑定姿势
+#[derive(Clone, Debug, Default)]
+pub struct JointChannel {
+  pub joint: usize,
+  pub translation: Option<Track<[f32; 3]>>,
+  pub rotation: Option<Track<[f32; 4]>>, // 四元数 (x, y, z, w)
+  pub scale: Option<Track<[f32; 3]>>,
+}
+
+/// 骨骼动画片段
+#[derive(Clone, Debug, Default)]
+pub struct SkeletalClip {
+  pub name: String,
+  pub channels: Vec<JointChannel>,
+}
+
+impl SkeletalClip {
+  pub fn duration(&self) -> f32 {
+    self.channels.iter()
+      .flat_map(|c| [
+        c.translation.as_ref().map(|t| t.duration()),
+        c.rotation.as_ref().map(|t| t.duration()),
+        c.scale.as_ref().map(|t| t.duration()),
+      ])
+      .flatten()
+      .fold(0.0, f32::max)
+  }
+
+  /// 在 `time` 处采样出骨架的姿势
+  pub fn sample(&self, skeleton: &Skeleton, time: f32) -> Pose {
+    let mut pose = skeleton.rest_pose();
+    for channel in self.channels.iter() {
+      let Some(joint) = pose.get_mut(channel.joint) else {
+        continue
+      };
+      if let Some(v) = channel.translation.as_ref().and_then(|t| t.sample(time)) {
+        joint.translation = Vector3::from(v);
+      }
+      if let Some(q) = channel.rotation.as_ref().and_then(|t| t.sample_quat(time)) {
+        joint.rotation = q;
+      }
+      if let Some(v) = channel.scale.as_ref().and_then(|t| t.sample(time)) {
+        joint.scale = Vector3::from(v);
+      }
+    }
+    pose
+  }
+}
+
+/// 蒙皮网格：模型空间的顶点和骨架，`transform` 把模型空间变换到世界坐标
+#[derive(Clone, Debug)]
+pub struct SkinnedMesh {
+  pub vertices: Vec<SkinnedVertex>,
+  pub skeleton: Skeleton,
+  pub transform: Matrix4<f32>,
+}
+
+impl SkinnedMesh {
+  /// 最终传给着色器的矩阵：`transform` * 蒙皮矩阵
+  pub fn joint_matrices(&self, pose: &Pose) -> Vec<Matrix4<f32>> {
+    self.skeleton.joint_matrices(pose).iter().map(|m| self.transform * m).collect()
+  }
+}
+
+/// CPU 蒙皮，结果与着色器中的计算相同；不支持 GPU 蒙皮时也可以直接绘制结果
+pub fn skin_vertices(vertices: &[SkinnedVertex], joint_matrices: &[Matrix4<f32>]) -> Vec<Vertex> {
+  vertices.iter().map(|v| {
+    let p = Vector3::from(v.position).push(1.0);
+    let mut skinned = nalgebra::Vector4::zeros();
+    for (joint, weight) in v.joints.iter().zip(v.weights.iter()) {
+      if *weight == 0.0 {
+        continue
+      }
+      if let Some(m) = joint_matrices.get(*joint as usize).filter(|_| (*joint as usize) < MAX_JOINTS) {
+        skinned += (m * p) * *weight;
+      }
+    }
+    Vertex {
+      position: [skinned.x, skinned.y, skinned.z],
+      color: v.color,
+      tex_coords: v.tex_coords,
+    }
+  }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::FRAC_PI_2;
+
+  use super::*;
+
+  // 两节关节：根在原点，子关节在 (0, 10, 0)
+  fn arm() -> Skeleton {
+    let mut skeleton = Skeleton::default();
+    let root = skeleton.add_joint("root", None, JointTransform::default());
+    skeleton.add_joint("elbow", Some(root), JointTransform { translation: Vector3::new(0.0, 10.0, 0.0), ..Default::default() });
+    skeleton
+  }
+
+  fn vertex(position: [f32; 3], joints: [u32; 4], weights: [f32; 4]) -> SkinnedVertex {
+    SkinnedVertex { position, color: [1.0; 4], tex_coords: [0.0; 2], joints, weights }
+  }
+
+  fn assert_near(actual: [f32; 3], expected: [f32; 3]) {
+    for (a, e) in actual.iter().zip(expected.iter()) {
+      assert!((a - e).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+  }
+
+  // 子关节绕 z 轴转 90 度
+  fn bent_pose(skeleton: &Skeleton) -> Pose {
+    let mut pose = skeleton.rest_pose();
+    pose[1].rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), FRAC_PI_2);
+    pose
+  }
+
+  #[test]
+  fn rest_pose_keeps_vertices() {
+    let skeleton = arm();
+    for m in skeleton.joint_matrices(&skeleton.rest_pose()) {
+      assert!((m - Matrix4::identity()).abs().max() < 1e-6);
+    }
+    let vertices = [vertex([0.0, 15.0, 0.0], [1, 0, 0, 0], [1.0, 0.0, 0.0, 0.0]), vertex([3.0, 2.0, 1.0], [0, 1, 0, 0], [0.5, 0.5, 0.0, 0.0])];
+    let skinned = skin_vertices(&vertices, &skeleton.joint_matrices(&skeleton.rest_pose()));
+    assert_near(skinned[0].position, [0.0, 15.0, 0.0]);
+    assert_near(skinned[1].position, [3.0, 2.0, 1.0]);
+  }
+
+  #[test]
+  fn single_joint_follows_its_transform() {
+    let skeleton = arm();
+    // 子关节空间中 (0, 5, 0)，转 90 度后为 (-5, 0, 0)，再加上关节位置 (0, 10, 0)
+    let skinned = skin_vertices(&[vertex([0.0, 15.0, 0.0], [1, 0, 0, 0], [1.0, 0.0, 0.0, 0.0])], &skeleton.joint_matrices(&bent_pose(&skeleton)));
+    assert_near(skinned[0].position, [-5.0, 10.0, 0.0]);
+  }
+
+  #[test]
+  fn weights_blend_joint_results() {
+    let skeleton = arm();
+    // 根关节不动得到 (0, 15, 0)，子关节得到 (-5, 10, 0)，各占一半
+    let skinned = skin_vertices(&[vertex([0.0, 15.0, 0.0], [0, 1, 0, 0], [0.5, 0.5, 0.0, 0.0])], &skeleton.joint_matrices(&bent_pose(&skeleton)));
+    assert_near(skinned[0].position, [-2.5, 12.5, 0.0]);
+  }
+
+  #[test]
+  fn mesh_transform_and_unknown_joints() {
+    let skeleton = arm();
+    let mesh = SkinnedMesh {
+      vertices: vec![vertex([0.0, 15.0, 0.0], [1, MAX_JOINTS as u32, 0, 0], [1.0, 1.0, 0.0, 0.0])],
+      skeleton,
+      transform: Matrix4::new_translation(&Vector3::new(100.0, 0.0, 0.0)),
+    };
+    // 超出范围的关节不参与蒙皮
+    let skinned = skin_vertices(&mesh.vertices, &mesh.joint_matrices(&bent_pose(&mesh.skeleton)));
+    assert_near(skinned[0].position, [95.0, 10.0, 0.0]);
+  }
+}
@@ -0,0 +1,242 @@
+use nalgebra::{Quaternion, UnitQuaternion};
+use serde::{Deserialize, Serialize};
+
+use super::easing::Easing;
+
+/// 关键帧之间的插值方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+  Step, // 保持前一个关键帧的值
+  #[default]
+  Linear,
+  Cubic, // Catmull-Rom 样条，经过所有关键帧
+  Slerp, // 球面插值，用于欧拉角旋转，其他值按线性插值处理
+}
+
+/// 可以插值的值
+pub trait Interpolate: Copy {
+  fn lerp(a: Self, b: Self, t: f32) -> Self;
+
+  /// Catmull-Rom 插值，结果在 `p1`、`p2` 之间
+  fn cubic(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+  fn lerp(a: Self, b: Self, t: f32) -> Self {
+    a + (b - a) * t
+  }
+
+  fn cubic(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+  }
+}
+
+impl Interpolate for [f32; 3] {
+  fn lerp(a: Self, b: Self, t: f32) -> Self {
+    [0, 1, 2].map(|i| f32::lerp(a[i], b[i], t))
+  }
+
+  fn cubic(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self {
+    [0, 1, 2].map(|i| f32::cubic(p0[i], p1[i], p2[i], p3[i], t))
+  }
+}
+
+impl Interpolate for [f32; 4] {
+  fn lerp(a: Self, b: Self, t: f32) -> Self {
+    [0, 1, 2, 3].map(|i| f32::lerp(a[i], b[i], t))
+  }
+
+  fn cubic(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self {
+    [0, 1, 2, 3].map(|i| f32::cubic(p0[i], p1[i], p2[i], p3[i], t))
+  }
+}
+
+/// 关键帧，`easing` 作用于从该帧到下一帧的区间
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe<T> {
+  pub time: f32, // 秒
+  pub value: T,
+  #[serde(default)]
+  pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+  pub fn new(time: f32, value: T) -> Self {
+    Self { time, value, easing: Easing::Linear }
+  }
+
+  pub fn eased(time: f32, value: T, easing: Easing) -> Self {
+    Self { time, value, easing }
+  }
+}
+
+/// 一组按时间排序的关键帧
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Track<T> {
+  pub keys: Vec<Keyframe<T>>,
+  #[serde(default)]
+  pub interpolation: Interpolation,
+}
+
+impl<T: Interpolate> Track<T> {
+  /// 关键帧会按时间排序
+  pub fn new(mut keys: Vec<Keyframe<T>>, interpolation: Interpolation) -> Self {
+    keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Self { keys, interpolation }
+  }
+
+  /// 最后一个关键帧的时间
+  pub fn duration(&self) -> f32 {
+    self.keys.last().map(|k| k.time).unwrap_or(0.0)
+  }
+
+  /// 找到 `time` 所在的区间，返回 (区间起点下标, 缓动后的区间进度)
+  ///
+  /// 早于第一帧或晚于最后一帧时返回端点，进度为 0。
+  fn segment(&self, time: f32) -> Option<(usize, f32)> {
+    let last = self.keys.len().checked_sub(1)?;
+    if time <= self.keys[0].time {
+      return Some((0, 0.0));
+    }
+    if time >= self.keys[last].time {
+      return Some((last, 0.0));
+    }
+    // keys[i].time <= time < keys[i + 1].time
+    let i = self.keys.partition_point(|k| k.time <= time) - 1;
+    let (a, b) = (&self.keys[i], &self.keys[i + 1]);
+    let span = b.time - a.time;
+    let t = if span > 0.0 { (time - a.time) / span } else { 0.0 };
+    Some((i, a.easing.apply(t)))
+  }
+
+  /// 在 `time` 处采样，没有关键帧时返回 None
+  pub fn sample(&self, time: f32) -> Option<T> {
+    let (i, t) = self.segment(time)?;
+    let value = self.keys[i].value;
+    if t == 0.0 || i + 1 >= self.keys.len() {
+      return Some(value);
+    }
+    let next = self.keys[i + 1].value;
+    Some(match self.interpolation {
+      Interpolation::Step => value,
+      Interpolation::Linear | Interpolation::Slerp => T::lerp(value, next, t),
+      Interpolation::Cubic => {
+        // 两端缺少的控制点用端点代替
+        let p0 = self.keys[i.saturating_sub(1)].value;
+        let p3 = self.keys.get(i + 2).map(|k| k.value).unwrap_or(next);
+        T::cubic(p0, value, next, p3, t)
+      },
+    })
+  }
+}
+
+impl Track<[f32; 3]> {
+  /// 对欧拉角（角度）旋转采样，`Slerp` 时转换为四元数做球面插值，其他插值方式与 `sample` 相同
+  pub fn sample_rotation(&self, time: f32) -> Option<[f32; 3]> {
+    if self.interpolation != Interpolation::Slerp {
+      return self.sample(time);
+    }
+    let (i, t) = self.segment(time)?;
+    let value = self.keys[i].value;
+    if t == 0.0 || i + 1 >= self.keys.len() {
+      return Some(value);
+    }
+    let a = euler_to_quat(value);
+    let b = euler_to_quat(self.keys[i + 1].value);
+    // 两个旋转相差 180° 时没有唯一的最短路径，退回线性插值
+    let q = a.try_slerp(&b, t, 1.0e-6).unwrap_or_else(|| a.nlerp(&b, t));
+    let (rx, ry, rz) = q.euler_angles();
+    Some([rx.to_degrees(), ry.to_degrees(), rz.to_degrees()])
+  }
+}
+
+impl Track<[f32; 4]> {
+  /// 对四元数旋转（x, y, z, w）采样，`Slerp` 和 `Linear` 都做球面插值，结果已归一化
+  pub fn sample_quat(&self, time: f32) -> Option<UnitQuaternion<f32>> {
+    let (i, t) = self.segment(time)?;
+    let a = quat(self.keys[i].value);
+    if t == 0.0 || i + 1 >= self.keys.len() {
+      return Some(a);
+    }
+    let b = quat(self.keys[i + 1].value);
+    Some(match self.interpolation {
+      Interpolation::Step => a,
+      Interpolation::Linear | Interpolation::Slerp => a.try_slerp(&b, t, 1.0e-6).unwrap_or_else(|| a.nlerp(&b, t)),
+      Interpolation::Cubic => quat(self.sample(time)?),
+    })
+  }
+}
+
+fn quat([x, y, z, w]: [f32; 4]) -> UnitQuaternion<f32> {
+  UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z))
+}
+
+// 与 `Transform::matrix` 使用相同的欧拉角顺序
+fn euler_to_quat(rotation: [f32; 3]) -> UnitQuaternion<f32> {
+  let [rx, ry, rz] = rotation;
+  UnitQuaternion::from_euler_angles(rx.to_radians(), ry.to_radians(), rz.to_radians())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+  }
+
+  fn track(interpolation: Interpolation) -> Track<f32> {
+    // 故意乱序，构造时按时间排序
+    Track::new(vec![Keyframe::new(2.0, 30.0), Keyframe::new(0.0, 10.0), Keyframe::new(1.0, 20.0), Keyframe::new(3.0, 0.0)], interpolation)
+  }
+
+  #[test]
+  fn linear_and_step() {
+    let linear = track(Interpolation::Linear);
+    assert_eq!(linear.duration(), 3.0);
+    assert_eq!(linear.sample(0.5), Some(15.0));
+    assert_eq!(linear.sample(1.0), Some(20.0));
+    assert_eq!(linear.sample(2.5), Some(15.0));
+    // 两端之外保持端点的值
+    assert_eq!(linear.sample(-1.0), Some(10.0));
+    assert_eq!(linear.sample(5.0), Some(0.0));
+
+    let step = track(Interpolation::Step);
+    assert_eq!(step.sample(0.99), Some(10.0));
+    assert_eq!(step.sample(1.0), Some(20.0));
+    assert_eq!(step.sample(2.5), Some(30.0));
+
+    assert_eq!(Track::<f32>::new(vec![], Interpolation::Linear).sample(0.0), None);
+  }
+
+  #[test]
+  fn cubic_passes_through_keys() {
+    let cubic = track(Interpolation::Cubic);
+    for (time, value) in [(0.0, 10.0), (1.0, 20.0), (2.0, 30.0), (3.0, 0.0)] {
+      assert!(close(cubic.sample(time).unwrap(), value));
+    }
+    // 手算：0.5 * (2 * 20 + (30 - 10) * 0.5 + (20 - 100 + 120 - 0) * 0.25 + (60 - 10 - 90 + 0) * 0.125) = 27.5
+    assert!(close(cubic.sample(1.5).unwrap(), 27.5));
+    // 关键帧在一条直线上时与线性插值相同
+    let line = Track::new((0..4).map(|i| Keyframe::new(i as f32, i as f32 * 10.0)).collect(), Interpolation::Cubic);
+    assert!(close(line.sample(1.25).unwrap(), 12.5));
+  }
+
+  #[test]
+  fn easing_applies_to_the_segment_after_the_key() {
+    let track = Track::new(vec![Keyframe::eased(0.0, 0.0, Easing::QuadIn), Keyframe::new(1.0, 1.0), Keyframe::new(2.0, 0.0)], Interpolation::Linear);
+    assert_eq!(track.sample(0.5), Some(0.25));
+    assert_eq!(track.sample(1.5), Some(0.5));
+  }
+
+  #[test]
+  fn slerp_rotation() {
+    let track = Track::new(vec![Keyframe::new(0.0, [0.0, 0.0, 0.0]), Keyframe::new(1.0, [0.0, 90.0, 0.0])], Interpolation::Slerp);
+    let [x, y, z] = track.sample_rotation(0.5).unwrap();
+    assert!(close(x, 0.0) && close(y, 45.0) && close(z, 0.0), "{:?}", [x, y, z]);
+    assert_eq!(track.sample_rotation(1.0), Some([0.0, 90.0, 0.0]));
+  }
+}
@@ -14,8 +14,24 @@ use crate::render::camera::CameraMove;
 use crate::render::draw::draw_ver;
 use crate::render::draw::update_camera;
 use crate::render::draw::update_vertex_buffer;
+use crate::render::post::{Bloom, Fxaa, Ssao, ToneMap};
+use crate::render::recorder::RecorderConfig;
+use crate::render::screenshot::ScreenshotRequest;
 use crate::render::wgpu_ctx::*;
-use crate::views::home::draw_home;
+use crate::constants::{FONT_PATH, PROFILE_DIR, RECORD_ENV, SCREENSHOT_SCALE, WIN_MAX_HEIGHT, WIN_MAX_WIDTH, WIN_MIN_HEIGHT, WIN_MIN_WIDTH};
+use crate::text::font::Font;
+use crate::text::layout::TextStyle;
+use crate::views::editor::EditorView;
+use crate::views::home::HomeView;
+use crate::views::registry::ViewRegistry;
+use crate::views::script::{ScriptView, SharedScript};
+use crate::views::viewer::ViewerView;
+#[cfg(target_arch = "wasm32")]
+use web_sys::HtmlCanvasElement;
+#[cfg(target_arch = "wasm32")]
+use winit::event_loop::EventLoopProxy;
+#[cfg(target_arch = "wasm32")]
+use winit::platform::web::WindowAttributesExtWebSys;
 
 // 添加 Default 以便App::default()来快速创建App实例
 #[derive(Default)]
@@ -25,22 +41,103 @@ pub struct App<'window> {
   // 生命唯一的一个窗口实例对象，确保不会多次创建窗口
   window: Option<Arc<Window>>,
   wgpu_ctx: Option<WgpuCtx<'window>>,
-  scene: String, // 场景
+  scene: String, // 场景，对应视图名称，修改后下一帧切换视图
   mouse_pos: (f64, f64),
   mouse_d_pos: (f64, f64),
-  last_time: Option<std::time::Instant>,
+  mouse_motion: (f64, f64), // 上一帧之后累计的鼠标移动，重绘时按帧间隔转动相机
+  last_time: Option<web_time::Instant>,
+  views: ViewRegistry, // 已注册的视图
+  occluded: bool, // 窗口被完全遮挡或页面被隐藏时不绘制
+  script: Option<SharedScript>, // 外部脚本驱动的场景，设置后注册 script 视图并默认显示
+  #[cfg(target_arch = "wasm32")]
+  canvas: Option<HtmlCanvasElement>, // 浏览器中绘制到的画布
+  #[cfg(target_arch = "wasm32")]
+  proxy: Option<EventLoopProxy<WgpuCtx<'static>>>, // 异步初始化完成后把上下文发回事件循环
 }
 
-impl<'window> ApplicationHandler for App<'window> {
+impl App<'static> {
+  /// 在浏览器中绘制到 `canvas`，异步创建的 wgpu 上下文通过 `proxy` 发回事件循环；显示 `script` 中由 JS 修改的场景
+  #[cfg(target_arch = "wasm32")]
+  pub fn with_canvas(canvas: HtmlCanvasElement, proxy: EventLoopProxy<WgpuCtx<'static>>, script: SharedScript) -> Self {
+    Self { canvas: Some(canvas), proxy: Some(proxy), script: Some(script), ..Default::default() }
+  }
+
+  /// 为窗口创建 wgpu 上下文，浏览器中异步创建，完成后通过 `user_event` 回到 `attach`
+  fn create_context(&mut self, window: Arc<Window>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    self.attach(WgpuCtx::new(window));
+    #[cfg(target_arch = "wasm32")]
+    if let Some(proxy) = self.proxy.clone() {
+      wasm_bindgen_futures::spawn_local(async move {
+        let wgpu_ctx = WgpuCtx::new_async(window).await;
+        if proxy.send_event(wgpu_ctx).is_err() {
+          web_sys::console::error_1(&"事件循环已关闭，无法启动渲染".into());
+        }
+      });
+    }
+  }
+
+  /// 设备丢失后释放整个上下文并重新创建；视图中可能保存了旧设备上的资源，全部重新注册，之后重新进入当前视图
+  fn recover_device(&mut self) {
+    let Some(window) = self.window.clone() else {
+      return
+    };
+    println!("重新创建 GPU 设备");
+    self.wgpu_ctx = None;
+    self.views = ViewRegistry::new();
+    self.create_context(window);
+  }
+
+  /// wgpu 上下文创建完成后加载字体、注册视图
+  fn attach(&mut self, mut wgpu_ctx: WgpuCtx<'static>) {
+    if let Err(e) = wgpu_ctx.load_font(FONT_PATH) {
+      println!("字体加载失败，使用内置的 Unifont 位图字体: {}", e);
+      wgpu_ctx.use_font(Font::unifont());
+    }
+    // 异步创建期间窗口尺寸可能已经变化
+    if let Some(window) = self.window.as_ref() {
+      let size = window.inner_size();
+      wgpu_ctx.resize(size.width, size.height);
+    }
+    self.wgpu_ctx = Some(wgpu_ctx);
+    if self.views.is_empty() {
+      self.views.register(Box::new(HomeView::default()));
+      self.views.register(Box::new(EditorView::default()));
+      self.views.register(Box::new(ViewerView::default()));
+      if let Some(script) = self.script.as_ref() {
+        self.views.register(Box::new(ScriptView::new(script.clone())));
+      }
+    }
+    if self.scene.is_empty() {
+      self.scene = if self.script.is_some() { "script" } else { "home" }.to_string();
+    }
+  }
+}
+
+// 用户事件为浏览器中异步创建完成的 wgpu 上下文
+impl ApplicationHandler<WgpuCtx<'static>> for App<'static> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
       // 如果窗口未创建，则创建窗口
       if self.window.is_none() {
-        let win_attr = Window::default_attributes().with_title("kidar Engine").with_inner_size(PhysicalSize::new(1600, 900));
+        let win_attr = Window::default_attributes()
+          .with_title("kidar Engine")
+          .with_min_inner_size(PhysicalSize::new(WIN_MIN_WIDTH, WIN_MIN_HEIGHT))
+          .with_max_inner_size(PhysicalSize::new(WIN_MAX_WIDTH, WIN_MAX_HEIGHT));
+        #[cfg(not(target_arch = "wasm32"))]
+        let win_attr = win_attr.with_inner_size(PhysicalSize::new(1600, 900));
+        // 浏览器中画布尺寸由页面决定
+        #[cfg(target_arch = "wasm32")]
+        let win_attr = win_attr.with_canvas(self.canvas.clone());
         let window = Arc::new(event_loop.create_window(win_attr).expect("Failed to create window"));
-        // window.Some(wgpu_ctx);
-        let wgpu_ctx = WgpuCtx::new(window.clone());
-        self.wgpu_ctx = Some(wgpu_ctx);
-        self.window = Some(window);
+        self.window = Some(window.clone());
+        self.create_context(window);
+      }
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, wgpu_ctx: WgpuCtx<'static>) {
+      self.attach(wgpu_ctx);
+      if let Some(window) = self.window.as_ref() {
+        window.request_redraw();
       }
     }
 
@@ -49,15 +146,46 @@ impl<'window> ApplicationHandler for App<'window> {
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+      // 鼠标和键盘输入先交给当前视图（相机激活时除外），被视图消费的输入不再控制相机
+      let consumed = match &event {
+        WindowEvent::CursorMoved { .. } | WindowEvent::MouseInput { .. } | WindowEvent::KeyboardInput { .. } | WindowEvent::ModifiersChanged(_) => {
+          match self.wgpu_ctx.as_mut() {
+            Some(wgpu_ctx) if !wgpu_ctx.camera.is_active() => self.views.handle_input(wgpu_ctx, &event),
+            _ => false,
+          }
+        },
+        _ => false,
+      };
       match event {
-        WindowEvent::Resized(_new_size ) => {
-          // 处理窗口大小变化
+        WindowEvent::Resized(new_size) => {
+          // 处理窗口大小变化，最小化时尺寸为 0，暂停绘制
+          if let Some(wgpu_ctx) = self.wgpu_ctx.as_mut() {
+            wgpu_ctx.resize(new_size.width, new_size.height);
+          }
           if let Some(window) = self.window.as_ref() {
             window.request_redraw(); // 请求重绘
           }
         },
+        WindowEvent::Occluded(occluded) => {
+          self.occluded = occluded;
+          if !occluded {
+            if let Some(window) = self.window.as_ref() {
+              window.request_redraw();
+            }
+          }
+        },
         WindowEvent::RedrawRequested => {
-          let now = std::time::Instant::now();
+          if self.wgpu_ctx.as_ref().is_some_and(|ctx| ctx.is_device_lost()) {
+            self.recover_device();
+            return;
+          }
+          // 最小化或被遮挡时停止重绘，恢复时由 Resized / Occluded 重新请求；暂停的时间不计入帧间隔
+          if self.occluded || self.wgpu_ctx.as_ref().is_some_and(|ctx| ctx.minimized) {
+            self.last_time = None;
+            self.mouse_motion = (0.0, 0.0);
+            return;
+          }
+          let now = web_time::Instant::now();
           if self.last_time.is_none() {
             self.last_time = Some(now);
           }
@@ -65,14 +193,41 @@ impl<'window> ApplicationHandler for App<'window> {
           self.last_time = Some(now);
           // 处理窗口重绘
           if let Some(wgpu_ctx) = self.wgpu_ctx.as_mut() {
-            let size = self.window.as_ref().unwrap().inner_size();
-            self.mouse_pos = (size.width as f64/2.0, size.height as f64/2.0);
-            wgpu_ctx.camera.set_screen_size(size.width as f32, size.height as f32);
+            // 录制时按固定的模拟时间推进，画面与实际帧率无关
+            let delta_time = wgpu_ctx.recorder.frame_time().unwrap_or(delta_time);
+            wgpu_ctx.profiler.begin_frame();
+            wgpu_ctx.camera.look_rotate(std::mem::take(&mut self.mouse_motion), delta_time);
+            self.mouse_pos = (wgpu_ctx.vw as f64/2.0, wgpu_ctx.vh as f64/2.0);
 
-            let vertex_list = draw_home();
+            // scene 字段变化时切换视图，视图不存在则恢复为当前视图
+            if self.views.target_name() != Some(self.scene.as_str()) && !self.views.switch_to(&self.scene, wgpu_ctx) {
+              self.scene = self.views.target_name().unwrap_or_default().to_string();
+            }
+            wgpu_ctx.profiler.begin_scope("shaders");
+            wgpu_ctx.reload_shaders();
+            wgpu_ctx.profiler.end_scope();
+            wgpu_ctx.profiler.begin_scope("update");
+            self.views.update(wgpu_ctx, delta_time);
+            wgpu_ctx.profiler.end_scope();
+            wgpu_ctx.profiler.begin_scope("build");
+            let vertex_list = self.views.build_render_list(wgpu_ctx);
             update_vertex_buffer(wgpu_ctx, vertex_list);
             update_camera(wgpu_ctx, delta_time);
+            wgpu_ctx.profiler.end_scope();
+            if let Some(text_renderer) = wgpu_ctx.text_renderer.as_mut() {
+              let fps = if delta_time > 0.0 { 1.0 / delta_time } else { 0.0 };
+              text_renderer.queue_text(&format!("FPS: {:.0}", fps), 10.0, 10.0, &TextStyle::default(), [1.0, 1.0, 1.0, 1.0]);
+              // 着色器编译错误显示在 FPS 下方，修复后自动消失
+              for (i, error) in wgpu_ctx.shaders.errors().enumerate() {
+                text_renderer.queue_text(&error.to_string(), 10.0, 40.0 + i as f32 * 24.0, &TextStyle::default(), [1.0, 0.3, 0.3, 1.0]);
+              }
+              wgpu_ctx.profiler.queue_overlay(text_renderer, wgpu_ctx.vw as f32);
+            }
+            wgpu_ctx.profiler.begin_scope("draw");
             wgpu_ctx.draw();
+            wgpu_ctx.profiler.end_scope();
+            wgpu_ctx.debug_draw.end_frame(delta_time);
+            wgpu_ctx.profiler.end_frame(&wgpu_ctx.device, &wgpu_ctx.queue);
             // println!("RedrawRequested");
             // draw_ver(wgpu_ctx, vertex_list);
           }
@@ -102,6 +257,10 @@ impl<'window> ApplicationHandler for App<'window> {
         }
         WindowEvent::MouseInput { device_id, state, button } => {
           // TODO: 处理鼠标点击
+          // 点击在视图的界面上时不激活相机
+          if consumed {
+            return;
+          }
           if state == winit::event::ElementState::Pressed && button == winit::event::MouseButton::Left {
             println!("Mouse input {:#?}", self.mouse_d_pos);
             if let Some(wgpu_ctx) = self.wgpu_ctx.as_mut() {
@@ -114,6 +273,9 @@ impl<'window> ApplicationHandler for App<'window> {
         WindowEvent::KeyboardInput { device_id, event, is_synthetic } => {
           // TODO: 处理键盘输入, 按键w、a、s、d 控制相机移动
           // println!("Keyboard input {:#?}， is_synthetic {:#?}", event, is_synthetic);
+          if consumed {
+            return;
+          }
           if let Some(wgpu_ctx) = self.wgpu_ctx.as_mut() {
             match event.physical_key {
                 winit::keyboard::PhysicalKey::Code(KeyCode::KeyW) => {
@@ -134,6 +296,105 @@ impl<'window> ApplicationHandler for App<'window> {
                 winit::keyboard::PhysicalKey::Code(KeyCode::ShiftLeft) => {
                   wgpu_ctx.camera.is_down = event.state == winit::event::ElementState::Pressed;
                 },
+                // F1、F2、F3 切换视图
+                winit::keyboard::PhysicalKey::Code(KeyCode::F1) => {
+                  self.scene = "home".to_string();
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::F2) => {
+                  self.scene = "editor".to_string();
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::F3) => {
+                  self.scene = "viewer".to_string();
+                },
+                // F4 切换飞行和行走
+                winit::keyboard::PhysicalKey::Code(KeyCode::F4) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    wgpu_ctx.camera.toggle_mode();
+                  }
+                },
+                // 后处理开关使用数字键，F5、F6 是首页的加载和保存场景：1 切换色调映射，2 开关泛光，3 开关 FXAA，4 开关 SSAO，5 切换半透明绘制方式
+                winit::keyboard::PhysicalKey::Code(KeyCode::Digit1) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    if let Some(tone_map) = wgpu_ctx.post.get_mut::<ToneMap>() {
+                      tone_map.mapping = tone_map.mapping.next();
+                      println!("色调映射：{:?}", tone_map.mapping);
+                    }
+                  }
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::Digit2) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    if let Some(bloom) = wgpu_ctx.post.get_mut::<Bloom>() {
+                      bloom.enabled = !bloom.enabled;
+                      println!("泛光：{}", bloom.enabled);
+                    }
+                  }
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::Digit3) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    if let Some(fxaa) = wgpu_ctx.post.get_mut::<Fxaa>() {
+                      fxaa.enabled = !fxaa.enabled;
+                      println!("FXAA：{}", fxaa.enabled);
+                    }
+                  }
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::Digit4) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    if let Some(ssao) = wgpu_ctx.post.get_mut::<Ssao>() {
+                      ssao.enabled = !ssao.enabled;
+                      println!("SSAO：{}", ssao.enabled);
+                    }
+                  }
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::Digit5) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    wgpu_ctx.transparency = wgpu_ctx.transparency.next();
+                    println!("半透明：{:?}", wgpu_ctx.transparency);
+                  }
+                },
+                // F11 开始或结束录制，参数来自 RECORD_ENV 环境变量
+                winit::keyboard::PhysicalKey::Code(KeyCode::F11) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    if wgpu_ctx.recorder.is_recording() {
+                      wgpu_ctx.recorder.stop();
+                    } else {
+                      let config = match std::env::var(RECORD_ENV) {
+                        Ok(text) => RecorderConfig::parse(&text),
+                        Err(_) => Ok(RecorderConfig::default()),
+                      };
+                      if let Err(e) = config.and_then(|config| wgpu_ctx.recorder.start(config, &self.scene)) {
+                        println!("无法开始录制：{}", e);
+                      }
+                    }
+                  }
+                },
+                // ` 显示或隐藏性能分析，\ 导出最近的帧为 Chrome trace
+                winit::keyboard::PhysicalKey::Code(KeyCode::Backquote) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    wgpu_ctx.profiler.show_overlay = !wgpu_ctx.profiler.show_overlay;
+                  }
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::Backslash) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    let time = chrono::Local::now().format("%Y%m%d_%H%M%S");
+                    let path = std::path::Path::new(PROFILE_DIR).join(format!("trace_{}.json", time));
+                    match wgpu_ctx.profiler.export_trace(&path) {
+                      Ok(()) => println!("性能分析已导出：{}", path.display()),
+                      Err(e) => println!("导出性能分析失败：{}", e),
+                    }
+                  }
+                },
+                // F12 保存窗口截图，F10 按窗口尺寸的 SCREENSHOT_SCALE 倍重新渲染后保存
+                winit::keyboard::PhysicalKey::Code(KeyCode::F12) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    wgpu_ctx.screenshot = Some(ScreenshotRequest { view: self.scene.clone(), size: None });
+                  }
+                },
+                winit::keyboard::PhysicalKey::Code(KeyCode::F10) => {
+                  if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    let size = (wgpu_ctx.vw * SCREENSHOT_SCALE, wgpu_ctx.vh * SCREENSHOT_SCALE);
+                    wgpu_ctx.screenshot = Some(ScreenshotRequest { view: self.scene.clone(), size: Some(size) });
+                  }
+                },
                 winit::keyboard::PhysicalKey::Code(KeyCode::Escape) => {
                   self.window.as_ref().unwrap().set_cursor_visible(true);
                   self.window.as_ref().unwrap().set_cursor_grab(CursorGrabMode::None).unwrap();
@@ -151,6 +412,11 @@ impl<'window> ApplicationHandler for App<'window> {
         },
         // 处理退出
         WindowEvent::CloseRequested => {
+            if let Some(wgpu_ctx) = self.wgpu_ctx.as_ref() {
+              if let Err(e) = wgpu_ctx.pipelines.save() {
+                println!("{}", e);
+              }
+            }
             event_loop.exit();
         }
         _ => ()
@@ -166,12 +432,9 @@ impl<'window> ApplicationHandler for App<'window> {
         match event {
             DeviceEvent::MouseMotion { delta } => {
               // println!("MouseMotion: {:#?}", &delta);
-              if let Some(wgpu_ctx) = self.wgpu_ctx.as_mut() {
-                let now = std::time::Instant::now();
-                let delta_time = now.duration_since(self.last_time.unwrap()).as_secs_f32();
-                self.last_time = Some(now);
-                  wgpu_ctx.camera.look_rotate(delta, delta_time);
-              } 
+              // 先累计，重绘时按帧间隔（录制时为模拟时间）转动相机
+              self.mouse_motion.0 += delta.0;
+              self.mouse_motion.1 += delta.1;
             },
             DeviceEvent::Added => {},
             DeviceEvent::Removed => {},
@@ -183,4 +446,4 @@ impl<'window> ApplicationHandler for App<'window> {
     }
     
     
-}
\ No newline at end of file
+}
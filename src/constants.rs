@@ -1,4 +1,35 @@
 pub const WIN_MAX_WIDTH: u32 = 1920;
 pub const WIN_MAX_HEIGHT: u32 = 1080;
 pub const WIN_MIN_WIDTH: u32 = 800;
-pub const WIN_MIN_HEIGHT: u32 = 600;
\ No newline at end of file
+pub const WIN_MIN_HEIGHT: u32 = 600;
+
+// 默认字体路径，需要包含中文字形
+pub const FONT_PATH: &str = "assets/fonts/default.ttf";
+
+// 设置该环境变量为着色器目录（例如 src/template）时，从磁盘读取着色器并热重载
+pub const SHADER_DIR_ENV: &str = "KIDAR_SHADER_DIR";
+
+// 驱动管线缓存的目录
+pub const PIPELINE_CACHE_DIR: &str = "cache";
+
+// 首页场景文件路径，随程序发布，运行时只读
+pub const HOME_SCENE_PATH: &str = "assets/scenes/home.json";
+// 保存首页场景的路径，存在时优先于 `HOME_SCENE_PATH` 加载
+pub const USER_HOME_SCENE_PATH: &str = "saves/home.json";
+// 截图保存目录
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+// 高分辨率截图相对窗口尺寸的倍数
+pub const SCREENSHOT_SCALE: u32 = 4;
+
+// 录制保存目录
+pub const RECORD_DIR: &str = "recordings";
+
+// 默认录制参数，可以用该环境变量覆盖，格式为 `宽x高@帧率[:png|:y4m]`，例如 1920x1080@60:y4m
+pub const RECORD_ENV: &str = "KIDAR_RECORD";
+pub const RECORD_WIDTH: u32 = 1280;
+pub const RECORD_HEIGHT: u32 = 720;
+pub const RECORD_FPS: u32 = 30;
+
+// 性能分析导出目录
+pub const PROFILE_DIR: &str = "profiles";
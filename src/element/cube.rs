@@ -22,51 +22,51 @@ impl Cube {
 
     let pos = [
       // 前面
-      Vertex { position: [x2, y2, z2], color: [c, 0.0, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x, y2, z2], color: [c, 0.0, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x, y, z2], color: [c, 0.0, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x, y, z2], color: [c, 0.0, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z2], color: [c, 0.0, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y2, z2], color: [c, 0.0, 0.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z2], color: [c, 0.0, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z2], color: [c, 0.0, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z2], color: [c, 0.0, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z2], color: [c, 0.0, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z2], color: [c, 0.0, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z2], color: [c, 0.0, 0.0, 1.0], tex_coords: [w, h] },
       
       // 后面
-      Vertex { position: [x, y2, z], color: [0.0, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y2, z], color: [0.0, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z], color: [0.0, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z], color: [0.0, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x, y, z], color: [0.0, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x, y2, z], color: [0.0, c, 0.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z], color: [0.0, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z], color: [0.0, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z], color: [0.0, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z], color: [0.0, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z], color: [0.0, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z], color: [0.0, c, 0.0, 1.0], tex_coords: [w, h] },
 
       // 上面
-      Vertex { position: [x2, y, z2], color: [0.0, 0.0, c], tex_coords: [w, h] },
-      Vertex { position: [x, y, z2], color: [0.0, 0.0, c], tex_coords: [w, h] },
-      Vertex { position: [x, y, z], color: [0.0, 0.0, c], tex_coords: [w, h] },
-      Vertex { position: [x, y, z], color: [0.0, 0.0, c], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z], color: [0.0, 0.0, c], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z2], color: [0.0, 0.0, c], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z2], color: [0.0, 0.0, c, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z2], color: [0.0, 0.0, c, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z], color: [0.0, 0.0, c, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z], color: [0.0, 0.0, c, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z], color: [0.0, 0.0, c, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z2], color: [0.0, 0.0, c, 1.0], tex_coords: [w, h] },
 
       // 下面
-      Vertex { position: [x2, y2, z], color: [0.0, c, 0.5], tex_coords: [w, h] },
-      Vertex { position: [x, y2, z], color: [0.0, c, 0.5], tex_coords: [w, h] },
-      Vertex { position: [x, y2, z2], color: [0.0, c, 0.5], tex_coords: [w, h] },
-      Vertex { position: [x, y2, z2], color: [0.0, c, 0.5], tex_coords: [w, h] },
-      Vertex { position: [x2, y2, z2], color: [0.0, c, 0.5], tex_coords: [w, h] },
-      Vertex { position: [x2, y2, z], color: [0.0, c, 0.5], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z], color: [0.0, c, 0.5, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z], color: [0.0, c, 0.5, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z2], color: [0.0, c, 0.5, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z2], color: [0.0, c, 0.5, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z2], color: [0.0, c, 0.5, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z], color: [0.0, c, 0.5, 1.0], tex_coords: [w, h] },
       // 左面
-      Vertex { position: [x2, y2, z], color: [0.5, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y2, z2], color: [1.5, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z2], color: [1.5, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z2], color: [1.5, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y, z], color: [1.5, c, 0.0], tex_coords: [w, h] },
-      Vertex { position: [x2, y2, z], color: [0.5, c, 0.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z], color: [0.5, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z2], color: [1.5, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z2], color: [1.5, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z2], color: [1.5, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y, z], color: [1.5, c, 0.0, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x2, y2, z], color: [0.5, c, 0.0, 1.0], tex_coords: [w, h] },
       
       // 右面
-      Vertex { position: [x, y2, z2], color: [0.0, c, 0.2], tex_coords: [w, h] },
-      Vertex { position: [x, y2, z], color: [0.0, c, 0.2], tex_coords: [w, h] },
-      Vertex { position: [x, y, z], color: [0.0, c, 0.2], tex_coords: [w, h] },
-      Vertex { position: [x, y, z], color: [0.0, c, 0.2], tex_coords: [w, h] },
-      Vertex { position: [x, y, z2], color: [0.0, c, 0.2], tex_coords: [w, h] },
-      Vertex { position: [x, y2, z2], color: [0.0, c, 0.2], tex_coords: [w, h] }, 
+      Vertex { position: [x, y2, z2], color: [0.0, c, 0.2, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z], color: [0.0, c, 0.2, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z], color: [0.0, c, 0.2, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z], color: [0.0, c, 0.2, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y, z2], color: [0.0, c, 0.2, 1.0], tex_coords: [w, h] },
+      Vertex { position: [x, y2, z2], color: [0.0, c, 0.2, 1.0], tex_coords: [w, h] }, 
       
     ].to_vec();
 
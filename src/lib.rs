@@ -1,18 +1,26 @@
-extern crate wasm_bindgen;
+//! 渲染器和场景代码，原生程序（main.rs）和浏览器（wasm32）共用
 
-use wasm_bindgen::prelude::*;
+pub mod render;
+pub mod app;
+pub mod element;
+pub mod views;
+pub mod calc;
+pub mod constants;
+pub mod text;
+pub mod ui;
+pub mod scene;
+pub mod anim;
+pub mod physics;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
 
-#[wasm_bindgen]
-extern {
-  // 在rust中使用JS函数
-  pub fn alert(s: &str);
+/// 创建窗口并运行事件循环，窗口关闭后返回
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run() {
+  use app::App;
+  use winit::event_loop::EventLoop;
 
-  #[wasm_bindgen(js_namespace = console)]
-  pub fn log(s: &str);
+  let event_loop = EventLoop::with_user_event().build().unwrap();
+  let mut app = App::default();
+  event_loop.run_app(&mut app).expect("run app error.");
 }
-
-#[wasm_bindgen]
-pub fn greet(name: &str) {
-  // 通过wasm_bindgen暴露出去，可在JS中调用
-  alert(&format!("Hello, {}!", name));
-}
\ No newline at end of file
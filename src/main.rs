@@ -1,14 +1,5 @@
-use crate::app::App;
-use winit::event_loop::EventLoop;
-
-mod render;
-mod app;
-mod element;
-mod views;
-mod calc;
-
 fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    let mut app = App::default();
-    event_loop.run_app(&mut app).expect("run app error.");
+  // 浏览器中通过 web::start 启动
+  #[cfg(not(target_arch = "wasm32"))]
+  kidar_rust_3d::run();
 }
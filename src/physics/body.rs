@@ -0,0 +1,126 @@
+use nalgebra::{Matrix3, UnitQuaternion, Vector3};
+
+use super::collider::{Aabb, Collider};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyType {
+  Dynamic, // 受重力和碰撞影响
+  Kinematic, // 按设置的速度移动，不受碰撞影响，但会推开动态刚体
+  Static, // 固定不动
+}
+
+#[derive(Clone, Debug)]
+pub struct RigidBody {
+  pub body_type: BodyType,
+  pub collider: Collider,
+  pub position: Vector3<f32>,
+  pub rotation: UnitQuaternion<f32>,
+  pub linear_velocity: Vector3<f32>,
+  pub angular_velocity: Vector3<f32>,
+  pub restitution: f32, // 弹性系数，0 不反弹，1 完全弹性
+  pub friction: f32, // 摩擦系数
+  pub linear_damping: f32,
+  pub angular_damping: f32,
+  pub element: Option<u32>, // 同步到的场景元素 id
+  inv_mass: f32,
+  inv_inertia: Vector3<f32>, // 局部坐标系下主转动惯量的倒数
+}
+
+impl RigidBody {
+  /// 质量默认为 1，动态刚体可以用 `with_mass` 修改
+  pub fn new(body_type: BodyType, collider: Collider, position: Vector3<f32>) -> Self {
+    let mut body = Self {
+      body_type,
+      collider,
+      position,
+      rotation: UnitQuaternion::identity(),
+      linear_velocity: Vector3::zeros(),
+      angular_velocity: Vector3::zeros(),
+      restitution: 0.1,
+      friction: 0.5,
+      linear_damping: 0.01,
+      angular_damping: 0.05,
+      element: None,
+      inv_mass: 0.0,
+      inv_inertia: Vector3::zeros(),
+    };
+    body.set_mass(1.0);
+    body
+  }
+
+  pub fn with_mass(mut self, mass: f32) -> Self {
+    self.set_mass(mass);
+    self
+  }
+
+  pub fn with_rotation(mut self, rotation: UnitQuaternion<f32>) -> Self {
+    self.rotation = rotation;
+    self
+  }
+
+  pub fn with_material(mut self, restitution: f32, friction: f32) -> Self {
+    self.restitution = restitution;
+    self.friction = friction;
+    self
+  }
+
+  pub fn with_element(mut self, id: u32) -> Self {
+    self.element = Some(id);
+    self
+  }
+
+  /// 设置质量，只对动态刚体有效；静态和运动学刚体的质量视为无穷大
+  pub fn set_mass(&mut self, mass: f32) {
+    if self.body_type != BodyType::Dynamic || mass <= 0.0 {
+      self.inv_mass = 0.0;
+      self.inv_inertia = Vector3::zeros();
+      return
+    }
+    self.inv_mass = 1.0 / mass;
+    self.inv_inertia = self.collider.inertia(mass).map(|i| if i > 0.0 { 1.0 / i } else { 0.0 });
+  }
+
+  pub fn inv_mass(&self) -> f32 {
+    self.inv_mass
+  }
+
+  pub fn is_dynamic(&self) -> bool {
+    self.body_type == BodyType::Dynamic
+  }
+
+  /// 世界坐标系下转动惯量的逆矩阵：R * I⁻¹ * Rᵀ
+  pub fn inv_inertia_world(&self) -> Matrix3<f32> {
+    let r = self.rotation.to_rotation_matrix();
+    r.matrix() * Matrix3::from_diagonal(&self.inv_inertia) * r.matrix().transpose()
+  }
+
+  /// 刚体上 `point` 处的速度
+  pub fn velocity_at(&self, point: &Vector3<f32>) -> Vector3<f32> {
+    self.linear_velocity + self.angular_velocity.cross(&(point - self.position))
+  }
+
+  /// 在 `point` 处施加冲量
+  pub fn apply_impulse(&mut self, impulse: &Vector3<f32>, point: &Vector3<f32>) {
+    if self.inv_mass == 0.0 {
+      return
+    }
+    self.linear_velocity += impulse * self.inv_mass;
+    self.angular_velocity += self.inv_inertia_world() * (point - self.position).cross(impulse);
+  }
+
+  pub fn aabb(&self) -> Aabb {
+    self.collider.aabb(&self.position, &self.rotation)
+  }
+
+  /// 按速度移动一步
+  pub fn integrate(&mut self, dt: f32) {
+    if self.body_type == BodyType::Static {
+      return
+    }
+    self.position += self.linear_velocity * dt;
+    let w = self.angular_velocity;
+    if w.norm_squared() > 0.0 {
+      self.rotation = UnitQuaternion::from_scaled_axis(w * dt) * self.rotation;
+    }
+  }
+}
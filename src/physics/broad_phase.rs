@@ -0,0 +1,72 @@
+use super::body::RigidBody;
+use super::collider::Aabb;
+
+/// 扫描排除（sweep and prune）：按包围盒在 x 轴上的起点排序，只检查 x 区间重叠的刚体
+///
+/// 返回可能碰撞的刚体下标对 (i, j)，i < j，按下标排序；两个都不是动态刚体的组合会被跳过。
+pub fn find_pairs(bodies: &[RigidBody]) -> Vec<(usize, usize)> {
+  let aabbs: Vec<Aabb> = bodies.iter().map(|b| b.aabb()).collect();
+  let mut order: Vec<usize> = (0..bodies.len()).collect();
+  // 起点相同时按下标排序，保证结果确定
+  order.sort_by(|a, b| aabbs[*a].min.x.total_cmp(&aabbs[*b].min.x).then(a.cmp(b)));
+
+  let mut pairs = vec![];
+  let mut active: Vec<usize> = vec![];
+  for i in order {
+    let min_x = aabbs[i].min.x;
+    active.retain(|a| aabbs[*a].max.x >= min_x);
+    for a in active.iter() {
+      if !bodies[i].is_dynamic() && !bodies[*a].is_dynamic() {
+        continue
+      }
+      if aabbs[i].overlaps(&aabbs[*a]) {
+        pairs.push(((*a).min(i), (*a).max(i)));
+      }
+    }
+    active.push(i);
+  }
+  pairs.sort();
+  pairs
+}
+
+#[cfg(test)]
+mod tests {
+  use nalgebra::{UnitQuaternion, Vector3};
+
+  use super::*;
+  use crate::physics::body::BodyType;
+  use crate::physics::collider::Collider;
+
+  #[test]
+  fn matches_brute_force() {
+    // 固定种子的线性同余随机数
+    let mut seed = 12345u64;
+    let mut rand = move |range: f32| {
+      seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+      (seed >> 40) as f32 / (1u64 << 24) as f32 * range
+    };
+    let bodies: Vec<RigidBody> = (0..200).map(|i| {
+      let body_type = [BodyType::Dynamic, BodyType::Static, BodyType::Kinematic][i % 3];
+      let collider = match i % 3 {
+        0 => Collider::cuboid(rand(80.0) + 1.0, rand(80.0) + 1.0, rand(80.0) + 1.0),
+        1 => Collider::Sphere { radius: rand(40.0) + 1.0 },
+        _ => Collider::Capsule { half_height: rand(30.0), radius: rand(20.0) + 1.0 },
+      };
+      let rotation = UnitQuaternion::from_euler_angles(rand(6.0), rand(6.0), rand(6.0));
+      // 一部分刚体的 x 坐标相同，检查起点相同时的处理
+      let x = if i % 10 == 0 { 100.0 } else { rand(1000.0) };
+      RigidBody::new(body_type, collider, Vector3::new(x, rand(300.0), rand(300.0))).with_rotation(rotation)
+    }).collect();
+
+    let mut expected = vec![];
+    for i in 0..bodies.len() {
+      for j in i + 1..bodies.len() {
+        if (bodies[i].is_dynamic() || bodies[j].is_dynamic()) && bodies[i].aabb().overlaps(&bodies[j].aabb()) {
+          expected.push((i, j));
+        }
+      }
+    }
+    assert!(!expected.is_empty());
+    assert_eq!(find_pairs(&bodies), expected);
+  }
+}
@@ -0,0 +1,329 @@
+use nalgebra::Vector3;
+
+use super::body::{BodyType, RigidBody};
+use super::collider::Collider;
+use super::narrow_phase::collide;
+
+// 每次移动后消除穿透的最大迭代次数
+const RESOLVE_ITERATIONS: usize = 4;
+// 贴地检测时向下探测的额外距离
+const GROUND_PROBE: f32 = 2.0;
+// 向下落地时每次移动的距离，太大会穿进台阶后被横向推开
+const DROP_INCREMENT: f32 = 5.0;
+
+/// 胶囊体角色控制器：重力、跳跃、上台阶、坡度限制，碰到墙时沿墙滑动
+///
+/// 场景中的碰撞体都视为静止，角色不会推动它们。坐标单位与场景相同（接近厘米）。
+#[derive(Clone, Debug)]
+pub struct CharacterController {
+  pub feet: Vector3<f32>, // 脚底中心
+  pub radius: f32,
+  pub height: f32, // 胶囊总高
+  pub eye_height: f32, // 眼睛离脚底的高度
+  pub step_height: f32, // 可以直接走上去的台阶高度
+  pub max_slope: f32, // 可以站立的最大坡度（弧度）
+  pub gravity: f32,
+  pub jump_speed: f32,
+  pub vertical_speed: f32,
+  pub grounded: bool,
+}
+
+impl Default for CharacterController {
+  fn default() -> Self {
+    Self {
+      feet: Vector3::zeros(),
+      radius: 30.0,
+      height: 180.0,
+      eye_height: 165.0,
+      step_height: 35.0,
+      max_slope: 45f32.to_radians(),
+      gravity: -980.0,
+      jump_speed: 420.0,
+      vertical_speed: 0.0,
+      grounded: false,
+    }
+  }
+}
+
+impl CharacterController {
+  pub fn eye(&self) -> Vector3<f32> {
+    self.feet + Vector3::new(0.0, self.eye_height, 0.0)
+  }
+
+  /// 把眼睛放到 `eye`，并清除竖直速度
+  pub fn set_eye(&mut self, eye: Vector3<f32>) {
+    self.feet = eye - Vector3::new(0.0, self.eye_height, 0.0);
+    self.vertical_speed = 0.0;
+    self.grounded = false;
+  }
+
+  fn capsule(&self) -> RigidBody {
+    let half_height = (self.height / 2.0 - self.radius).max(0.0);
+    let center = self.feet + Vector3::new(0.0, self.height / 2.0, 0.0);
+    RigidBody::new(BodyType::Kinematic, Collider::Capsule { half_height, radius: self.radius }, center)
+  }
+
+  // 法线（从碰撞体指向角色）是否朝上到可以站立
+  fn walkable(&self, normal: &Vector3<f32>) -> bool {
+    normal.y >= self.max_slope.cos()
+  }
+
+  /// 消除与碰撞体的穿透，返回所有接触的法线（从碰撞体指向角色）
+  ///
+  /// `horizontal` 为 true 时不能站立的面（墙、陡坡）只在水平方向推开，避免走路时沿陡坡爬升；
+  /// 竖直移动时按法线推开，角色会沿陡坡滑下。
+  fn resolve(&mut self, colliders: &[RigidBody], horizontal: bool) -> Vec<Vector3<f32>> {
+    let mut normals = vec![];
+    for _ in 0..RESOLVE_ITERATIONS {
+      let capsule = self.capsule();
+      let aabb = capsule.aabb();
+      let mut moved = false;
+      for (i, body) in colliders.iter().enumerate() {
+        if !aabb.overlaps(&body.aabb()) {
+          continue
+        }
+        // 同一个碰撞体只按最深的接触推开
+        let Some(contact) = collide(0, &capsule, i, body).into_iter().max_by(|a, b| a.depth.total_cmp(&b.depth)) else {
+          continue
+        };
+        if contact.depth <= 0.0 {
+          continue
+        }
+        let normal = -contact.normal;
+        let mut push = normal * contact.depth;
+        if horizontal && !self.walkable(&normal) {
+          push.y = push.y.min(0.0);
+          let horizontal = Vector3::new(normal.x, 0.0, normal.z);
+          if push.norm_squared() < f32::EPSILON && horizontal.norm_squared() > f32::EPSILON {
+            push = horizontal.normalize() * contact.depth;
+          }
+        }
+        self.feet += push;
+        normals.push(normal);
+        moved = true;
+        break
+      }
+      if !moved {
+        break
+      }
+    }
+    normals
+  }
+
+  fn overlaps_any(&self, colliders: &[RigidBody]) -> bool {
+    let capsule = self.capsule();
+    colliders.iter().enumerate().any(|(i, body)| collide(0, &capsule, i, body).iter().any(|c| c.depth > 0.0))
+  }
+
+  // 水平移动并沿墙滑动，返回实际移动的水平距离
+  fn slide(&mut self, offset: Vector3<f32>, colliders: &[RigidBody]) -> f32 {
+    let start = self.feet;
+    self.feet += offset;
+    self.resolve(colliders, true);
+    let moved = self.feet - start;
+    Vector3::new(moved.x, 0.0, moved.z).norm()
+  }
+
+  // 逐段向下移动最多 `distance`，碰到东西就停下，返回是否落在可以站立的面上
+  fn drop_down(&mut self, distance: f32, colliders: &[RigidBody]) -> bool {
+    let mut left = distance;
+    while left > 0.0 {
+      let delta = left.min(DROP_INCREMENT);
+      self.feet.y -= delta;
+      left -= delta;
+      let normals = self.resolve(colliders, false);
+      if !normals.is_empty() {
+        return normals.iter().any(|n| self.walkable(n))
+      }
+    }
+    false
+  }
+
+  /// 推进一帧
+  /// - `walk`: 本帧期望的水平位移
+  /// - `jump`: 是否起跳，只有站在地面上时有效
+  pub fn update(&mut self, walk: Vector3<f32>, jump: bool, dt: f32, colliders: &[RigidBody]) {
+    let walk = Vector3::new(walk.x, 0.0, walk.z);
+    let was_grounded = self.grounded;
+    if self.grounded && jump {
+      self.vertical_speed = self.jump_speed;
+      self.grounded = false;
+    }
+
+    // 水平移动，被挡住时尝试抬高一个台阶再走
+    if walk.norm_squared() > 0.0 {
+      let start = self.feet;
+      let distance = self.slide(walk, colliders);
+      if was_grounded && distance < walk.norm() * 0.5 {
+        let blocked = self.feet;
+        self.feet = start + Vector3::new(0.0, self.step_height, 0.0);
+        let stepped = if self.overlaps_any(colliders) { 0.0 } else { self.slide(walk, colliders) };
+        let raised = self.feet;
+        let accepted = stepped > distance && (self.drop_down(self.step_height + GROUND_PROBE, colliders) || {
+          // 落在台阶边缘上时法线是斜的，再往前探一个半径，前方能站立才算台阶，陡坡不能这样爬上去
+          // 通过时抬到台阶面的高度，否则下一步又会从边缘滑下去
+          let landed = self.feet;
+          self.feet = raised + walk.normalize() * self.radius;
+          let ahead = !self.overlaps_any(colliders) && self.drop_down(self.step_height + GROUND_PROBE, colliders);
+          let top = self.feet.y;
+          self.feet = landed;
+          if ahead {
+            self.feet.y = top.max(landed.y);
+          }
+          ahead
+        });
+        if !accepted {
+          self.feet = blocked;
+        }
+      }
+    }
+
+    // 竖直移动
+    self.vertical_speed += self.gravity * dt;
+    self.feet.y += self.vertical_speed * dt;
+    let normals = self.resolve(colliders, false);
+    self.grounded = normals.iter().any(|n| self.walkable(n));
+    if self.grounded && self.vertical_speed < 0.0 {
+      self.vertical_speed = 0.0;
+    }
+    if normals.iter().any(|n| n.y < -0.7) && self.vertical_speed > 0.0 {
+      // 撞到天花板
+      self.vertical_speed = 0.0;
+    }
+
+    // 走下台阶时贴住地面，而不是飞出去
+    if was_grounded && !self.grounded && self.vertical_speed <= 0.0 {
+      let before = self.feet;
+      if self.drop_down(self.step_height + GROUND_PROBE, colliders) {
+        self.grounded = true;
+        self.vertical_speed = 0.0;
+      } else {
+        self.feet = before;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nalgebra::UnitQuaternion;
+
+  use super::*;
+
+  const DT: f32 = 1.0 / 60.0;
+
+  // 静态盒子，`min`、`max` 为包围盒的两个角
+  fn block(min: [f32; 3], max: [f32; 3]) -> RigidBody {
+    let (min, max) = (Vector3::from(min), Vector3::from(max));
+    let size = max - min;
+    RigidBody::new(BodyType::Static, Collider::cuboid(size.x, size.y, size.z), (min + max) / 2.0)
+  }
+
+  // 顶面是 y = 0 的地面
+  fn ground() -> RigidBody {
+    block([-2000.0, -100.0, -2000.0], [2000.0, 0.0, 2000.0])
+  }
+
+  // 从 (x0, 0) 开始沿 +x 方向升高、坡度为 `angle`（角度）的斜坡
+  fn ramp(x0: f32, angle: f32) -> RigidBody {
+    let angle = angle.to_radians();
+    let normal = Vector3::new(-angle.sin(), angle.cos(), 0.0);
+    let center = Vector3::new(x0, 0.0, 0.0) - normal * 500.0;
+    RigidBody::new(BodyType::Static, Collider::cuboid(1000.0, 1000.0, 1000.0), center)
+      .with_rotation(UnitQuaternion::from_euler_angles(0.0, 0.0, angle))
+  }
+
+  fn run(character: &mut CharacterController, walk: Vector3<f32>, frames: usize, colliders: &[RigidBody]) {
+    for _ in 0..frames {
+      character.update(walk, false, DT, colliders);
+    }
+  }
+
+  // 站在 `feet` 处的地面上
+  fn standing(feet: Vector3<f32>, colliders: &[RigidBody]) -> CharacterController {
+    let mut character = CharacterController { feet, ..Default::default() };
+    run(&mut character, Vector3::zeros(), 10, colliders);
+    assert!(character.grounded);
+    character
+  }
+
+  #[test]
+  fn falls_and_lands_on_the_ground() {
+    let colliders = [ground()];
+    let mut character = CharacterController { feet: Vector3::new(0.0, 300.0, 0.0), ..Default::default() };
+    character.update(Vector3::zeros(), false, DT, &colliders);
+    assert!(!character.grounded);
+    assert!(character.vertical_speed < 0.0);
+    run(&mut character, Vector3::zeros(), 120, &colliders);
+    assert!(character.grounded);
+    assert_eq!(character.vertical_speed, 0.0);
+    assert!(character.feet.y.abs() < 1.0, "y = {}", character.feet.y);
+  }
+
+  #[test]
+  fn jumps_only_when_grounded() {
+    let colliders = [ground()];
+    // 空中按跳跃没有效果
+    let mut character = CharacterController { feet: Vector3::new(0.0, 300.0, 0.0), ..Default::default() };
+    character.update(Vector3::zeros(), true, DT, &colliders);
+    assert!(character.vertical_speed < 0.0);
+
+    let mut character = standing(Vector3::zeros(), &colliders);
+    character.update(Vector3::zeros(), true, DT, &colliders);
+    assert!(!character.grounded);
+    assert!(character.feet.y > 0.0);
+    let speed = character.vertical_speed;
+    assert!(speed > 0.0 && speed < character.jump_speed);
+    // 上升途中再按跳跃不会再次加速
+    character.update(Vector3::zeros(), true, DT, &colliders);
+    assert!(character.vertical_speed < speed);
+    run(&mut character, Vector3::zeros(), 120, &colliders);
+    assert!(character.grounded);
+    assert!(character.feet.y.abs() < 1.0);
+  }
+
+  #[test]
+  fn steps_onto_a_low_box() {
+    let colliders = [ground(), block([100.0, 0.0, -200.0], [400.0, 20.0, 200.0])];
+    let mut character = standing(Vector3::zeros(), &colliders);
+    run(&mut character, Vector3::new(5.0, 0.0, 0.0), 60, &colliders);
+    assert!(character.feet.x > 150.0, "x = {}", character.feet.x);
+    assert!((character.feet.y - 20.0).abs() < 1.0, "y = {}", character.feet.y);
+    assert!(character.grounded);
+  }
+
+  #[test]
+  fn is_blocked_by_a_tall_box() {
+    let colliders = [ground(), block([100.0, 0.0, -200.0], [400.0, 100.0, 200.0])];
+    let mut character = standing(Vector3::zeros(), &colliders);
+    run(&mut character, Vector3::new(5.0, 0.0, 0.0), 60, &colliders);
+    assert!(character.feet.x <= 100.0 - character.radius + 0.5, "x = {}", character.feet.x);
+    assert!(character.feet.y.abs() < 1.0, "y = {}", character.feet.y);
+  }
+
+  #[test]
+  fn slides_along_a_wall() {
+    let colliders = [ground(), block([100.0, 0.0, -2000.0], [200.0, 300.0, 2000.0])];
+    let mut character = standing(Vector3::zeros(), &colliders);
+    run(&mut character, Vector3::new(5.0, 0.0, 5.0), 60, &colliders);
+    assert!(character.feet.x <= 100.0 - character.radius + 0.5, "x = {}", character.feet.x);
+    // 贴墙后 z 方向的分量不受影响，60 帧共 300
+    assert!(character.feet.z > 250.0, "z = {}", character.feet.z);
+  }
+
+  #[test]
+  fn refuses_slopes_steeper_than_max_slope() {
+    let colliders = [ground(), ramp(100.0, 60.0)];
+    let mut character = standing(Vector3::zeros(), &colliders);
+    run(&mut character, Vector3::new(5.0, 0.0, 0.0), 120, &colliders);
+    assert!(character.feet.y < 10.0, "y = {}", character.feet.y);
+    assert!(character.feet.x < 120.0, "x = {}", character.feet.x);
+
+    // 坡度小于 max_slope 时可以走上去
+    let colliders = [ground(), ramp(100.0, 20.0)];
+    let mut character = standing(Vector3::zeros(), &colliders);
+    run(&mut character, Vector3::new(5.0, 0.0, 0.0), 120, &colliders);
+    assert!(character.feet.x > 400.0, "x = {}", character.feet.x);
+    assert!(character.feet.y > 80.0, "y = {}", character.feet.y);
+  }
+}
@@ -0,0 +1,64 @@
+use nalgebra::{UnitQuaternion, Vector3};
+
+/// 碰撞形状，在刚体的局部坐标系中以原点为中心
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collider {
+  Box { half_extents: Vector3<f32> },
+  Sphere { radius: f32 },
+  Capsule { half_height: f32, radius: f32 }, // 沿局部 y 轴，`half_height` 不含两端半球
+}
+
+/// 轴对齐包围盒
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+  pub min: Vector3<f32>,
+  pub max: Vector3<f32>,
+}
+
+impl Aabb {
+  pub fn overlaps(&self, other: &Aabb) -> bool {
+    (0..3).all(|i| self.min[i] <= other.max[i] && self.max[i] >= other.min[i])
+  }
+}
+
+impl Collider {
+  pub fn cuboid(x: f32, y: f32, z: f32) -> Self {
+    Collider::Box { half_extents: Vector3::new(x / 2.0, y / 2.0, z / 2.0) }
+  }
+
+  /// 世界坐标下的包围盒
+  pub fn aabb(&self, position: &Vector3<f32>, rotation: &UnitQuaternion<f32>) -> Aabb {
+    let extent = match self {
+      Collider::Box { half_extents } => {
+        // 旋转后的包围盒半长：|R| * e
+        let r = rotation.to_rotation_matrix();
+        let abs = r.matrix().abs();
+        abs * half_extents
+      },
+      Collider::Sphere { radius } => Vector3::repeat(*radius),
+      Collider::Capsule { half_height, radius } => {
+        let axis = rotation * Vector3::y() * *half_height;
+        axis.abs() + Vector3::repeat(*radius)
+      },
+    };
+    Aabb { min: position - extent, max: position + extent }
+  }
+
+  /// 质量为 `mass` 时局部坐标系下的主转动惯量
+  pub fn inertia(&self, mass: f32) -> Vector3<f32> {
+    match self {
+      Collider::Box { half_extents } => {
+        let s = half_extents * 2.0;
+        let (x2, y2, z2) = (s.x * s.x, s.y * s.y, s.z * s.z);
+        Vector3::new(y2 + z2, x2 + z2, x2 + y2) * (mass / 12.0)
+      },
+      Collider::Sphere { radius } => Vector3::repeat(0.4 * mass * radius * radius),
+      Collider::Capsule { half_height, radius } => {
+        // 近似为总长相同的圆柱
+        let h = (half_height + radius) * 2.0;
+        let side = mass * (3.0 * radius * radius + h * h) / 12.0;
+        Vector3::new(side, 0.5 * mass * radius * radius, side)
+      },
+    }
+  }
+}
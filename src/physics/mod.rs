@@ -0,0 +1,7 @@
+pub mod collider;
+pub mod body;
+pub mod broad_phase;
+pub mod narrow_phase;
+pub mod solver;
+pub mod world;
+pub mod character;
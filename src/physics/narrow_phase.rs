@@ -0,0 +1,343 @@
+use nalgebra::{Matrix3, Vector3};
+
+use super::body::RigidBody;
+use super::collider::Collider;
+
+// 判断顶点是否在盒子内时的容差
+const INSIDE_TOLERANCE: f32 = 0.01;
+// 分离轴测试中边-边轴需要比面轴明显更优才会被选中，避免面接触时法线抖动
+const EDGE_AXIS_BIAS: f32 = 0.95;
+
+/// 接触点，`normal` 从刚体 a 指向刚体 b
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+  pub a: usize,
+  pub b: usize,
+  pub normal: Vector3<f32>,
+  pub point: Vector3<f32>, // 世界坐标
+  pub depth: f32, // 穿透深度，正数表示重叠
+  pub normal_impulse: f32, // 求解得到的累计冲量，下一步用于预热（warm starting）
+  pub tangent_impulse: [f32; 2],
+}
+
+// 与刚体下标无关的接触结果
+struct Hit {
+  normal: Vector3<f32>,
+  point: Vector3<f32>,
+  depth: f32,
+}
+
+impl Hit {
+  fn flipped(self) -> Self {
+    Hit { normal: -self.normal, ..self }
+  }
+}
+
+// 世界坐标下的有向盒
+struct Obb {
+  center: Vector3<f32>,
+  axes: Matrix3<f32>, // 每列是一个局部轴
+  half: Vector3<f32>,
+}
+
+impl Obb {
+  // 在方向 n 上的投影半径
+  fn radius(&self, n: &Vector3<f32>) -> f32 {
+    (0..3).map(|i| self.half[i] * self.axes.column(i).dot(n).abs()).sum()
+  }
+
+  fn vertices(&self) -> Vec<Vector3<f32>> {
+    let mut list = Vec::with_capacity(8);
+    for i in 0..8 {
+      let s = Vector3::new(
+        if i & 1 == 0 { -1.0 } else { 1.0 },
+        if i & 2 == 0 { -1.0 } else { 1.0 },
+        if i & 4 == 0 { -1.0 } else { 1.0 },
+      );
+      list.push(self.center + self.axes * s.component_mul(&self.half));
+    }
+    list
+  }
+
+  fn to_local(&self, p: &Vector3<f32>) -> Vector3<f32> {
+    self.axes.transpose() * (p - self.center)
+  }
+
+  fn contains(&self, p: &Vector3<f32>) -> bool {
+    let local = self.to_local(p);
+    (0..3).all(|i| local[i].abs() <= self.half[i] + INSIDE_TOLERANCE)
+  }
+
+  fn closest_point(&self, p: &Vector3<f32>) -> Vector3<f32> {
+    let local = self.to_local(p);
+    let clamped = Vector3::from_fn(|i, _| local[i].clamp(-self.half[i], self.half[i]));
+    self.center + self.axes * clamped
+  }
+
+  // 在方向 n 上最远的顶点
+  fn support(&self, n: &Vector3<f32>) -> Vector3<f32> {
+    let mut p = self.center;
+    for i in 0..3 {
+      let axis = self.axes.column(i).into_owned();
+      p += axis * self.half[i] * axis.dot(n).signum();
+    }
+    p
+  }
+}
+
+fn obb(body: &RigidBody, half: &Vector3<f32>) -> Obb {
+  Obb { center: body.position, axes: *body.rotation.to_rotation_matrix().matrix(), half: *half }
+}
+
+// 胶囊中心线段的两个端点
+fn segment(body: &RigidBody, half_height: f32) -> (Vector3<f32>, Vector3<f32>) {
+  let axis = body.rotation * Vector3::y() * half_height;
+  (body.position - axis, body.position + axis)
+}
+
+/// 检测两个刚体之间的接触，`a`、`b` 是刚体下标，会写入结果
+pub fn collide(a: usize, body_a: &RigidBody, b: usize, body_b: &RigidBody) -> Vec<Contact> {
+  let hits = match (&body_a.collider, &body_b.collider) {
+    (Collider::Sphere { radius: ra }, Collider::Sphere { radius: rb }) => {
+      sphere_sphere(&body_a.position, *ra, &body_b.position, *rb).into_iter().collect()
+    },
+    (Collider::Sphere { radius }, Collider::Box { half_extents }) => {
+      sphere_box(&body_a.position, *radius, &obb(body_b, half_extents)).into_iter().collect()
+    },
+    (Collider::Box { half_extents }, Collider::Sphere { radius }) => {
+      sphere_box(&body_b.position, *radius, &obb(body_a, half_extents)).into_iter().map(Hit::flipped).collect()
+    },
+    (Collider::Box { half_extents: ha }, Collider::Box { half_extents: hb }) => {
+      box_box(&obb(body_a, ha), &obb(body_b, hb))
+    },
+    (Collider::Capsule { half_height, radius }, Collider::Sphere { radius: rs }) => {
+      let (p0, p1) = segment(body_a, *half_height);
+      let q = closest_on_segment(&p0, &p1, &body_b.position);
+      sphere_sphere(&q, *radius, &body_b.position, *rs).into_iter().collect()
+    },
+    (Collider::Sphere { radius: rs }, Collider::Capsule { half_height, radius }) => {
+      let (p0, p1) = segment(body_b, *half_height);
+      let q = closest_on_segment(&p0, &p1, &body_a.position);
+      sphere_sphere(&body_a.position, *rs, &q, *radius).into_iter().collect()
+    },
+    (Collider::Capsule { half_height: ha, radius: ra }, Collider::Capsule { half_height: hb, radius: rb }) => {
+      let (a0, a1) = segment(body_a, *ha);
+      let (b0, b1) = segment(body_b, *hb);
+      let (pa, pb) = closest_between_segments(&a0, &a1, &b0, &b1);
+      sphere_sphere(&pa, *ra, &pb, *rb).into_iter().collect()
+    },
+    (Collider::Capsule { half_height, radius }, Collider::Box { half_extents }) => {
+      capsule_box(segment(body_a, *half_height), *radius, &obb(body_b, half_extents))
+    },
+    (Collider::Box { half_extents }, Collider::Capsule { half_height, radius }) => {
+      capsule_box(segment(body_b, *half_height), *radius, &obb(body_a, half_extents)).into_iter().map(Hit::flipped).collect()
+    },
+  };
+  hits.into_iter()
+    .map(|h| Contact { a, b, normal: h.normal, point: h.point, depth: h.depth, normal_impulse: 0.0, tangent_impulse: [0.0; 2] })
+    .collect()
+}
+
+fn sphere_sphere(pa: &Vector3<f32>, ra: f32, pb: &Vector3<f32>, rb: f32) -> Option<Hit> {
+  let d = pb - pa;
+  let dist = d.norm();
+  let depth = ra + rb - dist;
+  if depth < 0.0 {
+    return None;
+  }
+  // 球心重合时任取一个方向
+  let normal = if dist > f32::EPSILON { d / dist } else { Vector3::y() };
+  Some(Hit { normal, point: pa + normal * (ra - depth / 2.0), depth })
+}
+
+// 法线从球指向盒子
+fn sphere_box(center: &Vector3<f32>, radius: f32, b: &Obb) -> Option<Hit> {
+  let q = b.closest_point(center);
+  let d = q - center;
+  let dist = d.norm();
+  if dist > f32::EPSILON {
+    let depth = radius - dist;
+    if depth < 0.0 {
+      return None;
+    }
+    return Some(Hit { normal: d / dist, point: q, depth });
+  }
+  // 球心在盒子内：沿距离最近的面推出
+  let local = b.to_local(center);
+  let (axis, face_dist) = (0..3)
+    .map(|i| (i, b.half[i] - local[i].abs()))
+    .min_by(|x, y| x.1.total_cmp(&y.1))
+    .unwrap();
+  let outward = b.axes.column(axis).into_owned() * local[axis].signum();
+  Some(Hit { normal: -outward, point: *center, depth: radius + face_dist })
+}
+
+// 分离轴测试：15 条候选轴中重叠最少的作为法线，接触点取互相包含的顶点
+fn box_box(a: &Obb, b: &Obb) -> Vec<Hit> {
+  let d = b.center - a.center;
+  let mut best: Option<(f32, Vector3<f32>)> = None;
+  let mut test = |axis: Vector3<f32>, bias: f32| -> bool {
+    let len = axis.norm();
+    if len < 1.0e-6 {
+      return true; // 平行的边，叉积无意义
+    }
+    let n = axis / len;
+    let overlap = a.radius(&n) + b.radius(&n) - d.dot(&n).abs();
+    if overlap < 0.0 {
+      return false;
+    }
+    if best.map(|(o, _)| overlap < o * bias).unwrap_or(true) {
+      best = Some((overlap, if d.dot(&n) < 0.0 { -n } else { n }));
+    }
+    true
+  };
+  for i in 0..3 {
+    if !test(a.axes.column(i).into_owned(), 1.0) || !test(b.axes.column(i).into_owned(), 1.0) {
+      return vec![];
+    }
+  }
+  for i in 0..3 {
+    for j in 0..3 {
+      let axis = a.axes.column(i).cross(&b.axes.column(j));
+      if !test(axis, EDGE_AXIS_BIAS) {
+        return vec![];
+      }
+    }
+  }
+  let Some((overlap, normal)) = best else {
+    return vec![]
+  };
+
+  // a 在法线方向上的最远平面和 b 在反方向上的最远平面
+  let a_face = a.center.dot(&normal) + a.radius(&normal);
+  let b_face = b.center.dot(&normal) - b.radius(&normal);
+  let mut hits: Vec<Hit> = vec![];
+  let candidates = b.vertices().into_iter().filter(|v| a.contains(v)).map(|v| (v, a_face - v.dot(&normal)))
+    .chain(a.vertices().into_iter().filter(|v| b.contains(v)).map(|v| (v, v.dot(&normal) - b_face)));
+  for (point, depth) in candidates {
+    // 两个盒子对齐时顶点会重合，只保留一个
+    if hits.iter().any(|h| (h.point - point).norm() < INSIDE_TOLERANCE * 10.0) {
+      continue
+    }
+    hits.push(Hit { normal, point, depth: depth.clamp(0.0, overlap) });
+  }
+  if hits.is_empty() {
+    // 边-边接触：取两个最深顶点的中点
+    let point = (a.support(&normal) + b.support(&-normal)) / 2.0;
+    hits.push(Hit { normal, point, depth: overlap });
+  }
+  hits
+}
+
+// 法线从胶囊指向盒子；除了离盒子最近的点，两端半球也会单独生成接触，使躺着的胶囊保持稳定
+fn capsule_box((p0, p1): (Vector3<f32>, Vector3<f32>), radius: f32, b: &Obb) -> Vec<Hit> {
+  // 交替求线段和盒子上的最近点，几次迭代即可收敛
+  let mut p = (p0 + p1) / 2.0;
+  for _ in 0..4 {
+    let q = b.closest_point(&p);
+    p = closest_on_segment(&p0, &p1, &q);
+  }
+  let mut hits: Vec<Hit> = vec![];
+  for center in [p, p0, p1] {
+    if hits.iter().any(|h| (h.point - b.closest_point(&center)).norm() < INSIDE_TOLERANCE) {
+      continue
+    }
+    if let Some(hit) = sphere_box(&center, radius, b) {
+      hits.push(hit);
+    }
+  }
+  hits
+}
+
+fn closest_on_segment(p0: &Vector3<f32>, p1: &Vector3<f32>, q: &Vector3<f32>) -> Vector3<f32> {
+  let d = p1 - p0;
+  let len2 = d.norm_squared();
+  if len2 <= f32::EPSILON {
+    return *p0;
+  }
+  let t = ((q - p0).dot(&d) / len2).clamp(0.0, 1.0);
+  p0 + d * t
+}
+
+// 两条线段之间的最近点对
+fn closest_between_segments(p1: &Vector3<f32>, q1: &Vector3<f32>, p2: &Vector3<f32>, q2: &Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+  let d1 = q1 - p1;
+  let d2 = q2 - p2;
+  let r = p1 - p2;
+  let a = d1.norm_squared();
+  let e = d2.norm_squared();
+  let f = d2.dot(&r);
+  if a <= f32::EPSILON && e <= f32::EPSILON {
+    return (*p1, *p2);
+  }
+  let (s, t) = if a <= f32::EPSILON {
+    (0.0, (f / e).clamp(0.0, 1.0))
+  } else {
+    let c = d1.dot(&r);
+    if e <= f32::EPSILON {
+      ((-c / a).clamp(0.0, 1.0), 0.0)
+    } else {
+      let b = d1.dot(&d2);
+      let denom = a * e - b * b;
+      let mut s = if denom > f32::EPSILON { ((b * f - c * e) / denom).clamp(0.0, 1.0) } else { 0.0 };
+      let mut t = (b * s + f) / e;
+      if t < 0.0 {
+        t = 0.0;
+        s = (-c / a).clamp(0.0, 1.0);
+      } else if t > 1.0 {
+        t = 1.0;
+        s = ((b - c) / a).clamp(0.0, 1.0);
+      }
+      (s, t)
+    }
+  };
+  (p1 + d1 * s, p2 + d2 * t)
+}
+
+#[cfg(test)]
+mod tests {
+  use nalgebra::UnitQuaternion;
+
+  use super::*;
+  use crate::physics::body::BodyType;
+
+  fn cube(position: Vector3<f32>, rotation: UnitQuaternion<f32>) -> RigidBody {
+    RigidBody::new(BodyType::Dynamic, Collider::cuboid(100.0, 100.0, 100.0), position).with_rotation(rotation)
+  }
+
+  #[test]
+  fn box_box_face_contact() {
+    let a = cube(Vector3::zeros(), UnitQuaternion::identity());
+    let b = cube(Vector3::new(10.0, 90.0, 0.0), UnitQuaternion::identity());
+    let contacts = collide(0, &a, 1, &b);
+    assert!(!contacts.is_empty());
+    for c in contacts.iter() {
+      assert!((c.normal - Vector3::y()).norm() < 1e-6);
+      assert!((c.depth - 10.0).abs() < 1e-4);
+      // 接触点在重叠区域内
+      assert!(c.point.y >= 40.0 - 1e-4 && c.point.y <= 50.0 + 1e-4);
+    }
+
+    // 交换顺序时法线反向，深度不变
+    let flipped = collide(0, &b, 1, &a);
+    assert!(flipped.iter().all(|c| (c.normal + Vector3::y()).norm() < 1e-6 && (c.depth - 10.0).abs() < 1e-4));
+  }
+
+  #[test]
+  fn box_box_rotated_and_separated() {
+    // 绕 y 轴旋转 45° 的盒子从 +x 方向压在 a 的侧面上：对角线半长 50√2
+    let rotation = UnitQuaternion::from_euler_angles(0.0, std::f32::consts::FRAC_PI_4, 0.0);
+    let x = 50.0 + 50.0 * std::f32::consts::SQRT_2 - 3.0;
+    let a = cube(Vector3::zeros(), UnitQuaternion::identity());
+    let b = cube(Vector3::new(x, 0.0, 0.0), rotation);
+    let contacts = collide(0, &a, 1, &b);
+    assert!(!contacts.is_empty());
+    for c in contacts.iter() {
+      assert!((c.normal - Vector3::x()).norm() < 1e-5);
+      assert!((c.depth - 3.0).abs() < 1e-3);
+    }
+
+    let b = cube(Vector3::new(x + 4.0, 0.0, 0.0), rotation);
+    assert!(collide(0, &a, 1, &b).is_empty());
+  }
+}
@@ -0,0 +1,121 @@
+use nalgebra::Vector3;
+
+use super::body::RigidBody;
+use super::narrow_phase::Contact;
+
+// 位置修正系数（Baumgarte）
+const BAUMGARTE: f32 = 0.2;
+// 允许的穿透深度，小于它不做位置修正，避免静止物体抖动
+const SLOP: f32 = 0.5;
+// 相对速度小于它时不反弹
+const RESTITUTION_THRESHOLD: f32 = 50.0;
+
+// 求解过程中每个接触点的缓存
+struct ContactConstraint {
+  a: usize,
+  b: usize,
+  normal: Vector3<f32>,
+  tangents: [Vector3<f32>; 2],
+  ra: Vector3<f32>, // 接触点相对刚体 a 质心
+  rb: Vector3<f32>,
+  normal_mass: f32, // 法线方向的有效质量
+  tangent_mass: [f32; 2],
+  bias: f32, // 目标分离速度（位置修正和反弹）
+  friction: f32,
+  normal_impulse: f32, // 累计冲量
+  tangent_impulse: [f32; 2],
+}
+
+// 与 n 正交的两个单位向量
+fn tangent_basis(n: &Vector3<f32>) -> [Vector3<f32>; 2] {
+  let helper = if n.x.abs() < 0.57 { Vector3::x() } else { Vector3::y() };
+  let t1 = n.cross(&helper).normalize();
+  [t1, n.cross(&t1)]
+}
+
+fn effective_mass(a: &RigidBody, b: &RigidBody, ra: &Vector3<f32>, rb: &Vector3<f32>, dir: &Vector3<f32>) -> f32 {
+  let ka = (a.inv_inertia_world() * ra.cross(dir)).cross(ra);
+  let kb = (b.inv_inertia_world() * rb.cross(dir)).cross(rb);
+  let k = a.inv_mass() + b.inv_mass() + dir.dot(&(ka + kb));
+  if k > 0.0 { 1.0 / k } else { 0.0 }
+}
+
+// 对两个刚体施加大小相反的冲量，b 受到 +impulse
+fn apply(bodies: &mut [RigidBody], c: &ContactConstraint, impulse: Vector3<f32>) {
+  let pa = bodies[c.a].position + c.ra;
+  let pb = bodies[c.b].position + c.rb;
+  bodies[c.a].apply_impulse(&-impulse, &pa);
+  bodies[c.b].apply_impulse(&impulse, &pb);
+}
+
+fn relative_velocity(bodies: &[RigidBody], c: &ContactConstraint) -> Vector3<f32> {
+  let a = &bodies[c.a];
+  let b = &bodies[c.b];
+  (b.linear_velocity + b.angular_velocity.cross(&c.rb)) - (a.linear_velocity + a.angular_velocity.cross(&c.ra))
+}
+
+/// 顺序冲量法求解接触：法线方向不穿透、可反弹，切线方向按库仑摩擦限制
+///
+/// 接触中已有的累计冲量会先施加一次（预热），求解后的累计冲量写回 `contacts`。
+/// 接触按输入顺序依次求解，相同输入总是得到相同结果。
+pub fn solve_contacts(bodies: &mut [RigidBody], contacts: &mut [Contact], dt: f32, iterations: usize) {
+  if dt <= 0.0 {
+    return
+  }
+  let mut constraints: Vec<ContactConstraint> = contacts.iter().map(|contact| {
+    let a = &bodies[contact.a];
+    let b = &bodies[contact.b];
+    let ra = contact.point - a.position;
+    let rb = contact.point - b.position;
+    let n = contact.normal;
+    let tangents = tangent_basis(&n);
+    let vn = (b.velocity_at(&contact.point) - a.velocity_at(&contact.point)).dot(&n);
+    let restitution = a.restitution.max(b.restitution);
+    let bounce = if vn < -RESTITUTION_THRESHOLD { -restitution * vn } else { 0.0 };
+    let correction = BAUMGARTE / dt * (contact.depth - SLOP).max(0.0);
+    ContactConstraint {
+      a: contact.a,
+      b: contact.b,
+      normal: n,
+      tangents,
+      ra,
+      rb,
+      normal_mass: effective_mass(a, b, &ra, &rb, &n),
+      tangent_mass: tangents.map(|t| effective_mass(a, b, &ra, &rb, &t)),
+      bias: bounce.max(correction),
+      friction: (a.friction * b.friction).sqrt(),
+      normal_impulse: contact.normal_impulse,
+      tangent_impulse: contact.tangent_impulse,
+    }
+  }).collect();
+
+  for c in constraints.iter() {
+    let impulse = c.normal * c.normal_impulse + c.tangents[0] * c.tangent_impulse[0] + c.tangents[1] * c.tangent_impulse[1];
+    apply(bodies, c, impulse);
+  }
+
+  for _ in 0..iterations {
+    for c in constraints.iter_mut() {
+      // 摩擦
+      for i in 0..2 {
+        let vt = relative_velocity(bodies, c).dot(&c.tangents[i]);
+        let limit = c.friction * c.normal_impulse;
+        let old = c.tangent_impulse[i];
+        c.tangent_impulse[i] = (old - vt * c.tangent_mass[i]).clamp(-limit, limit);
+        let delta = c.tangent_impulse[i] - old;
+        apply(bodies, c, c.tangents[i] * delta);
+      }
+      // 法线
+      let vn = relative_velocity(bodies, c).dot(&c.normal);
+      let old = c.normal_impulse;
+      c.normal_impulse = (old + (c.bias - vn) * c.normal_mass).max(0.0);
+      let delta = c.normal_impulse - old;
+      apply(bodies, c, c.normal * delta);
+    }
+  }
+
+  for (contact, c) in contacts.iter_mut().zip(constraints.iter()) {
+    contact.normal_impulse = c.normal_impulse;
+    contact.tangent_impulse = c.tangent_impulse;
+  }
+}
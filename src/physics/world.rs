@@ -0,0 +1,232 @@
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::scene::model::{Element, Scene};
+
+use super::body::{BodyType, RigidBody};
+use super::broad_phase::find_pairs;
+use super::collider::Collider;
+use super::narrow_phase::{collide, Contact};
+use super::solver::solve_contacts;
+
+// 固定步长（秒）
+const FIXED_DT: f32 = 1.0 / 60.0;
+// 一帧最多执行的步数，卡顿时丢弃多余的时间，避免越算越慢
+const MAX_STEPS_PER_UPDATE: usize = 5;
+// 场景坐标的单位接近厘米，重力取 9.8 m/s²
+const GRAVITY: f32 = -980.0;
+// 由场景生成地面时地面盒子的厚度和向外扩展的距离
+const GROUND_THICKNESS: f32 = 100.0;
+const GROUND_MARGIN: f32 = 2000.0;
+// 新旧接触点距离小于它时视为同一个接触，沿用上一步的冲量
+const WARM_START_DISTANCE: f32 = 2.0;
+
+/// 物理世界，按固定步长推进，结果只取决于输入和步数
+pub struct PhysicsWorld {
+  pub bodies: Vec<RigidBody>,
+  pub gravity: Vector3<f32>,
+  pub iterations: usize, // 每步求解接触的迭代次数
+  accumulator: f32, // 还没有模拟的时间
+  contacts: Vec<Contact>, // 上一步的接触，用于调试绘制
+}
+
+impl Default for PhysicsWorld {
+  fn default() -> Self {
+    Self {
+      bodies: vec![],
+      gravity: Vector3::new(0.0, GRAVITY, 0.0),
+      iterations: 10,
+      accumulator: 0.0,
+      contacts: vec![],
+    }
+  }
+}
+
+impl PhysicsWorld {
+  /// 添加刚体，返回刚体下标
+  pub fn add_body(&mut self, body: RigidBody) -> usize {
+    self.bodies.push(body);
+    self.bodies.len() - 1
+  }
+
+  pub fn contacts(&self) -> &[Contact] {
+    &self.contacts
+  }
+
+  /// 由场景生成物理世界：每个元素是一个动态盒子（质量与体积成正比），所有元素下方加一块静态地面
+  pub fn from_scene(scene: &Scene) -> Self {
+    let mut world = Self::default();
+    for el in scene.elements.iter() {
+      world.add_body(element_body(el, BodyType::Dynamic));
+    }
+    if let Some(ground) = ground_body(scene) {
+      world.add_body(ground);
+    }
+    world
+  }
+
+  /// 把刚体的位置和旋转写回对应的场景元素
+  pub fn sync_to_scene(&self, scene: &mut Scene) {
+    for body in self.bodies.iter() {
+      let Some(el) = body.element.and_then(|id| scene.element_mut(id)) else {
+        continue
+      };
+      let (rx, ry, rz) = body.rotation.euler_angles();
+      el.transform.position = body.position.into();
+      el.transform.rotation = [rx.to_degrees(), ry.to_degrees(), rz.to_degrees()];
+    }
+  }
+
+  /// 累计 `dt` 并按固定步长推进，返回本次执行的步数
+  pub fn update(&mut self, dt: f32) -> usize {
+    self.accumulator += dt.max(0.0);
+    let mut steps = 0;
+    while self.accumulator >= FIXED_DT && steps < MAX_STEPS_PER_UPDATE {
+      self.step(FIXED_DT);
+      self.accumulator -= FIXED_DT;
+      steps += 1;
+    }
+    if steps == MAX_STEPS_PER_UPDATE {
+      self.accumulator = self.accumulator.min(FIXED_DT);
+    }
+    steps
+  }
+
+  /// 推进一步：施加重力、检测碰撞、求解接触、更新位置
+  pub fn step(&mut self, dt: f32) {
+    for body in self.bodies.iter_mut().filter(|b| b.is_dynamic()) {
+      body.linear_velocity += self.gravity * dt;
+      body.linear_velocity /= 1.0 + dt * body.linear_damping;
+      body.angular_velocity /= 1.0 + dt * body.angular_damping;
+    }
+
+    let mut contacts: Vec<Contact> = find_pairs(&self.bodies).into_iter()
+      .flat_map(|(a, b)| collide(a, &self.bodies[a], b, &self.bodies[b]))
+      .collect();
+    for contact in contacts.iter_mut() {
+      let old = self.contacts.iter().find(|c| {
+        c.a == contact.a && c.b == contact.b && (c.point - contact.point).norm() < WARM_START_DISTANCE && c.normal.dot(&contact.normal) > 0.99
+      });
+      if let Some(old) = old {
+        contact.normal_impulse = old.normal_impulse;
+        contact.tangent_impulse = old.tangent_impulse;
+      }
+    }
+    solve_contacts(&mut self.bodies, &mut contacts, dt, self.iterations);
+    self.contacts = contacts;
+
+    for body in self.bodies.iter_mut() {
+      body.integrate(dt);
+    }
+  }
+}
+
+// 元素对应的盒子刚体，动态刚体的质量与体积成正比
+fn element_body(el: &Element, body_type: BodyType) -> RigidBody {
+  let size = el.shape.size().component_mul(&Vector3::from(el.transform.scale)).abs();
+  let [rx, ry, rz] = el.transform.rotation;
+  let rotation = UnitQuaternion::from_euler_angles(rx.to_radians(), ry.to_radians(), rz.to_radians());
+  let mass = (size.x * size.y * size.z / 1.0e6).max(0.001);
+  RigidBody::new(body_type, Collider::cuboid(size.x, size.y, size.z), Vector3::from(el.transform.position))
+    .with_rotation(rotation)
+    .with_mass(mass)
+    .with_element(el.id)
+}
+
+// 所有元素下方的一块静态地面
+fn ground_body(scene: &Scene) -> Option<RigidBody> {
+  let (min, max) = scene.bounds()?;
+  let extent = max - min + Vector3::repeat(GROUND_MARGIN * 2.0);
+  let center = Vector3::new((min.x + max.x) / 2.0, min.y - GROUND_THICKNESS / 2.0, (min.z + max.z) / 2.0);
+  Some(RigidBody::new(BodyType::Static, Collider::cuboid(extent.x, GROUND_THICKNESS, extent.z), center))
+}
+
+/// 场景的静态碰撞体：每个元素一个静态盒子，加上地面，供角色控制器使用
+pub fn static_colliders(scene: &Scene) -> Vec<RigidBody> {
+  let mut bodies: Vec<RigidBody> = scene.elements.iter().map(|el| element_body(el, BodyType::Static)).collect();
+  bodies.extend(ground_body(scene));
+  bodies
+}
+
+/// 按元素当前的变换更新 `static_colliders` 生成的碰撞体，只处理 `ids` 中的元素，地面不变
+pub fn update_colliders(scene: &Scene, colliders: &mut [RigidBody], ids: &[u32]) {
+  for body in colliders.iter_mut() {
+    let Some(el) = body.element.filter(|id| ids.contains(id)).and_then(|id| scene.element(id)) else {
+      continue
+    };
+    *body = element_body(el, body.body_type);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::scene::model::{Shape, Transform};
+
+  fn cube(id: u32, transform: Transform) -> Element {
+    Element { id, name: String::new(), shape: Shape::Cube { size: [100.0; 3] }, transform, material: String::new() }
+  }
+
+  fn body_bits(body: &RigidBody) -> Vec<u32> {
+    body.position.iter().chain(body.rotation.coords.iter()).chain(body.linear_velocity.iter()).chain(body.angular_velocity.iter())
+      .map(|v| v.to_bits())
+      .collect()
+  }
+
+  #[test]
+  fn resting_box_does_not_sink() {
+    let mut scene = Scene::new("test");
+    // 盒子底面正好在 y = 0，地面顶面也在 y = 0
+    scene.elements.push(cube(1, Transform::at(0.0, 50.0, 0.0)));
+    let mut world = PhysicsWorld::from_scene(&scene);
+    for _ in 0..600 {
+      world.step(FIXED_DT);
+    }
+    let body = &world.bodies[0];
+    // 位置修正只在超过 SLOP 时生效，下沉不超过它
+    assert!(body.position.y > 49.0, "y = {}", body.position.y);
+    assert!(body.position.y < 51.0, "y = {}", body.position.y);
+    assert!(body.linear_velocity.norm() < 1.0, "v = {}", body.linear_velocity.norm());
+    assert!(body.rotation.angle() < 1e-3);
+  }
+
+  #[test]
+  fn update_colliders_only_touches_listed_elements() {
+    let mut scene = Scene::new("test");
+    scene.elements.push(cube(1, Transform::at(0.0, 50.0, 0.0)));
+    scene.elements.push(cube(2, Transform::at(300.0, 50.0, 0.0)));
+    let mut colliders = static_colliders(&scene);
+    let ground = colliders[2].position;
+
+    scene.elements[0].transform = Transform { scale: [2.0; 3], ..Transform::at(0.0, 80.0, 0.0) };
+    scene.elements[1].transform.position = [0.0; 3];
+    update_colliders(&scene, &mut colliders, &[1]);
+    assert_eq!(colliders[0].position, Vector3::new(0.0, 80.0, 0.0));
+    assert_eq!(colliders[0].collider, Collider::cuboid(200.0, 200.0, 200.0));
+    assert_eq!(colliders[0].body_type, BodyType::Static);
+    assert_eq!(colliders[1].position, Vector3::new(300.0, 50.0, 0.0));
+    assert_eq!(colliders[2].position, ground);
+  }
+
+  #[test]
+  fn identical_runs_are_bit_identical() {
+    let build = || {
+      let mut scene = Scene::new("test");
+      for i in 0..6 {
+        let mut transform = Transform::at((i % 2) as f32 * 30.0, 60.0 + i as f32 * 110.0, (i % 3) as f32 * 20.0);
+        transform.rotation = [i as f32 * 7.0, i as f32 * 13.0, 0.0];
+        scene.elements.push(cube(i + 1, transform));
+      }
+      PhysicsWorld::from_scene(&scene)
+    };
+    let mut a = build();
+    let mut b = build();
+    for _ in 0..300 {
+      a.update(FIXED_DT);
+      b.update(FIXED_DT);
+    }
+    assert!(!a.contacts().is_empty());
+    for (x, y) in a.bodies.iter().zip(b.bodies.iter()) {
+      assert_eq!(body_bits(x), body_bits(y));
+    }
+  }
+}
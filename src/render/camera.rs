@@ -1,8 +1,11 @@
 use std::f32::consts::{FRAC_PI_2, PI};
 
-use nalgebra::{Matrix4, Point3, Unit, UnitQuaternion, Vector3};
+use nalgebra::{Matrix4, Point3, Unit, UnitQuaternion, Vector3, Vector4};
 use wgpu::*;
 
+use crate::physics::body::RigidBody;
+use crate::physics::character::CharacterController;
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct CameraUniform {
@@ -24,6 +27,16 @@ pub enum CameraMove {
   None,  
 }
 
+/// 相机移动方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+  Fly, // 自由飞行，不受重力和碰撞影响
+  Walk, // 第一人称行走，胶囊体受重力并与场景碰撞
+}
+
+// 行走速度（每秒）
+const WALK_SPEED: f32 = 300.0;
+
 pub struct Camera {
   active_status: bool, // 相机是否激活
   position: Vector3<f32>, // 相机位置
@@ -45,6 +58,9 @@ pub struct Camera {
   pub is_right: bool,
   pub is_up: bool,
   pub is_down: bool,
+  mode: CameraMode,
+  walker: CharacterController, // 行走模式下的角色
+  colliders: Vec<RigidBody>, // 行走模式下碰撞的场景几何
 }
 
 impl Camera {
@@ -70,6 +86,9 @@ impl Camera {
       is_right: false,
       is_up: false,
       is_down: false,
+      mode: CameraMode::Fly,
+      walker: CharacterController::default(),
+      colliders: vec![],
     }
   }
   
@@ -107,11 +126,46 @@ impl Camera {
     Matrix4::new_perspective(1.0, self.fov, self.near, self.far)
   } 
 
+  // 投影、视图、模型矩阵相乘，世界坐标直接转换到裁剪空间
+  pub fn pvm_matrix(&self) -> Matrix4<f32> {
+    self.projection_matrix() * self.view_matrix() * self.model_matrix()
+  }
+
+  /// 世界坐标转换为屏幕像素坐标（左上角为原点），不在视野内时返回 None
+  pub fn world_to_screen(&self, p: Vector3<f32>) -> Option<(f32, f32)> {
+    let clip = self.pvm_matrix() * p.push(1.0);
+    if clip.w <= 0.0 {
+      return None;
+    }
+    let ndc = clip.xyz() / clip.w;
+    if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 || ndc.z < 0.0 || ndc.z > 1.0 {
+      return None;
+    }
+    Some((
+      (ndc.x + 1.0) / 2.0 * self.screen_width,
+      (1.0 - ndc.y) / 2.0 * self.screen_height,
+    ))
+  }
+
+  /// 屏幕像素坐标（左上角为原点）对应的世界空间射线，返回起点和单位方向，相机矩阵不可逆时返回 None
+  pub fn screen_ray(&self, x: f32, y: f32) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let inverse = self.pvm_matrix().try_inverse()?;
+    let ndc_x = x / self.screen_width * 2.0 - 1.0;
+    let ndc_y = 1.0 - y / self.screen_height * 2.0;
+    let unproject = |z: f32| {
+      let p = inverse * Vector4::new(ndc_x, ndc_y, z, 1.0);
+      (p.w.abs() > f32::EPSILON).then(|| p.xyz() / p.w)
+    };
+    // 近裁剪面和远裁剪面上的两个点
+    let near = unproject(-1.0)?;
+    let far = unproject(1.0)?;
+    Some((near, (far - near).try_normalize(f32::EPSILON)?))
+  }
+
   pub fn uniform_obj(&self) -> CameraUniform  {
-    let proj = self.projection_matrix();
     let view = self.view_matrix();
     let model = self.model_matrix();
-    let pvm = proj * view * model;
+    let pvm = self.pvm_matrix();
     // let pvm = Matrix4::new(
     //   -0.0021650565, 0.0, 3.2413034e-6, 3.2348273e-6, 
     //   0.0, 0.003849002, 0.0, 0.0, 
@@ -136,6 +190,11 @@ impl Camera {
 
   }
 
+  /// 相机矩阵左乘 `offset` 后的 uniform，分块渲染时把画面的一部分放大到整个视口
+  pub fn offset_uniform_obj(&self, offset: Matrix4<f32>) -> CameraUniform {
+    CameraUniform { proj: offset * self.pvm_matrix(), ..self.uniform_obj() }
+  }
+
   pub fn set_screen_size(&mut self, screen_width: f32, screen_height: f32) {
     self.screen_width = screen_width;
     self.screen_height = screen_height;
@@ -145,6 +204,84 @@ impl Camera {
     self.active_status = status;
   }
 
+  /// 设置相机位置和朝向（弧度）
+  pub fn set_pose(&mut self, position: Vector3<f32>, yaw: f32, pitch: f32) {
+    self.position = position;
+    self.yaw = yaw;
+    self.pitch = pitch.clamp(-FRAC_PI_2 + 0.1, FRAC_PI_2 - 0.1);
+    let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+    let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+    self.target = Vector3::new(pitch_cos * yaw_sin, pitch_sin, pitch_cos * yaw_cos).normalize();
+  }
+
+  /// 设置视场角（弧度）和裁剪面
+  pub fn set_projection(&mut self, fov: f32, near: f32, far: f32) {
+    self.fov = fov;
+    self.near = near;
+    self.far = far;
+  }
+
+  pub fn position(&self) -> Vector3<f32> {
+    self.position
+  }
+
+  pub fn yaw(&self) -> f32 {
+    self.yaw
+  }
+
+  pub fn pitch(&self) -> f32 {
+    self.pitch
+  }
+
+  pub fn fov(&self) -> f32 {
+    self.fov
+  }
+
+  pub fn near(&self) -> f32 {
+    self.near
+  }
+
+  pub fn far(&self) -> f32 {
+    self.far
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.active_status
+  }
+
+  pub fn set_speed(&mut self, speed: f32) {
+    self.speed = speed;
+  }
+
+  pub fn mode(&self) -> CameraMode {
+    self.mode
+  }
+
+  /// 切换移动方式，进入行走模式时角色的眼睛放在当前相机位置
+  pub fn set_mode(&mut self, mode: CameraMode) {
+    if mode == CameraMode::Walk && self.mode != mode {
+      self.walker.set_eye(self.position);
+    }
+    self.mode = mode;
+  }
+
+  pub fn toggle_mode(&mut self) {
+    match self.mode {
+      CameraMode::Fly => self.set_mode(CameraMode::Walk),
+      CameraMode::Walk => self.set_mode(CameraMode::Fly),
+    }
+  }
+
+  /// 设置行走模式下碰撞的场景几何
+  pub fn set_colliders(&mut self, colliders: Vec<RigidBody>) {
+    self.colliders = colliders;
+  }
+
+  /// 行走模式下碰撞的场景几何，场景中的元素移动后用它更新对应的碰撞体
+  pub fn colliders_mut(&mut self) -> &mut [RigidBody] {
+    &mut self.colliders
+  }
+
   pub fn update(&mut self, dt: f32) {
     if !self.active_status { // 如果相机未激活，则不进行移动
       return
@@ -166,9 +303,22 @@ impl Camera {
     let up_amount = if self.is_up {1.0f32} else {0.0f32};
     let down_amount = if self.is_down {1.0f32} else {0.0f32};
 
-    self.position += forward * (forward_amount - backward_amount) * self.speed;
-    self.position += right * (right_amount - left_amount) * self.speed;
-    self.position += self.up * (up_amount - down_amount) * self.speed;
+    match self.mode {
+      CameraMode::Fly => {
+        self.position += forward * (forward_amount - backward_amount) * self.speed;
+        self.position += right * (right_amount - left_amount) * self.speed;
+        self.position += self.up * (up_amount - down_amount) * self.speed;
+      }
+      CameraMode::Walk => {
+        // 向上键起跳，向下键不起作用
+        let mut walk = forward * (forward_amount - backward_amount) + right * (right_amount - left_amount);
+        if walk.norm_squared() > 0.0 {
+          walk = walk.normalize() * WALK_SPEED * dt;
+        }
+        self.walker.update(walk, self.is_up, dt, &self.colliders);
+        self.position = self.walker.eye();
+      }
+    }
     // println!("Camera::update: {:?}， {:?}", &self.position, &self.yaw);
     let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
 
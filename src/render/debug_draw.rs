@@ -0,0 +1,322 @@
+use std::f32::consts::PI;
+
+use nalgebra::{Matrix4, Vector3, Vector4};
+use wgpu::*;
+
+use crate::render::{pipeline::{create_state_pipeline, RenderState}, profiler::DrawStats, shader::checked, vertex::Vertex};
+
+// 画球体时每个圆环的分段数
+const SPHERE_SEGMENTS: usize = 24;
+
+// 一组调试图元（都是线段），保存剩余显示时间和是否深度测试
+struct DebugItem {
+  vertices: Vec<Vertex>,
+  remain: f32, // 剩余显示时间（秒），0 表示只显示一帧
+  depth_test: bool,
+}
+
+/// 即时模式的调试绘制
+///
+/// 每帧调用 `line`、`aabb`、`sphere` 等方法收集图元，渲染后调用 `end_frame` 清理过期的图元。
+/// 通过 `set_duration` 可以让之后添加的图元保留指定秒数，`set_depth_test` 控制是否被场景遮挡。
+pub struct DebugDraw {
+  items: Vec<DebugItem>,
+  duration: f32, // 之后添加的图元的显示时长
+  depth_test: bool, // 之后添加的图元是否深度测试
+}
+
+impl Default for DebugDraw {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl DebugDraw {
+  pub fn new() -> Self {
+    Self {
+      items: vec![],
+      duration: 0.0,
+      depth_test: true,
+    }
+  }
+
+  /// 设置之后添加的图元显示时长（秒），0 表示只显示当前帧
+  pub fn set_duration(&mut self, duration: f32) {
+    self.duration = duration.max(0.0);
+  }
+
+  /// 设置之后添加的图元是否进行深度测试
+  pub fn set_depth_test(&mut self, depth_test: bool) {
+    self.depth_test = depth_test;
+  }
+
+  fn push(&mut self, vertices: Vec<Vertex>) {
+    if vertices.is_empty() {
+      return
+    }
+    self.items.push(DebugItem {
+      vertices,
+      remain: self.duration,
+      depth_test: self.depth_test,
+    });
+  }
+
+  fn vertex(p: Vector3<f32>, color: [f32; 3]) -> Vertex {
+    Vertex { position: [p.x, p.y, p.z], color: [color[0], color[1], color[2], 1.0], tex_coords: [0.0, 0.0] }
+  }
+
+  /// 线段
+  pub fn line(&mut self, from: Vector3<f32>, to: Vector3<f32>, color: [f32; 3]) {
+    self.push(vec![Self::vertex(from, color), Self::vertex(to, color)]);
+  }
+
+  /// 轴对齐包围盒
+  pub fn aabb(&mut self, min: Vector3<f32>, max: Vector3<f32>, color: [f32; 3]) {
+    let corners = [
+      Vector3::new(min.x, min.y, min.z),
+      Vector3::new(max.x, min.y, min.z),
+      Vector3::new(max.x, max.y, min.z),
+      Vector3::new(min.x, max.y, min.z),
+      Vector3::new(min.x, min.y, max.z),
+      Vector3::new(max.x, min.y, max.z),
+      Vector3::new(max.x, max.y, max.z),
+      Vector3::new(min.x, max.y, max.z),
+    ];
+    self.push(Self::box_lines(&corners, color));
+  }
+
+  /// 球体，用三个互相垂直的圆环表示
+  pub fn sphere(&mut self, center: Vector3<f32>, radius: f32, color: [f32; 3]) {
+    let mut vertices = Vec::with_capacity(SPHERE_SEGMENTS * 6);
+    for i in 0..SPHERE_SEGMENTS {
+      let a0 = i as f32 / SPHERE_SEGMENTS as f32 * 2.0 * PI;
+      let a1 = (i + 1) as f32 / SPHERE_SEGMENTS as f32 * 2.0 * PI;
+      let (s0, c0) = a0.sin_cos();
+      let (s1, c1) = a1.sin_cos();
+      // xy 平面
+      vertices.push(Self::vertex(center + Vector3::new(c0, s0, 0.0) * radius, color));
+      vertices.push(Self::vertex(center + Vector3::new(c1, s1, 0.0) * radius, color));
+      // xz 平面
+      vertices.push(Self::vertex(center + Vector3::new(c0, 0.0, s0) * radius, color));
+      vertices.push(Self::vertex(center + Vector3::new(c1, 0.0, s1) * radius, color));
+      // yz 平面
+      vertices.push(Self::vertex(center + Vector3::new(0.0, c0, s0) * radius, color));
+      vertices.push(Self::vertex(center + Vector3::new(0.0, c1, s1) * radius, color));
+    }
+    self.push(vertices);
+  }
+
+  /// 视锥体
+  /// - `view_proj`: 相机的 proj * view * model 矩阵，见 `Camera::pvm_matrix`
+  pub fn frustum(&mut self, view_proj: &Matrix4<f32>, color: [f32; 3]) {
+    let Some(inv) = view_proj.try_inverse() else {
+      return
+    };
+    // 裁剪空间的八个角点，z 取值与 wgpu 的深度范围 [0, 1] 一致
+    let ndc = [
+      (-1.0, -1.0, 0.0), (1.0, -1.0, 0.0), (1.0, 1.0, 0.0), (-1.0, 1.0, 0.0),
+      (-1.0, -1.0, 1.0), (1.0, -1.0, 1.0), (1.0, 1.0, 1.0), (-1.0, 1.0, 1.0),
+    ];
+    let mut corners = [Vector3::zeros(); 8];
+    for (i, (x, y, z)) in ndc.iter().enumerate() {
+      let p = inv * Vector4::new(*x, *y, *z, 1.0);
+      if p.w.abs() < f32::EPSILON {
+        return
+      }
+      corners[i] = p.xyz() / p.w;
+    }
+    self.push(Self::box_lines(&corners, color));
+  }
+
+  /// 箭头，箭头头部长度为总长度的 20%
+  pub fn arrow(&mut self, from: Vector3<f32>, to: Vector3<f32>, color: [f32; 3]) {
+    let dir = to - from;
+    let len = dir.norm();
+    if len < f32::EPSILON {
+      return
+    }
+    let dir = dir / len;
+    // 找一个与方向不平行的向量构造垂直方向
+    let helper = if dir.y.abs() < 0.99 { Vector3::y() } else { Vector3::x() };
+    let side = dir.cross(&helper).normalize();
+    let up = dir.cross(&side);
+    let head = len * 0.2;
+    let base = to - dir * head;
+    let mut vertices = vec![Self::vertex(from, color), Self::vertex(to, color)];
+    for offset in [side, -side, up, -up] {
+      vertices.push(Self::vertex(to, color));
+      vertices.push(Self::vertex(base + offset * head * 0.5, color));
+    }
+    self.push(vertices);
+  }
+
+  /// 十字标记，沿三个坐标轴各画一条线
+  pub fn cross(&mut self, center: Vector3<f32>, size: f32, color: [f32; 3]) {
+    let h = size / 2.0;
+    self.push(vec![
+      Self::vertex(center - Vector3::x() * h, color),
+      Self::vertex(center + Vector3::x() * h, color),
+      Self::vertex(center - Vector3::y() * h, color),
+      Self::vertex(center + Vector3::y() * h, color),
+      Self::vertex(center - Vector3::z() * h, color),
+      Self::vertex(center + Vector3::z() * h, color),
+    ]);
+  }
+
+  // 8 个角点（前 4 个为一个面，后 4 个为对面）生成 12 条棱
+  fn box_lines(corners: &[Vector3<f32>; 8], color: [f32; 3]) -> Vec<Vertex> {
+    let edges = [
+      (0, 1), (1, 2), (2, 3), (3, 0),
+      (4, 5), (5, 6), (6, 7), (7, 4),
+      (0, 4), (1, 5), (2, 6), (3, 7),
+    ];
+    let mut vertices = Vec::with_capacity(24);
+    for (a, b) in edges {
+      vertices.push(Self::vertex(corners[a], color));
+      vertices.push(Self::vertex(corners[b], color));
+    }
+    vertices
+  }
+
+  /// 当前帧需要绘制的线段顶点，按是否深度测试分为两组
+  pub fn vertices(&self) -> (Vec<Vertex>, Vec<Vertex>) {
+    let mut depth = vec![];
+    let mut overlay = vec![];
+    for item in self.items.iter() {
+      if item.depth_test {
+        depth.extend_from_slice(&item.vertices);
+      } else {
+        overlay.extend_from_slice(&item.vertices);
+      }
+    }
+    (depth, overlay)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  /// 深度测试和不测试的线段各一次绘制调用，线段不计入三角形数
+  pub fn draw_stats(&self) -> DrawStats {
+    let depth = self.items.iter().any(|item| item.depth_test);
+    let overlay = self.items.iter().any(|item| !item.depth_test);
+    DrawStats { draw_calls: depth as u32 + overlay as u32, triangles: 0 }
+  }
+
+  /// 一帧渲染结束后调用，扣除显示时间并移除过期的图元
+  pub fn end_frame(&mut self, dt: f32) {
+    self.items.retain_mut(|item| {
+      item.remain -= dt;
+      item.remain > 0.0
+    });
+  }
+
+  /// 清除全部图元
+  pub fn clear(&mut self) {
+    self.items.clear();
+  }
+}
+
+/// 调试图元的 GPU 渲染器
+///
+/// 只依赖颜色和深度纹理视图，窗口表面和离屏纹理都可以作为绘制目标。
+pub struct DebugRenderer {
+  depth_pipeline: RenderPipeline,
+  overlay_pipeline: RenderPipeline,
+  vertex_buffer: Buffer,
+  capacity: u64, // 顶点缓冲区大小（字节）
+}
+
+impl DebugRenderer {
+  pub fn new(device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, source: &str) -> Self {
+    let capacity = (std::mem::size_of::<Vertex>() * 1024) as u64;
+    Self {
+      depth_pipeline: create_state_pipeline(device, texture_format, &[bind_group_layout], source, &RenderState::lines(true), None),
+      overlay_pipeline: create_state_pipeline(device, texture_format, &[bind_group_layout], source, &RenderState::lines(false), None),
+      vertex_buffer: Self::create_buffer(device, capacity),
+      capacity,
+    }
+  }
+
+  /// 用新的着色器源码重建管线，失败时保留原来的管线
+  pub fn rebuild_pipeline(&mut self, device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, source: &str) -> Result<(), String> {
+    let (depth_pipeline, overlay_pipeline) = checked(device, || (
+      create_state_pipeline(device, texture_format, &[bind_group_layout], source, &RenderState::lines(true), None),
+      create_state_pipeline(device, texture_format, &[bind_group_layout], source, &RenderState::lines(false), None),
+    ))?;
+    self.depth_pipeline = depth_pipeline;
+    self.overlay_pipeline = overlay_pipeline;
+    Ok(())
+  }
+
+  fn create_buffer(device: &Device, size: u64) -> Buffer {
+    device.create_buffer(&BufferDescriptor {
+      label: Some("Debug Vertex Buffer"),
+      size,
+      usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    })
+  }
+
+  /// 在已有画面上叠加调试线段（不清除颜色和深度）
+  #[allow(clippy::too_many_arguments)]
+  pub fn draw(
+    &mut self,
+    device: &Device,
+    queue: &Queue,
+    encoder: &mut CommandEncoder,
+    view: &TextureView,
+    depth_view: &TextureView,
+    bind_group: &BindGroup,
+    debug_draw: &DebugDraw,
+  ) {
+    if debug_draw.is_empty() {
+      return
+    }
+    let (depth, overlay) = debug_draw.vertices();
+    let depth_len = depth.len() as u32;
+    let overlay_len = overlay.len() as u32;
+    let mut vertex_list = depth;
+    vertex_list.extend(overlay);
+
+    // 缓冲区不够时按 2 倍扩容
+    let size = std::mem::size_of_val(vertex_list.as_slice()) as u64;
+    if size > self.capacity {
+      self.capacity = size.next_power_of_two();
+      self.vertex_buffer = Self::create_buffer(device, self.capacity);
+    }
+    queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertex_list));
+
+    let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("Debug Draw Pass"),
+      depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+        view: depth_view,
+        depth_ops: Some(Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        }),
+        stencil_ops: None,
+      }),
+      timestamp_writes: None,
+      occlusion_query_set: None,
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        },
+      })]
+    });
+    r_pass.set_bind_group(0, bind_group, &[]);
+    r_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    if depth_len > 0 {
+      r_pass.set_pipeline(&self.depth_pipeline);
+      r_pass.draw(0..depth_len, 0..1);
+    }
+    if overlay_len > 0 {
+      r_pass.set_pipeline(&self.overlay_pipeline);
+      r_pass.draw(depth_len..depth_len + overlay_len, 0..1);
+    }
+  }
+}
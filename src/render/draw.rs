@@ -1,85 +1,142 @@
-use wgpu::*;
+use std::ops::Range;
+
+use nalgebra::Vector3;
+use wgpu::{Buffer, BufferDescriptor, BufferUsages, Device};
+
+use super::pipeline::{BlendMode, RenderState};
+use super::transparent::TransparencyMode;
 
 use super::{camera::Camera, vertex::Vertex, wgpu_ctx::WgpuCtx};
 
-pub fn create_depth_texture(ctx: &mut WgpuCtx, label: &str) -> (wgpu::Texture, wgpu::TextureView) {
-  let depth_texture_desc = wgpu::TextureDescriptor {
-      label: Some(label),
-      size: wgpu::Extent3d {
-          width: ctx.vw,
-          height: ctx.vh,
-          depth_or_array_layers: 1,
-      },
-      mip_level_count: 1,
-      sample_count: 1,
-      dimension: wgpu::TextureDimension::D2,
-      format: wgpu::TextureFormat::Depth32Float,
-      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-      view_formats: &[],
+/// 主顶点列表中按同一渲染状态绘制的一段
+///
+/// 混合状态不是 `BlendMode::Opaque` 的批次在所有不透明物体之后绘制，按 `center` 到相机的距离从远到近排序。
+#[derive(Clone, Debug)]
+pub struct RenderBatch {
+  pub state: RenderState,
+  pub range: Range<u32>, // 顶点下标范围
+  pub center: Option<Vector3<f32>>, // 半透明批次排序用的中心点，None 时排在最后并保持添加顺序
+}
+
+impl RenderBatch {
+  pub fn new(state: RenderState, range: Range<u32>) -> Self {
+    Self { state, range, center: None }
+  }
+
+  pub fn is_transparent(&self) -> bool {
+    self.state.blend != BlendMode::Opaque
+  }
+}
+
+/// 排列本帧的分批：不透明批次在前，保持添加顺序
+///
+/// 按 `TransparencyMode::Sorted` 绘制时半透明批次按中心点到 `eye` 的距离从远到近排序，没有中心点的排在最后；
+/// 使用 OIT 时不需要排序，半透明批次保持添加顺序并改为对应的渲染状态。
+pub fn sort_batches(batches: &mut [RenderBatch], eye: Vector3<f32>, mode: TransparencyMode) {
+  let sort_key = |batch: &RenderBatch| match (batch.is_transparent(), batch.center, mode) {
+    (false, _, _) => (0, 0.0),
+    (true, _, TransparencyMode::WeightedOit) => (1, 0.0),
+    (true, Some(center), TransparencyMode::Sorted) => (1, -(center - eye).norm()),
+    (true, None, TransparencyMode::Sorted) => (2, 0.0),
   };
+  batches.sort_by(|a, b| {
+    let (a, b) = (sort_key(a), sort_key(b));
+    a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+  });
+  if mode == TransparencyMode::WeightedOit {
+    for batch in batches.iter_mut().filter(|b| b.is_transparent()) {
+      batch.state = batch.state.weighted_oit();
+    }
+  }
+}
+
+// 主顶点缓冲区的初始大小，1024 个顶点
+pub const INITIAL_VERTEX_CAPACITY: u64 = (std::mem::size_of::<Vertex>() * 1024) as u64;
 
-  let texture = ctx.device.create_texture(&depth_texture_desc);
-  let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-  (texture, view)
+pub fn create_vertex_buffer(device: &Device, size: u64) -> Buffer {
+  device.create_buffer(&BufferDescriptor {
+    label: Some("Vertex Buffer"),
+    size,
+    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  })
 }
 
+/// 写入 `size` 字节需要的缓冲区大小：够用时不变，不够时扩容到 2 的幂
+pub fn grow_capacity(capacity: u64, size: u64) -> u64 {
+  if size > capacity { size.next_power_of_two() } else { capacity }
+}
 
-// pub fn get_depth_view(ctx: &mut WgpuCtx) {
-//   let mut dept_view_desc = TextureViewDescriptor::default();
-//   dept_view_desc.format = Some(TextureFormat::Depth32Float);
-//   return  dept_view_desc;
-// }
+/// 写入本帧的主顶点列表，缓冲区不够时重新创建
 pub fn update_vertex_buffer(ctx: &mut WgpuCtx, vertex_list: Vec<Vertex>) {
+  let capacity = grow_capacity(ctx.vertex_capacity, std::mem::size_of_val(vertex_list.as_slice()) as u64);
+  if capacity != ctx.vertex_capacity {
+    ctx.vertex_capacity = capacity;
+    ctx.vertex_buffer = create_vertex_buffer(&ctx.device, capacity);
+  }
   ctx.queue.write_buffer(&ctx.vertex_buffer, 0, bytemuck::cast_slice(&vertex_list));
   ctx.vertex_len = vertex_list.len() as u32;
 }
 
 pub fn draw_ver(ctx: &mut WgpuCtx, vertex_list: Vec<Vertex>) {
-  ctx.queue.write_buffer(&ctx.vertex_buffer, 0, bytemuck::cast_slice(&vertex_list));
-  ctx.vertex_len = vertex_list.len() as u32;
-
-  let frame = ctx.surface.get_current_texture().unwrap();
-  // 设置纹理
-  let view = frame.texture.create_view(&TextureViewDescriptor::default());
-  // println!("WgpuCtx::draw: {:?}", view);
-  let mut encoder = ctx.device.create_command_encoder(&CommandEncoderDescriptor { label: None });
-  let depth_view = create_depth_texture(ctx, "depth_texture").1;
-  // 此处使用作用域，将pass限制在一定范围内，出作用域后会自动调用drop清理资源。
-  {
-    let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-      label: None,
-      depth_stencil_attachment: Some(RenderPassDepthStencilAttachment { 
-          view: &depth_view,
-          depth_ops: Some(Operations {
-            load: LoadOp::Clear(1.0),
-            store: StoreOp::Store,
-          }),
-          stencil_ops: None,
-        }),
-      timestamp_writes: None,
-      occlusion_query_set: None,
-      color_attachments: &[Some(RenderPassColorAttachment {
-        view: &view,
-        resolve_target: None,
-        ops: Operations {
-          load: LoadOp::Clear(Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 }),
-          store: StoreOp::Store,
-        },
-      })]
-    });
-    // println!("r_pass: {:#?}", &ctx.bind_group.into());
-    r_pass.set_pipeline(&ctx.render_pipeline);
-    r_pass.set_bind_group(0, &ctx.bind_group, &[]);
-    r_pass.set_vertex_buffer(0, ctx.vertex_buffer.slice(..));
-    r_pass.draw(0..vertex_list.len() as u32, 0..1);
-  }
-
-  // 上面的pass结束后，才能调用finish
-  ctx.queue.submit(Some(encoder.finish())); // 提交命令到GPU
-  frame.present(); // 替换当前帧画面，显示最新的图像
+  update_vertex_buffer(ctx, vertex_list);
+  ctx.draw();
 }
 
 pub fn update_camera(ctx: &mut WgpuCtx, dt:f32) {
   ctx.camera.update(dt);
   ctx.queue.write_buffer(&ctx.vertex_uniform_buffer, 0, bytemuck::cast_slice(&[ctx.camera.uniform_obj()]));
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 用顶点范围的起点区分批次
+  fn batch(start: u32, blend: BlendMode, center: Option<[f32; 3]>) -> RenderBatch {
+    let state = RenderState { blend, depth_write: blend == BlendMode::Opaque, ..RenderState::default() };
+    RenderBatch { state, range: start..start + 3, center: center.map(Vector3::from) }
+  }
+
+  fn starts(batches: &[RenderBatch]) -> Vec<u32> {
+    batches.iter().map(|b| b.range.start).collect()
+  }
+
+  fn frame() -> Vec<RenderBatch> {
+    vec![
+      batch(0, BlendMode::Alpha, Some([0.0, 0.0, -2.0])),
+      batch(1, BlendMode::Opaque, Some([0.0, 0.0, -100.0])),
+      batch(2, BlendMode::Additive, None),
+      batch(3, BlendMode::Premultiplied, Some([0.0, 0.0, -10.0])),
+      batch(4, BlendMode::Opaque, None),
+      batch(5, BlendMode::Alpha, Some([3.0, 4.0, 0.0])),
+    ]
+  }
+
+  #[test]
+  fn opaque_first_then_back_to_front() {
+    let mut batches = frame();
+    sort_batches(&mut batches, Vector3::zeros(), TransparencyMode::Sorted);
+    // 距离：0 为 2，3 为 10，5 为 5；2 没有中心点，排在最后
+    assert_eq!(starts(&batches), vec![1, 4, 3, 5, 0, 2]);
+    assert!(batches[..2].iter().all(|b| !b.is_transparent()));
+    assert!(batches[2..].iter().all(|b| b.is_transparent()));
+    assert_eq!(batches[2].state.blend, BlendMode::Premultiplied);
+
+    // 相机移动后顺序跟着改变
+    let mut batches = frame();
+    sort_batches(&mut batches, Vector3::new(0.0, 0.0, -12.0), TransparencyMode::Sorted);
+    assert_eq!(starts(&batches), vec![1, 4, 5, 0, 3, 2]);
+  }
+
+  #[test]
+  fn oit_keeps_order_and_switches_state() {
+    let mut batches = frame();
+    sort_batches(&mut batches, Vector3::zeros(), TransparencyMode::WeightedOit);
+    assert_eq!(starts(&batches), vec![1, 4, 0, 2, 3, 5]);
+    for b in batches.iter() {
+      let expected = if b.range.start == 1 || b.range.start == 4 { BlendMode::Opaque } else { BlendMode::WeightedOit };
+      assert_eq!(b.state.blend, expected);
+      assert_eq!(b.state.depth_write, expected == BlendMode::Opaque);
+    }
+  }
+}
@@ -0,0 +1,371 @@
+use wgpu::*;
+
+use super::profiler::GpuTimer;
+use super::targets::RenderTargets;
+
+/// 渲染图中的纹理，由 `RenderGraph::import` 或 `RenderGraph::create` 返回
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceId(usize);
+
+/// 临时纹理的描述，纹理从 `RenderTargets` 中取用，只在一次 `execute` 内属于这个渲染图
+///
+/// 用途不需要填写：被写入的纹理加上 `RENDER_ATTACHMENT`，被读取的加上 `TEXTURE_BINDING`，
+/// 其它用途（例如复制）放在 `usage` 中。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureDesc {
+  pub width: u32,
+  pub height: u32,
+  pub format: TextureFormat,
+  pub mip_level_count: u32,
+  pub sample_count: u32,
+  pub usage: TextureUsages, // 额外的用途
+}
+
+impl TextureDesc {
+  pub fn new(width: u32, height: u32, format: TextureFormat) -> Self {
+    Self { width, height, format, mip_level_count: 1, sample_count: 1, usage: TextureUsages::empty() }
+  }
+}
+
+enum Resource {
+  Imported { texture: Texture }, // 外部传入的纹理，例如交换链的当前帧
+  Transient { desc: TextureDesc, usage: TextureUsages },
+}
+
+struct ResourceEntry {
+  name: &'static str,
+  resource: Resource,
+}
+
+type PassFn<'a> = Box<dyn FnOnce(&mut CommandEncoder, &PassResources) + 'a>;
+
+struct PassNode<'a> {
+  name: &'static str,
+  reads: Vec<ResourceId>, // 作为纹理采样
+  writes: Vec<ResourceId>, // 作为渲染目标
+  execute: PassFn<'a>,
+}
+
+/// 执行 pass 时可以访问的纹理
+pub struct PassResources {
+  textures: Vec<Option<(Texture, TextureView)>>, // 按 ResourceId 下标，没有用到的临时纹理为 None
+}
+
+impl PassResources {
+  pub fn texture(&self, id: ResourceId) -> &Texture {
+    &self.textures[id.0].as_ref().expect("pass 没有声明使用这个纹理").0
+  }
+
+  pub fn view(&self, id: ResourceId) -> &TextureView {
+    &self.textures[id.0].as_ref().expect("pass 没有声明使用这个纹理").1
+  }
+}
+
+/// 一帧的渲染图：pass 声明读写的纹理，渲染图据此排序、分配临时纹理，并记录到同一个 CommandEncoder 中
+///
+/// 排序规则：
+/// - 写同一个纹理的 pass 按添加顺序执行，后面的 pass 一般用 `LoadOp::Load` 叠加在前面的结果上
+/// - 读取纹理的 pass 在所有写它的 pass 之后执行，与添加顺序无关
+///
+/// 生命周期不重叠、描述相同的临时纹理共用同一块显存，显存跨帧保存在 `RenderTargets` 中，描述不变时不重新创建。
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+  resources: Vec<ResourceEntry>,
+  passes: Vec<PassNode<'a>>,
+}
+
+/// 添加 pass 时声明读写的纹理，最后调用 `execute` 完成添加
+pub struct PassBuilder<'g, 'a> {
+  graph: &'g mut RenderGraph<'a>,
+  name: &'static str,
+  reads: Vec<ResourceId>,
+  writes: Vec<ResourceId>,
+}
+
+impl<'a> PassBuilder<'_, 'a> {
+  pub fn read(mut self, id: ResourceId) -> Self {
+    self.reads.push(id);
+    self
+  }
+
+  pub fn write(mut self, id: ResourceId) -> Self {
+    self.writes.push(id);
+    self
+  }
+
+  /// 设置记录命令的函数，按排序后的顺序调用
+  pub fn execute(self, f: impl FnOnce(&mut CommandEncoder, &PassResources) + 'a) {
+    self.graph.passes.push(PassNode { name: self.name, reads: self.reads, writes: self.writes, execute: Box::new(f) });
+  }
+}
+
+impl<'a> RenderGraph<'a> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// 导入外部纹理，例如交换链的当前帧
+  pub fn import(&mut self, name: &'static str, texture: &Texture) -> ResourceId {
+    self.resources.push(ResourceEntry { name, resource: Resource::Imported { texture: texture.clone() } });
+    ResourceId(self.resources.len() - 1)
+  }
+
+  /// 声明临时纹理，只有被 pass 用到时才会分配
+  pub fn create(&mut self, name: &'static str, desc: TextureDesc) -> ResourceId {
+    self.resources.push(ResourceEntry { name, resource: Resource::Transient { desc, usage: desc.usage } });
+    ResourceId(self.resources.len() - 1)
+  }
+
+  /// 按名称查找已经导入或声明的纹理，同名时返回最先添加的；用于在其它模块添加的 pass 中访问例如 `depth`
+  pub fn find(&self, name: &str) -> Option<ResourceId> {
+    self.resources.iter().position(|entry| entry.name == name).map(ResourceId)
+  }
+
+  pub fn add_pass(&mut self, name: &'static str) -> PassBuilder<'_, 'a> {
+    PassBuilder { graph: self, name, reads: vec![], writes: vec![] }
+  }
+
+  /// 按依赖关系排序，返回 pass 下标；有循环依赖或读写冲突时返回错误
+  fn order(&self) -> Result<Vec<usize>, String> {
+    let n = self.passes.len();
+    let mut edges: Vec<Vec<usize>> = vec![vec![]; n]; // edges[a] 中的 pass 要在 a 之后执行
+    let mut in_degree = vec![0; n];
+    for (id, entry) in self.resources.iter().enumerate() {
+      let id = ResourceId(id);
+      let writers: Vec<usize> = (0..n).filter(|&p| self.passes[p].writes.contains(&id)).collect();
+      let readers: Vec<usize> = (0..n).filter(|&p| self.passes[p].reads.contains(&id)).collect();
+      if let Some(p) = readers.iter().find(|p| writers.contains(p)) {
+        return Err(format!("pass {} 同时读写纹理 {}", self.passes[*p].name, entry.name));
+      }
+      if writers.is_empty() && matches!(entry.resource, Resource::Transient { .. }) {
+        if let Some(p) = readers.first() {
+          return Err(format!("pass {} 读取的临时纹理 {} 没有被写入", self.passes[*p].name, entry.name));
+        }
+      }
+      let mut add_edge = |a: usize, b: usize| {
+        if !edges[a].contains(&b) {
+          edges[a].push(b);
+          in_degree[b] += 1;
+        }
+      };
+      for pair in writers.windows(2) {
+        add_edge(pair[0], pair[1]);
+      }
+      for &w in writers.iter() {
+        for &r in readers.iter() {
+          add_edge(w, r);
+        }
+      }
+    }
+    // 同时可以执行的 pass 中先执行先添加的，结果稳定
+    let mut order = Vec::with_capacity(n);
+    let mut ready: Vec<usize> = (0..n).filter(|&p| in_degree[p] == 0).collect();
+    while let Some(i) = ready.iter().enumerate().min_by_key(|(_, p)| **p).map(|(i, _)| i) {
+      let p = ready.swap_remove(i);
+      order.push(p);
+      for &next in edges[p].iter() {
+        in_degree[next] -= 1;
+        if in_degree[next] == 0 {
+          ready.push(next);
+        }
+      }
+    }
+    if order.len() < n {
+      let names: Vec<&str> = (0..n).filter(|p| !order.contains(p)).map(|p| self.passes[p].name).collect();
+      return Err(format!("pass 之间有循环依赖：{}", names.join(", ")));
+    }
+    Ok(order)
+  }
+
+  /// 为临时纹理分配实际的纹理下标，返回每个资源对应的纹理下标和每个纹理的名称（第一个使用者）、描述
+  ///
+  /// 按执行顺序扫描，纹理在最后一次使用之后归还，之后描述相同的临时纹理可以复用。
+  fn allocate(&mut self, order: &[usize]) -> (Vec<Option<usize>>, Vec<(&'static str, TextureDesc)>) {
+    let mut first = vec![usize::MAX; self.resources.len()];
+    let mut last = vec![0; self.resources.len()];
+    for (step, &p) in order.iter().enumerate() {
+      let pass = &self.passes[p];
+      for (id, write) in pass.reads.iter().map(|id| (id, false)).chain(pass.writes.iter().map(|id| (id, true))) {
+        first[id.0] = first[id.0].min(step);
+        last[id.0] = last[id.0].max(step);
+        if let Resource::Transient { usage, .. } = &mut self.resources[id.0].resource {
+          *usage |= if write { TextureUsages::RENDER_ATTACHMENT } else { TextureUsages::TEXTURE_BINDING };
+        }
+      }
+    }
+
+    let mut slots = vec![None; self.resources.len()];
+    let mut textures: Vec<(&'static str, TextureDesc)> = vec![];
+    let mut free_after: Vec<usize> = vec![]; // 每个纹理当前使用者的最后一步
+    let mut transient: Vec<usize> = (0..self.resources.len())
+      .filter(|&id| first[id] != usize::MAX && matches!(self.resources[id].resource, Resource::Transient { .. }))
+      .collect();
+    transient.sort_by_key(|&id| first[id]);
+    for id in transient {
+      let ResourceEntry { name, resource: Resource::Transient { desc, usage } } = &self.resources[id] else {
+        continue
+      };
+      let desc = TextureDesc { usage: *usage, ..*desc };
+      let reuse = (0..textures.len()).find(|&t| textures[t].1 == desc && free_after[t] < first[id]);
+      let t = reuse.unwrap_or_else(|| {
+        textures.push((name, desc));
+        free_after.push(0);
+        textures.len() - 1
+      });
+      free_after[t] = last[id];
+      slots[id] = Some(t);
+    }
+    (slots, textures)
+  }
+
+  /// 排序、从 `targets` 中取用临时纹理，把所有 pass 记录到同一个 CommandEncoder 中并提交
+  pub fn execute(self, device: &Device, queue: &Queue, targets: &mut RenderTargets) -> Result<(), String> {
+    self.execute_timed(device, queue, targets, None)
+  }
+
+  /// 同 `execute`，`timer` 不为空时在每个 pass 前后写入时间戳
+  pub fn execute_timed(mut self, device: &Device, queue: &Queue, targets: &mut RenderTargets, mut timer: Option<&mut GpuTimer>) -> Result<(), String> {
+    let order = self.order()?;
+    let (slots, descs) = self.allocate(&order);
+    targets.begin();
+    let allocated: Vec<(Texture, TextureView)> = descs.iter().map(|&(name, desc)| targets.acquire(device, name, desc)).collect();
+    let textures = self.resources.iter().zip(slots.iter()).map(|(entry, slot)| match (&entry.resource, slot) {
+      (Resource::Imported { texture }, _) => Some((texture.clone(), texture.create_view(&TextureViewDescriptor::default()))),
+      (Resource::Transient { .. }, Some(t)) => Some(allocated[*t].clone()),
+      (Resource::Transient { .. }, None) => None,
+    }).collect();
+    let resources = PassResources { textures };
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Render Graph") });
+    let mut passes: Vec<Option<PassNode>> = self.passes.into_iter().map(Some).collect();
+    for p in order {
+      if let Some(pass) = passes[p].take() {
+        let query = timer.as_mut().and_then(|t| t.begin_pass(&mut encoder, pass.name));
+        (pass.execute)(&mut encoder, &resources);
+        if let (Some(timer), Some(query)) = (timer.as_mut(), query) {
+          timer.end_pass(&mut encoder, query);
+        }
+      }
+    }
+    if let Some(timer) = timer {
+      timer.resolve(&mut encoder);
+    }
+    queue.submit(Some(encoder.finish()));
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn desc() -> TextureDesc {
+    TextureDesc::new(64, 64, TextureFormat::Rgba8Unorm)
+  }
+
+  fn pass(graph: &mut RenderGraph, name: &'static str, reads: &[ResourceId], writes: &[ResourceId]) {
+    let mut builder = graph.add_pass(name);
+    for &id in reads {
+      builder = builder.read(id);
+    }
+    for &id in writes {
+      builder = builder.write(id);
+    }
+    builder.execute(|_, _| {});
+  }
+
+  fn names(graph: &RenderGraph, order: &[usize]) -> Vec<&'static str> {
+    order.iter().map(|&p| graph.passes[p].name).collect()
+  }
+
+  #[test]
+  fn writers_run_before_readers() {
+    // 按相反的顺序添加 pass
+    let mut graph = RenderGraph::new();
+    let color = graph.create("color", desc());
+    let bloom = graph.create("bloom", desc());
+    let output = graph.create("output", desc());
+    pass(&mut graph, "composite", &[color, bloom], &[output]);
+    pass(&mut graph, "bloom", &[color], &[bloom]);
+    pass(&mut graph, "overlay", &[], &[color]);
+    pass(&mut graph, "scene", &[], &[color]);
+    let order = graph.order().unwrap();
+    // 写同一个纹理的 pass 按添加顺序执行
+    assert_eq!(names(&graph, &order), vec!["overlay", "scene", "bloom", "composite"]);
+
+    // 没有依赖的 pass 保持添加顺序
+    let mut graph = RenderGraph::new();
+    let a = graph.create("a", desc());
+    let b = graph.create("b", desc());
+    pass(&mut graph, "first", &[], &[a]);
+    pass(&mut graph, "second", &[], &[b]);
+    assert_eq!(graph.order().unwrap(), vec![0, 1]);
+  }
+
+  #[test]
+  fn order_errors() {
+    let mut graph = RenderGraph::new();
+    let a = graph.create("a", desc());
+    let b = graph.create("b", desc());
+    let c = graph.create("c", desc());
+    pass(&mut graph, "start", &[], &[c]);
+    pass(&mut graph, "x", &[a, c], &[b]);
+    pass(&mut graph, "y", &[b], &[a]);
+    let err = graph.order().unwrap_err();
+    assert!(err.contains("循环依赖") && err.contains("x") && err.contains("y") && !err.contains("start"), "{}", err);
+
+    let mut graph = RenderGraph::new();
+    let a = graph.create("a", desc());
+    let b = graph.create("b", desc());
+    pass(&mut graph, "blur", &[a], &[b]);
+    let err = graph.order().unwrap_err();
+    assert!(err.contains("blur") && err.contains("临时纹理 a") && err.contains("没有被写入"), "{}", err);
+
+    let mut graph = RenderGraph::new();
+    let a = graph.create("a", desc());
+    pass(&mut graph, "feedback", &[a], &[a]);
+    assert!(graph.order().unwrap_err().contains("同时读写"));
+  }
+
+  #[test]
+  fn transients_share_textures_when_lifetimes_do_not_overlap() {
+    // a -> b -> c -> d 的链：a 在 b 写完后不再使用，c 可以复用 a 的纹理，d 可以复用 b 的纹理
+    let mut graph = RenderGraph::new();
+    let a = graph.create("a", desc());
+    let b = graph.create("b", desc());
+    let c = graph.create("c", desc());
+    let d = graph.create("d", desc());
+    let small = graph.create("small", TextureDesc::new(32, 32, TextureFormat::Rgba8Unorm));
+    let unused = graph.create("unused", desc());
+    pass(&mut graph, "p0", &[], &[a]);
+    pass(&mut graph, "p1", &[a], &[b]);
+    pass(&mut graph, "p2", &[b], &[c]);
+    pass(&mut graph, "p3", &[c], &[d, small]);
+    pass(&mut graph, "p4", &[d], &[]);
+    let order = graph.order().unwrap();
+    let (slots, textures) = graph.allocate(&order);
+    assert_eq!(slots[a.0], slots[c.0]);
+    assert_eq!(slots[b.0], slots[d.0]);
+    assert_ne!(slots[a.0], slots[b.0]);
+    // 描述不同时不复用，没有用到的不分配
+    assert!(slots[small.0] != slots[a.0] && slots[small.0] != slots[b.0]);
+    assert_eq!(slots[unused.0], None);
+    assert_eq!(textures.len(), 3);
+    assert_eq!(textures[slots[a.0].unwrap()].0, "a");
+    assert_eq!(textures[slots[a.0].unwrap()].1.usage, TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING);
+    assert_eq!(textures[slots[small.0].unwrap()].1.usage, TextureUsages::RENDER_ATTACHMENT);
+
+    // a 一直被读到最后，和 b、c 的生命周期都重叠
+    let mut graph = RenderGraph::new();
+    let a = graph.create("a", desc());
+    let b = graph.create("b", desc());
+    let c = graph.create("c", desc());
+    pass(&mut graph, "p0", &[], &[a]);
+    pass(&mut graph, "p1", &[a], &[b]);
+    pass(&mut graph, "p2", &[a, b], &[c]);
+    let order = graph.order().unwrap();
+    let (slots, textures) = graph.allocate(&order);
+    assert_eq!(textures.len(), 3);
+    assert!(slots[a.0] != slots[b.0] && slots[a.0] != slots[c.0] && slots[b.0] != slots[c.0]);
+  }
+}
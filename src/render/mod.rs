@@ -1,5 +1,19 @@
 pub mod wgpu_ctx;
 pub mod vertex;
 pub mod pipeline;
+pub mod pipeline_cache;
+pub mod shader;
+pub mod preprocess;
 pub mod camera;
-pub mod draw;
\ No newline at end of file
+pub mod draw;
+pub mod graph;
+pub mod targets;
+pub mod post;
+pub mod debug_draw;
+pub mod text;
+pub mod skinned;
+pub mod morph;
+pub mod transparent;
+pub mod screenshot;
+pub mod recorder;
+pub mod profiler;
\ No newline at end of file
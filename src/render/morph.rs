@@ -0,0 +1,214 @@
+use util::{BufferInitDescriptor, DeviceExt};
+use wgpu::*;
+
+use crate::anim::morph::{MorphMesh, MAX_MORPH_TARGETS};
+use crate::render::{pipeline::create_morph_pipeline, profiler::DrawStats, shader::checked};
+
+// 与 morph.wgsl 中的 MorphUniform 布局一致
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct MorphUniform {
+  model: [[f32; 4]; 4],
+  weights: [f32; MAX_MORPH_TARGETS],
+  vertex_count: u32,
+  target_count: u32,
+  has_normals: u32, // 为 1 时按变形后的法线计算明暗
+  _padding: u32,
+}
+
+unsafe impl bytemuck::Zeroable for MorphUniform {}
+unsafe impl bytemuck::Pod for MorphUniform {}
+
+// 已上传到 GPU 的变形网格
+struct GpuMorphMesh {
+  vertex_buffer: Buffer,
+  uniform_buffer: Buffer,
+  bind_group: BindGroup,
+  vertex_count: u32,
+  target_count: u32,
+  has_normals: bool,
+}
+
+/// 在 GPU 上应用变形目标的渲染器
+///
+/// 网格通过 `upload` 上传一次，位置偏移、法线偏移和基础法线保存在 storage buffer 中；之后每帧用 `queue` 提交权重和变换。
+pub struct MorphRenderer {
+  pipeline: RenderPipeline,
+  morph_layout: BindGroupLayout,
+  meshes: Vec<GpuMorphMesh>,
+  queued: Vec<usize>,
+}
+
+impl MorphRenderer {
+  pub fn new(device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, source: &str) -> Self {
+    let morph_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("Morph Bind Group Layout"),
+      entries: &[
+        BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::VERTEX,
+          ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 1,
+          visibility: ShaderStages::VERTEX,
+          ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+          },
+          count: None,
+        },
+      ],
+    });
+    Self {
+      pipeline: create_morph_pipeline(device, texture_format, bind_group_layout, &morph_layout, source),
+      morph_layout,
+      meshes: vec![],
+      queued: vec![],
+    }
+  }
+
+  /// 用新的着色器源码重建管线，失败时保留原来的管线
+  pub fn rebuild_pipeline(&mut self, device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, source: &str) -> Result<(), String> {
+    self.pipeline = checked(device, || create_morph_pipeline(device, texture_format, bind_group_layout, &self.morph_layout, source))?;
+    Ok(())
+  }
+
+  /// 上传网格的顶点和变形偏移，返回之后提交绘制用的句柄
+  ///
+  /// 超过 `MAX_MORPH_TARGETS` 的变形目标不会上传，这类网格需要用 `MorphMesh::morphed_vertices` 在 CPU 上变形。
+  pub fn upload(&mut self, device: &Device, mesh: &MorphMesh) -> usize {
+    let target_count = mesh.targets.len().min(MAX_MORPH_TARGETS);
+    let targets = &mesh.targets[..target_count];
+    let has_normals = !mesh.normals.is_empty();
+    let vec4 = |d: &[f32; 3]| [d[0], d[1], d[2], 0.0];
+    // 布局与 morph.wgsl 一致：所有目标的位置偏移，然后是所有目标的法线偏移（没有时为 0），最后是基础法线
+    let mut deltas: Vec<[f32; 4]> = targets.iter().flat_map(|t| t.position_deltas.iter().map(vec4)).collect();
+    if has_normals {
+      for target in targets {
+        match target.normal_deltas.is_empty() {
+          true => deltas.extend(std::iter::repeat_n([0.0; 4], mesh.vertices.len())),
+          false => deltas.extend(target.normal_deltas.iter().map(vec4)),
+        }
+      }
+      deltas.extend(mesh.normals.iter().map(vec4));
+    }
+    // storage buffer 不能为空
+    if deltas.is_empty() {
+      deltas.push([0.0; 4]);
+    }
+    let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+      label: Some("Morph Vertex Buffer"),
+      contents: bytemuck::cast_slice(&mesh.vertices),
+      usage: BufferUsages::VERTEX,
+    });
+    let delta_buffer = device.create_buffer_init(&BufferInitDescriptor {
+      label: Some("Morph Delta Buffer"),
+      contents: bytemuck::cast_slice(&deltas),
+      usage: BufferUsages::STORAGE,
+    });
+    let uniform_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Morph Uniform Buffer"),
+      size: std::mem::size_of::<MorphUniform>() as u64,
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+      label: Some("Morph Bind Group"),
+      layout: &self.morph_layout,
+      entries: &[
+        BindGroupEntry { binding: 0, resource: uniform_buffer.as_entire_binding() },
+        BindGroupEntry { binding: 1, resource: delta_buffer.as_entire_binding() },
+      ],
+    });
+    self.meshes.push(GpuMorphMesh {
+      vertex_buffer,
+      uniform_buffer,
+      bind_group,
+      vertex_count: mesh.vertices.len() as u32,
+      target_count: target_count as u32,
+      has_normals,
+    });
+    self.meshes.len() - 1
+  }
+
+  /// 提交本帧要绘制的网格，使用 `mesh` 当前的权重和变换；`handle` 无效时忽略
+  ///
+  /// 每个句柄的权重保存在同一个 uniform buffer 中，一帧内多次提交时只有最后一次的权重生效。
+  pub fn queue(&mut self, queue: &Queue, handle: usize, mesh: &MorphMesh) {
+    let Some(gpu) = self.meshes.get(handle) else {
+      return
+    };
+    let mut weights = [0.0; MAX_MORPH_TARGETS];
+    for (dst, w) in weights.iter_mut().zip(mesh.weights.iter()) {
+      *dst = *w;
+    }
+    let uniform = MorphUniform {
+      model: mesh.transform.into(),
+      weights,
+      vertex_count: gpu.vertex_count,
+      target_count: gpu.target_count,
+      has_normals: gpu.has_normals as u32,
+      _padding: 0,
+    };
+    queue.write_buffer(&gpu.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    self.queued.push(handle);
+  }
+
+  /// 本帧提交的网格的绘制统计
+  pub fn draw_stats(&self) -> DrawStats {
+    let mut stats = DrawStats::default();
+    for &handle in self.queued.iter() {
+      stats.add_draw(self.meshes[handle].vertex_count);
+    }
+    stats
+  }
+
+  /// 清空本帧提交的网格，在一帧的所有绘制（包括截图）完成后调用
+  pub fn clear(&mut self) {
+    self.queued.clear();
+  }
+
+  /// 在已有画面上绘制本帧提交的网格（不清除颜色和深度），同一帧可以绘制多次
+  pub fn draw(&mut self, encoder: &mut CommandEncoder, view: &TextureView, depth_view: &TextureView, bind_group: &BindGroup) {
+    if self.queued.is_empty() {
+      return
+    }
+    let queued = &self.queued;
+    let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("Morph Pass"),
+      depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+        view: depth_view,
+        depth_ops: Some(Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        }),
+        stencil_ops: None,
+      }),
+      timestamp_writes: None,
+      occlusion_query_set: None,
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        },
+      })]
+    });
+    r_pass.set_pipeline(&self.pipeline);
+    r_pass.set_bind_group(0, bind_group, &[]);
+    for &handle in queued.iter() {
+      let gpu = &self.meshes[handle];
+      r_pass.set_bind_group(1, &gpu.bind_group, &[]);
+      r_pass.set_vertex_buffer(0, gpu.vertex_buffer.slice(..));
+      r_pass.draw(0..gpu.vertex_count, 0..1);
+    }
+  }
+}
@@ -1,21 +1,263 @@
+use crate::render::shader::ShaderFeatures;
+use crate::render::transparent::{OIT_ACCUM_FORMAT, OIT_REVEAL_FORMAT};
 use crate::render::vertex::*;
 use wgpu::*;
 
-pub fn create_pipeline(device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout) -> RenderPipeline {
+/// 混合方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+  #[default]
+  Opaque, // 不混合，直接覆盖
+  Alpha, // 按 alpha 混合
+  Premultiplied, // 颜色已预乘 alpha
+  Additive, // 颜色相加，用于发光效果
+  WeightedOit, // 加权混合的顺序无关透明，输出到累积和透明度两个目标，见 `transparent` 模块
+}
+
+impl BlendMode {
+  // 片元着色器的输出目标，`format` 为场景颜色纹理的格式
+  fn targets(&self, format: TextureFormat) -> Vec<Option<ColorTargetState>> {
+    let blend = match self {
+      BlendMode::Opaque => None,
+      BlendMode::Alpha => Some(BlendState::ALPHA_BLENDING),
+      BlendMode::Premultiplied => Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+      BlendMode::Additive => Some(BlendState {
+        color: BlendComponent { src_factor: BlendFactor::SrcAlpha, dst_factor: BlendFactor::One, operation: BlendOperation::Add },
+        alpha: BlendComponent { src_factor: BlendFactor::Zero, dst_factor: BlendFactor::One, operation: BlendOperation::Add },
+      }),
+      BlendMode::WeightedOit => {
+        // 累积目标直接相加；透明度目标乘上 (1 - alpha)，最后剩下的是背景透过的比例
+        let add = BlendComponent { src_factor: BlendFactor::One, dst_factor: BlendFactor::One, operation: BlendOperation::Add };
+        let reveal = BlendComponent { src_factor: BlendFactor::Zero, dst_factor: BlendFactor::OneMinusSrc, operation: BlendOperation::Add };
+        return vec![
+          Some(ColorTargetState { format: OIT_ACCUM_FORMAT, blend: Some(BlendState { color: add, alpha: add }), write_mask: ColorWrites::ALL }),
+          Some(ColorTargetState { format: OIT_REVEAL_FORMAT, blend: Some(BlendState { color: reveal, alpha: reveal }), write_mask: ColorWrites::RED }),
+        ];
+      },
+    };
+    vec![Some(ColorTargetState { format, blend, write_mask: ColorWrites::ALL })]
+  }
+}
+
+/// 场景管线使用的顶点格式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum VertexLayout {
+  #[default]
+  Basic, // `Vertex`
+  Skinned, // `SkinnedVertex`，需要 group 1 的关节矩阵
+}
+
+/// 渲染状态，使用 shader.wgsl 的场景管线按它区分，也是 `PipelineCache` 的键
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderState {
+  pub topology: PrimitiveTopology,
+  pub polygon_mode: PolygonMode, // Line 需要设备支持 POLYGON_MODE_LINE
+  pub cull_mode: Option<Face>,
+  pub blend: BlendMode,
+  pub depth_compare: CompareFunction, // Always 表示不做深度测试
+  pub depth_write: bool,
+  pub vertex_layout: VertexLayout,
+  pub features: ShaderFeatures, // 着色器变体
+}
+
+impl Default for RenderState {
+  /// 场景的不透明三角形：背面剔除、深度测试并写入深度
+  fn default() -> Self {
+    Self {
+      topology: PrimitiveTopology::TriangleList,
+      polygon_mode: PolygonMode::Fill,
+      cull_mode: Some(Face::Back),
+      blend: BlendMode::Opaque,
+      depth_compare: CompareFunction::Less,
+      depth_write: true,
+      vertex_layout: VertexLayout::Basic,
+      features: ShaderFeatures::NONE,
+    }
+  }
+}
+
+impl RenderState {
+  /// 调试线段：不剔除、不写入深度，`depth_test` 为 false 时总是绘制在最上层
+  pub fn lines(depth_test: bool) -> Self {
+    Self {
+      topology: PrimitiveTopology::LineList,
+      cull_mode: None, // 线段没有正反面
+      depth_compare: if depth_test { CompareFunction::LessEqual } else { CompareFunction::Always },
+      depth_write: false, // 调试线段不写入深度，避免遮挡场景
+      ..Self::default()
+    }
+  }
+
+  /// GPU 蒙皮的网格
+  pub fn skinned() -> Self {
+    Self {
+      vertex_layout: VertexLayout::Skinned,
+      features: ShaderFeatures::SKINNED,
+      ..Self::default()
+    }
+  }
+
+  /// 线框模式，不剔除背面
+  pub fn wireframe(self) -> Self {
+    Self { polygon_mode: PolygonMode::Line, cull_mode: None, ..self }
+  }
+
+  /// 使用混合时一般不写入深度，避免遮挡后面的半透明物体
+  pub fn with_blend(self, blend: BlendMode) -> Self {
+    Self { blend, depth_write: blend == BlendMode::Opaque && self.depth_write, ..self }
+  }
+
+  /// 半透明网格：按 alpha 混合，深度测试但不写入深度，需要从远到近绘制
+  pub fn transparent() -> Self {
+    Self::default().with_blend(BlendMode::Alpha)
+  }
+
+  /// 把半透明状态改为加权混合 OIT，不需要排序
+  pub fn weighted_oit(self) -> Self {
+    Self { blend: BlendMode::WeightedOit, depth_write: false, features: self.features | ShaderFeatures::OIT, ..self }
+  }
+}
+
+// 按渲染状态创建 shader.wgsl 的场景管线
+// - `bind_group_layouts`: group 0 为相机 uniform，蒙皮时 group 1 为关节矩阵
+// - `source`: 与 `state.features` 对应的着色器变体
+// - `cache`: 驱动的管线缓存，不支持时为 None
+pub fn create_state_pipeline(
+  device: &Device,
+  texture_format: TextureFormat,
+  bind_group_layouts: &[&BindGroupLayout],
+  source: &str,
+  state: &RenderState,
+  cache: Option<&PipelineCache>,
+) -> RenderPipeline {
   let shader: ShaderModule = device.create_shader_module(ShaderModuleDescriptor {
     label: Some("Shader"),
-    source: ShaderSource::Wgsl(include_str!("../template/shader.wgsl").into()),
+    source: ShaderSource::Wgsl(source.into()),
   });
 
   let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
     label: Some("Render Pipeline Layout"),
-    bind_group_layouts: &[bind_group_layout],
+    bind_group_layouts,
     push_constant_ranges: &[],
   });
 
+  let vertex_layout = match state.vertex_layout {
+    VertexLayout::Basic => create_vertex_buffer_layout(),
+    VertexLayout::Skinned => create_skinned_vertex_buffer_layout(),
+  };
+
   device.create_render_pipeline(&RenderPipelineDescriptor {
     label: Some("Render Pipeline"),
     layout: Some(&render_pipeline_layout),
+    vertex: VertexState {
+      module: &shader,
+      entry_point: Some("vs_main"),
+      buffers: &[vertex_layout],
+      compilation_options: Default::default(),
+    },
+    fragment: Some(FragmentState {
+      module: &shader,
+      entry_point: Some("fs_main"),
+      targets: &state.blend.targets(texture_format),
+      compilation_options: Default::default(),
+    }),
+    primitive: PrimitiveState {
+      topology: state.topology,
+      strip_index_format: None,
+      front_face: FrontFace::Ccw, // Ccw:逆时针顶点顺序为正面（默认）, Cw:顺时针顶点顺序为正面
+      cull_mode: state.cull_mode,
+      unclipped_depth: false, // 是否禁用近/远平面的深度裁剪, 默认false（启用裁剪）
+      polygon_mode: state.polygon_mode, // 设置为线框模式， 片源着色器绘制类型
+      conservative: false, // 是否启用保守光栅化
+    },
+    depth_stencil: Some(DepthStencilState {
+      format: TextureFormat::Depth32Float,
+      depth_write_enabled: state.depth_write,
+      depth_compare: state.depth_compare,
+      stencil: StencilState::default(),
+      bias: DepthBiasState::default(),
+    }),
+    multisample: MultisampleState {
+      count: 1, // HDR 和深度目标都不是多重采样纹理
+      mask: !0,
+      alpha_to_coverage_enabled: false,
+    },
+    multiview: None,
+    cache,
+  })
+}
+
+
+// 创建文字管线：屏幕空间、alpha 混合、不做深度测试
+pub fn create_text_pipeline(device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, vertex_layout: VertexBufferLayout, source: &str) -> RenderPipeline {
+  let shader: ShaderModule = device.create_shader_module(ShaderModuleDescriptor {
+    label: Some("Text Shader"),
+    source: ShaderSource::Wgsl(source.into()),
+  });
+
+  let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+    label: Some("Text Pipeline Layout"),
+    bind_group_layouts: &[bind_group_layout],
+    push_constant_ranges: &[],
+  });
+
+  device.create_render_pipeline(&RenderPipelineDescriptor {
+    label: Some("Text Pipeline"),
+    layout: Some(&render_pipeline_layout),
+    vertex: VertexState {
+      module: &shader,
+      entry_point: Some("vs_main"),
+      buffers: &[vertex_layout],
+      compilation_options: Default::default(),
+    },
+    fragment: Some(FragmentState {
+      module: &shader,
+      entry_point: Some("fs_main"),
+      targets: &[Some(ColorTargetState {
+        format: texture_format,
+        blend: Some(BlendState::ALPHA_BLENDING),
+        write_mask: ColorWrites::ALL,
+      })],
+      compilation_options: Default::default(),
+    }),
+    primitive: PrimitiveState {
+      topology: PrimitiveTopology::TriangleList,
+      strip_index_format: None,
+      front_face: FrontFace::Ccw,
+      cull_mode: None,
+      unclipped_depth: false,
+      polygon_mode: PolygonMode::Fill,
+      conservative: false,
+    },
+    depth_stencil: None,
+    multisample: MultisampleState {
+      count: 1,
+      mask: !0,
+      alpha_to_coverage_enabled: false,
+    },
+    multiview: None,
+    cache: None,
+  })
+}
+
+
+// 创建变形管线：与场景管线相同，顶点着色器按权重叠加变形目标的偏移
+// - `morph_layout`: 权重和偏移的 bind group layout（group 1）
+pub fn create_morph_pipeline(device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, morph_layout: &BindGroupLayout, source: &str) -> RenderPipeline {
+  let shader: ShaderModule = device.create_shader_module(ShaderModuleDescriptor {
+    label: Some("Morph Shader"),
+    source: ShaderSource::Wgsl(source.into()),
+  });
+
+  let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+    label: Some("Morph Pipeline Layout"),
+    bind_group_layouts: &[bind_group_layout, morph_layout],
+    push_constant_ranges: &[],
+  });
+
+  device.create_render_pipeline(&RenderPipelineDescriptor {
+    label: Some("Morph Pipeline"),
+    layout: Some(&render_pipeline_layout),
     vertex: VertexState {
       module: &shader,
       entry_point: Some("vs_main"),
@@ -33,11 +275,11 @@ pub fn create_pipeline(device: &Device, texture_format: TextureFormat, bind_grou
     primitive: PrimitiveState {
       topology: PrimitiveTopology::TriangleList,
       strip_index_format: None,
-      front_face: FrontFace::Ccw, // Ccw:逆时针顶点顺序为正面（默认）, Cw:顺时针顶点顺序为正面
-      cull_mode: Some(Face::Back), // 背面剔除,
-      unclipped_depth: false, // 是否禁用近/远平面的深度裁剪, 默认false（启用裁剪）
-      polygon_mode: PolygonMode::Fill, // 设置为线框模式， 片源着色器绘制类型
-      conservative: false, // 是否启用保守光栅化
+      front_face: FrontFace::Ccw,
+      cull_mode: Some(Face::Back),
+      unclipped_depth: false,
+      polygon_mode: PolygonMode::Fill,
+      conservative: false,
     },
     depth_stencil: Some(DepthStencilState {
       format: TextureFormat::Depth32Float,
@@ -54,4 +296,55 @@ pub fn create_pipeline(device: &Device, texture_format: TextureFormat, bind_grou
     multiview: None,
     cache: None,
   })
-}
\ No newline at end of file
+}
+
+
+// 创建全屏后处理管线：顶点着色器为 fullscreen.wgsl 中的 vs_fullscreen，不需要顶点缓冲和深度
+// - `entry`: 片元着色器入口
+// - `blend`: 叠加到输出纹理原有内容上时的混合方式，None 表示直接覆盖
+pub fn create_fullscreen_pipeline(device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, source: &str, entry: &str, blend: Option<BlendState>) -> RenderPipeline {
+  let shader: ShaderModule = device.create_shader_module(ShaderModuleDescriptor {
+    label: Some("Post Shader"),
+    source: ShaderSource::Wgsl(source.into()),
+  });
+
+  let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+    label: Some("Post Pipeline Layout"),
+    bind_group_layouts: &[bind_group_layout],
+    push_constant_ranges: &[],
+  });
+
+  device.create_render_pipeline(&RenderPipelineDescriptor {
+    label: Some("Post Pipeline"),
+    layout: Some(&render_pipeline_layout),
+    vertex: VertexState {
+      module: &shader,
+      entry_point: Some("vs_fullscreen"),
+      buffers: &[],
+      compilation_options: Default::default(),
+    },
+    fragment: Some(FragmentState {
+      module: &shader,
+      entry_point: Some(entry),
+      targets: &[Some(ColorTargetState { format: texture_format, blend, write_mask: ColorWrites::ALL })],
+      compilation_options: Default::default(),
+    }),
+    primitive: PrimitiveState {
+      topology: PrimitiveTopology::TriangleList,
+      strip_index_format: None,
+      front_face: FrontFace::Ccw,
+      cull_mode: None,
+      unclipped_depth: false,
+      polygon_mode: PolygonMode::Fill,
+      conservative: false,
+    },
+    depth_stencil: None,
+    multisample: MultisampleState {
+      count: 1,
+      mask: !0,
+      alpha_to_coverage_enabled: false,
+    },
+    multiview: None,
+    cache: None,
+  })
+}
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use wgpu::{AdapterInfo, BindGroupLayout, Device, Features, PipelineCacheDescriptor, PolygonMode, RenderPipeline, TextureFormat};
+
+use crate::constants::PIPELINE_CACHE_DIR;
+
+use super::pipeline::{create_state_pipeline, RenderState};
+use super::shader::{checked, ShaderFeatures, ShaderLibrary};
+
+/// 按渲染状态缓存 shader.wgsl 的场景管线，同一帧内不同元素可以使用不同的状态
+///
+/// 管线在第一次 `prepare` 时创建；创建失败的状态会被记住，不再重试，绘制时由调用方换成默认状态。
+/// 这里的管线只有 group 0 的相机 uniform，蒙皮网格由 `SkinnedRenderer` 自己创建管线。
+/// 设备支持 `PIPELINE_CACHE`（目前只有 Vulkan）时，驱动编译的结果通过 `save` 写到磁盘，下次启动时读取。
+pub struct PipelineCache {
+  texture_format: TextureFormat,
+  pipelines: HashMap<RenderState, RenderPipeline>,
+  failed: HashSet<RenderState>,
+  driver_cache: Option<wgpu::PipelineCache>,
+  path: Option<PathBuf>, // 驱动缓存文件
+}
+
+impl PipelineCache {
+  pub fn new(device: &Device, adapter_info: &AdapterInfo, texture_format: TextureFormat) -> Self {
+    let key = wgpu::util::pipeline_cache_key(adapter_info).filter(|_| device.features().contains(Features::PIPELINE_CACHE));
+    let path = key.map(|key| PathBuf::from(PIPELINE_CACHE_DIR).join(key));
+    let driver_cache = path.as_ref().map(|path| {
+      let data = fs::read(path).ok();
+      // SAFETY: 数据只来自之前 `get_data` 写出的文件；数据不匹配当前驱动时 fallback 会创建空缓存
+      unsafe {
+        device.create_pipeline_cache(&PipelineCacheDescriptor {
+          label: Some("Pipeline Cache"),
+          data: data.as_deref(),
+          fallback: true,
+        })
+      }
+    });
+    Self {
+      texture_format,
+      pipelines: HashMap::new(),
+      failed: HashSet::new(),
+      driver_cache,
+      path,
+    }
+  }
+
+  /// 确保 `state` 对应的管线已经创建，第一次创建失败时返回错误
+  pub fn prepare(&mut self, device: &Device, shaders: &mut ShaderLibrary, layout: &BindGroupLayout, state: &RenderState) -> Result<(), String> {
+    if self.pipelines.contains_key(state) || self.failed.contains(state) {
+      return Ok(());
+    }
+    // WebGPU、WebGL2 不支持线框，浏览器中错误作用域也不能同步检查，提前返回错误
+    if state.polygon_mode != PolygonMode::Fill && !device.features().contains(Features::POLYGON_MODE_LINE) {
+      self.failed.insert(*state);
+      return Err("设备不支持 POLYGON_MODE_LINE".to_string());
+    }
+    let source = shaders.variant("shader.wgsl", state.features);
+    let result = checked(device, || {
+      create_state_pipeline(device, self.texture_format, &[layout], source, state, self.driver_cache.as_ref())
+    });
+    match result {
+      Ok(pipeline) => {
+        self.pipelines.insert(*state, pipeline);
+        Ok(())
+      },
+      Err(e) => {
+        self.failed.insert(*state);
+        Err(e)
+      },
+    }
+  }
+
+  /// 已经创建的管线，没有 `prepare` 过时返回 None
+  pub fn get(&self, state: &RenderState) -> Option<&RenderPipeline> {
+    self.pipelines.get(state)
+  }
+
+  /// `state` 对应的管线，没有或创建失败时使用默认状态的管线
+  pub fn get_or_default(&self, state: &RenderState) -> &RenderPipeline {
+    self.get(state).or_else(|| self.get(&RenderState::default())).expect("默认渲染管线不存在")
+  }
+
+  /// 着色器变体更新后重建使用它的管线，失败时保留原来的管线
+  pub fn rebuild(&mut self, device: &Device, layout: &BindGroupLayout, features: ShaderFeatures, source: &str) -> Result<(), String> {
+    self.failed.retain(|state| state.features != features);
+    let states: Vec<RenderState> = self.pipelines.keys().filter(|s| s.features == features).copied().collect();
+    let pipelines = checked(device, || {
+      states.iter().map(|state| create_state_pipeline(device, self.texture_format, &[layout], source, state, self.driver_cache.as_ref())).collect::<Vec<_>>()
+    })?;
+    self.pipelines.extend(states.into_iter().zip(pipelines));
+    Ok(())
+  }
+
+  /// 把驱动缓存写到磁盘，不支持时什么都不做
+  pub fn save(&self) -> Result<(), String> {
+    let (Some(cache), Some(path)) = (self.driver_cache.as_ref(), self.path.as_ref()) else {
+      return Ok(());
+    };
+    let Some(data) = cache.get_data() else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir).map_err(|e| format!("创建目录 {} 失败：{}", dir.display(), e))?;
+    }
+    // 先写临时文件再改名，避免写到一半时退出留下损坏的缓存
+    let temp = path.with_extension("temp");
+    fs::write(&temp, &data).map_err(|e| format!("写入 {} 失败：{}", temp.display(), e))?;
+    fs::rename(&temp, path).map_err(|e| format!("写入 {} 失败：{}", path.display(), e))?;
+    println!("管线缓存已保存到 {}（{} 字节）", path.display(), data.len());
+    Ok(())
+  }
+}
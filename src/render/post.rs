@@ -0,0 +1,765 @@
+use std::any::Any;
+use std::collections::HashMap;
+
+use nalgebra::{Matrix4, Vector3};
+use util::{BufferInitDescriptor, DeviceExt};
+use wgpu::*;
+
+use super::graph::{RenderGraph, ResourceId, TextureDesc};
+use super::pipeline::create_fullscreen_pipeline;
+use super::shader::{checked, ShaderFeatures, ShaderLibrary};
+
+/// 场景渲染使用的 HDR 颜色格式，后处理链把它转换到交换链的格式
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// 传给全屏着色器的参数，对应 post_common.wgsl 中的 `params.values`，含义由每个效果自己决定
+pub type PostParams = [[f32; 4]; 4];
+
+/// 后处理的输入或输出纹理
+#[derive(Clone, Copy, Debug)]
+pub struct PostTarget {
+  pub id: ResourceId,
+  pub format: TextureFormat,
+}
+
+/// 场景的深度和相机，由深度重建位置的效果（例如 SSAO）使用
+#[derive(Clone, Copy, Debug)]
+pub struct SceneDepth {
+  pub depth: ResourceId, // Depth32Float
+  pub normals: Option<ResourceId>, // 世界空间法线，场景没有输出法线时为 None，由深度估算
+  pub pvm: Matrix4<f32>, // 与相机 uniform 中的 proj 相同
+  pub eye: Vector3<f32>, // 相机位置（世界坐标）
+}
+
+/// 添加后处理 pass 时需要的帧信息
+#[derive(Clone, Copy)]
+pub struct PostFrame<'a> {
+  pub device: &'a Device,
+  pub width: u32,
+  pub height: u32,
+  pub scene: Option<SceneDepth>, // 没有深度时跳过需要深度的效果
+}
+
+// 输入是线性颜色、输出纹理又不会自动编码时，需要在着色器中编码为 sRGB
+fn needs_srgb_encode(input: TextureFormat, output: TextureFormat) -> bool {
+  let linear = input.is_srgb() || matches!(input, TextureFormat::Rgba16Float | TextureFormat::Rgba32Float | TextureFormat::Rg11b10Ufloat);
+  linear && !output.is_srgb()
+}
+
+// post_common.wgsl 的绑定：两张可过滤的纹理、线性采样器和参数
+fn post_bind_group_layout(device: &Device) -> BindGroupLayout {
+  let texture_entry = |binding| BindGroupLayoutEntry {
+    binding,
+    visibility: ShaderStages::FRAGMENT,
+    ty: BindingType::Texture {
+      sample_type: TextureSampleType::Float { filterable: true },
+      view_dimension: TextureViewDimension::D2,
+      multisampled: false,
+    },
+    count: None,
+  };
+  device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+    label: Some("Post Bind Group Layout"),
+    entries: &[
+      texture_entry(0),
+      BindGroupLayoutEntry {
+        binding: 1,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+        count: None,
+      },
+      BindGroupLayoutEntry {
+        binding: 2,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
+        count: None,
+      },
+      texture_entry(3),
+    ],
+  })
+}
+
+/// 全屏片元着色器的一个入口，绑定格式见 post_common.wgsl，管线按输出格式创建并缓存
+pub struct FullscreenPass {
+  label: &'static str,
+  entry: &'static str,
+  source: String,
+  layout: BindGroupLayout,
+  sampler: Sampler,
+  blend: Option<BlendState>, // None 时清空输出纹理后直接覆盖
+  pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl FullscreenPass {
+  /// - `source`: 预处理后的 WGSL，需要包含 post_common.wgsl
+  /// - `entry`: 片元着色器入口
+  pub fn new(device: &Device, label: &'static str, source: &str, entry: &'static str) -> Self {
+    Self::with_layout(device, label, source, entry, post_bind_group_layout(device))
+  }
+
+  /// 使用自己的绑定，绘制时通过 `draw_bind_group` 传入 bind group
+  pub fn with_layout(device: &Device, label: &'static str, source: &str, entry: &'static str, layout: BindGroupLayout) -> Self {
+    let sampler = device.create_sampler(&SamplerDescriptor {
+      label: Some("Post Sampler"),
+      address_mode_u: AddressMode::ClampToEdge,
+      address_mode_v: AddressMode::ClampToEdge,
+      mag_filter: FilterMode::Linear,
+      min_filter: FilterMode::Linear,
+      ..Default::default()
+    });
+    Self { label, entry, source: source.to_string(), layout, sampler, blend: None, pipelines: HashMap::new() }
+  }
+
+  /// 按 `blend` 叠加到输出纹理原有的内容上，而不是清空后覆盖
+  pub fn blended(self, blend: BlendState) -> Self {
+    Self { blend: Some(blend), ..self }
+  }
+
+  pub fn layout(&self) -> &BindGroupLayout {
+    &self.layout
+  }
+
+  /// 确保输出格式为 `format` 的管线已经创建，失败时打印错误，绘制时跳过
+  pub fn prepare(&mut self, device: &Device, format: TextureFormat) {
+    if self.pipelines.contains_key(&format) {
+      return
+    }
+    match checked(device, || create_fullscreen_pipeline(device, format, &self.layout, &self.source, self.entry, self.blend)) {
+      Ok(pipeline) => {
+        self.pipelines.insert(format, pipeline);
+      },
+      Err(e) => println!("创建后处理管线 {} 失败：{}", self.label, e),
+    }
+  }
+
+  /// 着色器更新后重建已经创建的管线，失败时保留原来的管线
+  pub fn rebuild(&mut self, device: &Device, source: &str) -> Result<(), String> {
+    let formats: Vec<TextureFormat> = self.pipelines.keys().copied().collect();
+    let pipelines = checked(device, || {
+      formats.iter().map(|format| create_fullscreen_pipeline(device, *format, &self.layout, source, self.entry, self.blend)).collect::<Vec<_>>()
+    })?;
+    self.pipelines.extend(formats.into_iter().zip(pipelines));
+    self.source = source.to_string();
+    Ok(())
+  }
+
+  /// 绘制到 `output`，`inputs` 对应着色器中的 t_input 和 t_extra，不需要 t_extra 时传入两个相同的纹理
+  pub fn draw(&self, device: &Device, encoder: &mut CommandEncoder, output: &TextureView, format: TextureFormat, inputs: [&TextureView; 2], params: &PostParams) {
+    let buffer = device.create_buffer_init(&BufferInitDescriptor {
+      label: Some("Post Params"),
+      contents: bytemuck::cast_slice(params),
+      usage: BufferUsages::UNIFORM,
+    });
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+      label: Some(self.label),
+      layout: &self.layout,
+      entries: &[
+        BindGroupEntry { binding: 0, resource: BindingResource::TextureView(inputs[0]) },
+        BindGroupEntry { binding: 1, resource: BindingResource::Sampler(&self.sampler) },
+        BindGroupEntry { binding: 2, resource: buffer.as_entire_binding() },
+        BindGroupEntry { binding: 3, resource: BindingResource::TextureView(inputs[1]) },
+      ],
+    });
+    self.draw_bind_group(encoder, output, format, &bind_group);
+  }
+
+  /// 使用调用方创建的 bind group 绘制到 `output`
+  pub fn draw_bind_group(&self, encoder: &mut CommandEncoder, output: &TextureView, format: TextureFormat, bind_group: &BindGroup) {
+    let Some(pipeline) = self.pipelines.get(&format) else {
+      return
+    };
+    let load = if self.blend.is_some() { LoadOp::Load } else { LoadOp::Clear(Color::BLACK) };
+    let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some(self.label),
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view: output,
+        resolve_target: None,
+        ops: Operations { load, store: StoreOp::Store },
+      })],
+      depth_stencil_attachment: None,
+      timestamp_writes: None,
+      occlusion_query_set: None,
+    });
+    r_pass.set_pipeline(pipeline);
+    r_pass.set_bind_group(0, bind_group, &[]);
+    r_pass.draw(0..3, 0..1);
+  }
+}
+
+/// 后处理效果，由 `PostChain` 按顺序串起来
+///
+/// 每个效果读取上一步的结果，写入渲染图分配的纹理；最后一个效果的输出格式与交换链相同时直接写入交换链。
+pub trait PostEffect {
+  fn name(&self) -> &'static str;
+
+  fn enabled(&self) -> bool {
+    true
+  }
+
+  /// 是否需要 `PostFrame::scene`，没有场景深度时跳过
+  fn requires_depth(&self) -> bool {
+    false
+  }
+
+  /// 输出纹理的格式，`surface` 为交换链的格式
+  fn output_format(&self, input: TextureFormat, _surface: TextureFormat) -> TextureFormat {
+    input
+  }
+
+  /// 在渲染图中添加 pass，读取 `input`，结果写入 `output`
+  fn add_passes<'a>(&'a mut self, graph: &mut RenderGraph<'a>, frame: PostFrame<'a>, input: PostTarget, output: PostTarget);
+
+  /// 着色器文件 `file` 热重载后重建管线，与自己无关的文件直接忽略
+  fn reload(&mut self, _device: &Device, _file: &str, _source: &str) -> Result<(), String> {
+    Ok(())
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+// 只有一个全屏 pass 的效果共用的 add_passes
+fn add_single_pass<'a>(graph: &mut RenderGraph<'a>, name: &'static str, pass: &'a mut FullscreenPass, frame: PostFrame<'a>, input: PostTarget, output: PostTarget, params: PostParams) {
+  pass.prepare(frame.device, output.format);
+  let pass: &'a FullscreenPass = pass;
+  graph.add_pass(name).read(input.id).write(output.id).execute(move |encoder, res| {
+    pass.draw(frame.device, encoder, res.view(output.id), output.format, [res.view(input.id), res.view(input.id)], &params);
+  });
+}
+
+/// 色调映射方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapping {
+  None, // 只截断到 [0, 1]
+  #[default]
+  Aces,
+  Reinhard,
+  AgX,
+}
+
+impl ToneMapping {
+  /// 按 None、ACES、Reinhard、AgX 的顺序切换
+  pub fn next(self) -> Self {
+    match self {
+      ToneMapping::None => ToneMapping::Aces,
+      ToneMapping::Aces => ToneMapping::Reinhard,
+      ToneMapping::Reinhard => ToneMapping::AgX,
+      ToneMapping::AgX => ToneMapping::None,
+    }
+  }
+}
+
+/// 曝光和色调映射，把 HDR 颜色转换为交换链格式
+pub struct ToneMap {
+  pub enabled: bool,
+  pub exposure: f32, // 曝光倍数
+  pub mapping: ToneMapping,
+  pass: FullscreenPass,
+}
+
+impl ToneMap {
+  pub fn new(device: &Device, source: &str) -> Self {
+    Self { enabled: true, exposure: 1.0, mapping: ToneMapping::default(), pass: FullscreenPass::new(device, "Tone Map", source, "fs_tonemap") }
+  }
+}
+
+impl PostEffect for ToneMap {
+  fn name(&self) -> &'static str {
+    "tonemap"
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn output_format(&self, _input: TextureFormat, surface: TextureFormat) -> TextureFormat {
+    surface
+  }
+
+  fn add_passes<'a>(&'a mut self, graph: &mut RenderGraph<'a>, frame: PostFrame<'a>, input: PostTarget, output: PostTarget) {
+    let encode = if output.format.is_srgb() { 0.0 } else { 1.0 };
+    let params = [[self.exposure, self.mapping as u32 as f32, encode, 0.0], [0.0; 4], [0.0; 4], [0.0; 4]];
+    add_single_pass(graph, "tonemap", &mut self.pass, frame, input, output, params);
+  }
+
+  fn reload(&mut self, device: &Device, file: &str, source: &str) -> Result<(), String> {
+    if file != "tonemap.wgsl" {
+      return Ok(())
+    }
+    self.pass.rebuild(device, source)
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+/// 泛光：亮度超过阈值的部分逐级缩小模糊后叠加回原图
+pub struct Bloom {
+  pub enabled: bool,
+  pub threshold: f32, // 亮度阈值，HDR 中超过 1.0 的部分才发光
+  pub knee: f32, // 阈值附近的柔和过渡范围
+  pub intensity: f32, // 叠加强度
+  pub radius: f32, // 放大时的采样半径（纹素）
+  pub levels: usize, // 缩小的级数，第一级为半分辨率
+  prefilter: FullscreenPass,
+  downsample: FullscreenPass,
+  upsample: FullscreenPass,
+  composite: FullscreenPass,
+}
+
+impl Bloom {
+  pub fn new(device: &Device, source: &str) -> Self {
+    Self {
+      enabled: true,
+      threshold: 1.0,
+      knee: 0.5,
+      intensity: 0.3,
+      radius: 1.0,
+      levels: 5,
+      prefilter: FullscreenPass::new(device, "Bloom Prefilter", source, "fs_prefilter"),
+      downsample: FullscreenPass::new(device, "Bloom Downsample", source, "fs_downsample"),
+      upsample: FullscreenPass::new(device, "Bloom Upsample", source, "fs_upsample"),
+      composite: FullscreenPass::new(device, "Bloom Composite", source, "fs_composite"),
+    }
+  }
+}
+
+impl PostEffect for Bloom {
+  fn name(&self) -> &'static str {
+    "bloom"
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn add_passes<'a>(&'a mut self, graph: &mut RenderGraph<'a>, frame: PostFrame<'a>, input: PostTarget, output: PostTarget) {
+    let device = frame.device;
+    for pass in [&mut self.prefilter, &mut self.downsample, &mut self.upsample] {
+      pass.prepare(device, HDR_FORMAT);
+    }
+    self.composite.prepare(device, output.format);
+    let this: &'a Self = self;
+    let params = [[this.threshold, this.knee, this.radius, this.intensity], [0.0; 4], [0.0; 4], [0.0; 4]];
+
+    // 每一级缩小一半，太小时提前停止
+    let mut down = vec![];
+    let (mut width, mut height) = (frame.width, frame.height);
+    for _ in 0..this.levels.max(1) {
+      if !down.is_empty() && (width < 4 || height < 4) {
+        break
+      }
+      width = (width / 2).max(1);
+      height = (height / 2).max(1);
+      down.push((graph.create("bloom_down", TextureDesc::new(width, height, HDR_FORMAT)), width, height));
+    }
+    let first = down[0].0;
+    graph.add_pass("bloom_prefilter").read(input.id).write(first).execute(move |encoder, res| {
+      this.prefilter.draw(device, encoder, res.view(first), HDR_FORMAT, [res.view(input.id), res.view(input.id)], &params);
+    });
+    for pair in down.windows(2) {
+      let (from, to) = (pair[0].0, pair[1].0);
+      graph.add_pass("bloom_downsample").read(from).write(to).execute(move |encoder, res| {
+        this.downsample.draw(device, encoder, res.view(to), HDR_FORMAT, [res.view(from), res.view(from)], &params);
+      });
+    }
+    // 从最小的一级开始放大，每一级加上同样大小的缩小结果
+    let mut blurred = down[down.len() - 1].0;
+    for &(current, width, height) in down.iter().rev().skip(1) {
+      let from = blurred;
+      let to = graph.create("bloom_up", TextureDesc::new(width, height, HDR_FORMAT));
+      graph.add_pass("bloom_upsample").read(from).read(current).write(to).execute(move |encoder, res| {
+        this.upsample.draw(device, encoder, res.view(to), HDR_FORMAT, [res.view(from), res.view(current)], &params);
+      });
+      blurred = to;
+    }
+    graph.add_pass("bloom_composite").read(input.id).read(blurred).write(output.id).execute(move |encoder, res| {
+      this.composite.draw(device, encoder, res.view(output.id), output.format, [res.view(input.id), res.view(blurred)], &params);
+    });
+  }
+
+  fn reload(&mut self, device: &Device, file: &str, source: &str) -> Result<(), String> {
+    if file != "bloom.wgsl" {
+      return Ok(())
+    }
+    for pass in [&mut self.prefilter, &mut self.downsample, &mut self.upsample, &mut self.composite] {
+      pass.rebuild(device, source)?;
+    }
+    Ok(())
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+/// FXAA 抗锯齿，放在色调映射之后
+pub struct Fxaa {
+  pub enabled: bool,
+  pub span_max: f32, // 沿边缘方向最大搜索跨度（像素）
+  pub reduce_mul: f32,
+  pub reduce_min: f32,
+  pub edge_threshold: f32, // 局部对比度低于最大亮度的这个比例时不处理
+  pass: FullscreenPass,
+}
+
+impl Fxaa {
+  pub fn new(device: &Device, source: &str) -> Self {
+    Self {
+      enabled: true,
+      span_max: 8.0,
+      reduce_mul: 1.0 / 8.0,
+      reduce_min: 1.0 / 128.0,
+      edge_threshold: 0.125,
+      pass: FullscreenPass::new(device, "FXAA", source, "fs_fxaa"),
+    }
+  }
+}
+
+impl PostEffect for Fxaa {
+  fn name(&self) -> &'static str {
+    "fxaa"
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn add_passes<'a>(&'a mut self, graph: &mut RenderGraph<'a>, frame: PostFrame<'a>, input: PostTarget, output: PostTarget) {
+    let params = [[self.span_max, self.reduce_mul, self.reduce_min, self.edge_threshold], [0.0; 4], [0.0; 4], [0.0; 4]];
+    add_single_pass(graph, "fxaa", &mut self.pass, frame, input, output, params);
+  }
+
+  fn reload(&mut self, device: &Device, file: &str, source: &str) -> Result<(), String> {
+    if file != "fxaa.wgsl" {
+      return Ok(())
+    }
+    self.pass.rebuild(device, source)
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+/// SSAO 最多的采样数，与 ssao.wgsl 中的 MAX_SAMPLES 一致
+pub const MAX_SSAO_SAMPLES: usize = 64;
+// 遮蔽结果的格式：r 为可见度，g 为到相机的距离
+const AO_FORMAT: TextureFormat = TextureFormat::Rg16Float;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct SsaoUniform {
+  pvm: [[f32; 4]; 4],
+  inv_pvm: [[f32; 4]; 4],
+  eye: [f32; 4], // xyz 相机位置，w 采样半径
+  settings: [f32; 4], // 深度偏移、采样数、强度、是否使用法线纹理
+  kernel: [[f32; 4]; MAX_SSAO_SAMPLES],
+}
+
+unsafe impl bytemuck::Zeroable for SsaoUniform {}
+unsafe impl bytemuck::Pod for SsaoUniform {}
+
+// 半球内的采样偏移（z 朝上），方向按黄金角分布并偏向法线，长度偏向中心，结果固定不随帧变化
+fn ssao_kernel() -> [[f32; 4]; MAX_SSAO_SAMPLES] {
+  let golden_angle = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+  let mut kernel = [[0.0; 4]; MAX_SSAO_SAMPLES];
+  for (i, sample) in kernel.iter_mut().enumerate() {
+    let u = (i as f32 + 0.5) / MAX_SSAO_SAMPLES as f32;
+    let (r, z) = (u.sqrt(), (1.0 - u).sqrt());
+    let angle = i as f32 * golden_angle;
+    let t = ((i as f32 + 0.5) * 0.754_877_7).fract();
+    let scale = 0.1 + 0.9 * t * t;
+    *sample = [r * angle.cos() * scale, r * angle.sin() * scale, z * scale, 0.0];
+  }
+  kernel
+}
+
+/// 屏幕空间环境光遮蔽：由深度重建位置和法线，半球采样后双边模糊，乘到 HDR 颜色上
+///
+/// 场景着色器没有单独的环境光项，遮蔽直接作用在输出颜色上。
+pub struct Ssao {
+  pub enabled: bool,
+  pub radius: f32, // 采样半径（场景单位）
+  pub intensity: f32, // 遮蔽强度，1.0 时全部采样被挡住的像素完全变黑
+  pub bias: f32, // 深度偏移（场景单位），避免平面自遮挡
+  pub samples: usize, // 采样数，最多 `MAX_SSAO_SAMPLES`
+  pub blur: bool, // 是否双边模糊
+  pub blur_sharpness: f32, // 距离差异对模糊权重的影响，越大边缘越清晰
+  kernel: [[f32; 4]; MAX_SSAO_SAMPLES],
+  ao: FullscreenPass,
+  blur_pass: FullscreenPass,
+  composite: FullscreenPass,
+  empty_normals: TextureView, // 没有法线纹理时绑定的占位纹理
+}
+
+impl Ssao {
+  /// - `source`: ssao.wgsl
+  /// - `blur_source`: ssao_blur.wgsl
+  pub fn new(device: &Device, source: &str, blur_source: &str) -> Self {
+    let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("SSAO Bind Group Layout"),
+      entries: &[
+        BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: false },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 1,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: false },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 2,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
+          count: None,
+        },
+      ],
+    });
+    let empty_normals = device.create_texture(&TextureDescriptor {
+      label: Some("SSAO Empty Normals"),
+      size: Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: TextureDimension::D2,
+      format: HDR_FORMAT,
+      usage: TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    }).create_view(&TextureViewDescriptor::default());
+    Self {
+      enabled: true,
+      radius: 40.0,
+      intensity: 1.0,
+      bias: 2.0,
+      samples: 16,
+      blur: true,
+      blur_sharpness: 8.0,
+      kernel: ssao_kernel(),
+      ao: FullscreenPass::with_layout(device, "SSAO", source, "fs_ssao", layout),
+      blur_pass: FullscreenPass::new(device, "SSAO Blur", blur_source, "fs_blur"),
+      composite: FullscreenPass::new(device, "SSAO Composite", blur_source, "fs_composite"),
+      empty_normals,
+    }
+  }
+
+  fn uniform(&self, scene: &SceneDepth) -> SsaoUniform {
+    let inv_pvm = scene.pvm.try_inverse().unwrap_or_else(Matrix4::identity);
+    let use_normals = if scene.normals.is_some() { 1.0 } else { 0.0 };
+    SsaoUniform {
+      pvm: scene.pvm.into(),
+      inv_pvm: inv_pvm.into(),
+      eye: [scene.eye.x, scene.eye.y, scene.eye.z, self.radius],
+      settings: [self.bias, self.samples.clamp(1, MAX_SSAO_SAMPLES) as f32, self.intensity, use_normals],
+      kernel: self.kernel,
+    }
+  }
+}
+
+impl PostEffect for Ssao {
+  fn name(&self) -> &'static str {
+    "ssao"
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn requires_depth(&self) -> bool {
+    true
+  }
+
+  fn add_passes<'a>(&'a mut self, graph: &mut RenderGraph<'a>, frame: PostFrame<'a>, input: PostTarget, output: PostTarget) {
+    let Some(scene) = frame.scene else {
+      return
+    };
+    let device = frame.device;
+    self.ao.prepare(device, AO_FORMAT);
+    self.blur_pass.prepare(device, AO_FORMAT);
+    self.composite.prepare(device, output.format);
+    let this: &'a Self = self;
+    let uniform = this.uniform(&scene);
+
+    let raw = graph.create("ssao", TextureDesc::new(frame.width, frame.height, AO_FORMAT));
+    let mut pass = graph.add_pass("ssao").read(scene.depth);
+    if let Some(normals) = scene.normals {
+      pass = pass.read(normals);
+    }
+    pass.write(raw).execute(move |encoder, res| {
+      let buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("SSAO Uniform"),
+        contents: bytemuck::cast_slice(&[uniform]),
+        usage: BufferUsages::UNIFORM,
+      });
+      let normals = scene.normals.map(|n| res.view(n)).unwrap_or(&this.empty_normals);
+      let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("SSAO Bind Group"),
+        layout: this.ao.layout(),
+        entries: &[
+          BindGroupEntry { binding: 0, resource: BindingResource::TextureView(res.view(scene.depth)) },
+          BindGroupEntry { binding: 1, resource: BindingResource::TextureView(normals) },
+          BindGroupEntry { binding: 2, resource: buffer.as_entire_binding() },
+        ],
+      });
+      this.ao.draw_bind_group(encoder, res.view(raw), AO_FORMAT, &bind_group);
+    });
+
+    // 先横向再纵向模糊
+    let mut ao = raw;
+    if this.blur {
+      for direction in [[1.0, 0.0], [0.0, 1.0]] {
+        let (from, to) = (ao, graph.create("ssao_blur", TextureDesc::new(frame.width, frame.height, AO_FORMAT)));
+        let params = [[direction[0], direction[1], this.blur_sharpness, 0.0], [0.0; 4], [0.0; 4], [0.0; 4]];
+        graph.add_pass("ssao_blur").read(from).write(to).execute(move |encoder, res| {
+          this.blur_pass.draw(device, encoder, res.view(to), AO_FORMAT, [res.view(from), res.view(from)], &params);
+        });
+        ao = to;
+      }
+    }
+    let params = [[0.0; 4]; 4];
+    graph.add_pass("ssao_composite").read(input.id).read(ao).write(output.id).execute(move |encoder, res| {
+      this.composite.draw(device, encoder, res.view(output.id), output.format, [res.view(input.id), res.view(ao)], &params);
+    });
+  }
+
+  fn reload(&mut self, device: &Device, file: &str, source: &str) -> Result<(), String> {
+    match file {
+      "ssao.wgsl" => self.ao.rebuild(device, source),
+      "ssao_blur.wgsl" => self.blur_pass.rebuild(device, source).and_then(|_| self.composite.rebuild(device, source)),
+      _ => Ok(()),
+    }
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+/// 自定义的全屏效果：一个片元着色器入口，参数直接传给着色器
+///
+/// 源码中用 `#include "post_common.wgsl"` 引入绑定和顶点着色器，先经过 `ShaderLibrary::compile_source` 预处理。
+pub struct FullscreenEffect {
+  pub enabled: bool,
+  pub params: PostParams,
+  name: &'static str,
+  pass: FullscreenPass,
+}
+
+impl FullscreenEffect {
+  pub fn new(device: &Device, name: &'static str, source: &str, entry: &'static str) -> Self {
+    Self { enabled: true, params: [[0.0; 4]; 4], name, pass: FullscreenPass::new(device, name, source, entry) }
+  }
+}
+
+impl PostEffect for FullscreenEffect {
+  fn name(&self) -> &'static str {
+    self.name
+  }
+
+  fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn add_passes<'a>(&'a mut self, graph: &mut RenderGraph<'a>, frame: PostFrame<'a>, input: PostTarget, output: PostTarget) {
+    add_single_pass(graph, self.name, &mut self.pass, frame, input, output, self.params);
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+/// 后处理链：按顺序执行启用的效果，默认为 SSAO、泛光、色调映射、FXAA
+///
+/// 最后的结果与交换链格式不同（例如关闭了色调映射）时，再加一个复制 pass 转换格式。
+pub struct PostChain {
+  effects: Vec<Box<dyn PostEffect>>,
+  blit: FullscreenPass,
+}
+
+impl PostChain {
+  pub fn new(device: &Device, shaders: &mut ShaderLibrary) -> Self {
+    let ssao_source = shaders.variant("ssao.wgsl", ShaderFeatures::NONE).to_string();
+    Self {
+      effects: vec![
+        Box::new(Ssao::new(device, &ssao_source, shaders.variant("ssao_blur.wgsl", ShaderFeatures::NONE))),
+        Box::new(Bloom::new(device, shaders.variant("bloom.wgsl", ShaderFeatures::NONE))),
+        Box::new(ToneMap::new(device, shaders.variant("tonemap.wgsl", ShaderFeatures::NONE))),
+        Box::new(Fxaa::new(device, shaders.variant("fxaa.wgsl", ShaderFeatures::NONE))),
+      ],
+      blit: FullscreenPass::new(device, "Blit", shaders.variant("blit.wgsl", ShaderFeatures::NONE), "fs_blit"),
+    }
+  }
+
+  /// 添加到最后
+  pub fn push(&mut self, effect: Box<dyn PostEffect>) {
+    self.effects.push(effect);
+  }
+
+  /// 插入到 `index` 的位置，例如插入到色调映射之前处理 HDR 颜色
+  pub fn insert(&mut self, index: usize, effect: Box<dyn PostEffect>) {
+    self.effects.insert(index.min(self.effects.len()), effect);
+  }
+
+  /// 按名称移除
+  pub fn remove(&mut self, name: &str) -> Option<Box<dyn PostEffect>> {
+    let index = self.effects.iter().position(|e| e.name() == name)?;
+    Some(self.effects.remove(index))
+  }
+
+  /// 效果的名称，按执行顺序
+  pub fn names(&self) -> Vec<&'static str> {
+    self.effects.iter().map(|e| e.name()).collect()
+  }
+
+  /// 按类型取出效果修改参数，例如 `get_mut::<Bloom>()`
+  pub fn get_mut<T: PostEffect + 'static>(&mut self) -> Option<&mut T> {
+    self.effects.iter_mut().find_map(|e| e.as_any_mut().downcast_mut::<T>())
+  }
+
+  /// 在渲染图中添加后处理，读取 `input`（一般为 HDR 场景），最终写入 `output`
+  pub fn add_passes<'a>(&'a mut self, graph: &mut RenderGraph<'a>, frame: PostFrame<'a>, input: PostTarget, output: PostTarget) {
+    let effects: Vec<&'a mut Box<dyn PostEffect>> = self.effects.iter_mut()
+      .filter(|e| e.enabled() && (frame.scene.is_some() || !e.requires_depth()))
+      .collect();
+    let count = effects.len();
+    let mut current = input;
+    for (i, effect) in effects.into_iter().enumerate() {
+      let format = effect.output_format(current.format, output.format);
+      let target = if i + 1 == count && format == output.format {
+        output
+      } else {
+        PostTarget { id: graph.create(effect.name(), TextureDesc::new(frame.width, frame.height, format)), format }
+      };
+      effect.add_passes(graph, frame, current, target);
+      current = target;
+    }
+    if current.id != output.id {
+      let encode = if needs_srgb_encode(current.format, output.format) { 1.0 } else { 0.0 };
+      let params = [[encode, 0.0, 0.0, 0.0], [0.0; 4], [0.0; 4], [0.0; 4]];
+      add_single_pass(graph, "blit", &mut self.blit, frame, current, output, params);
+    }
+  }
+
+  /// 着色器热重载后重建使用 `file` 的管线
+  pub fn reload(&mut self, device: &Device, file: &str, source: &str) -> Result<(), String> {
+    if file == "blit.wgsl" {
+      self.blit.rebuild(device, source)?;
+    }
+    for effect in self.effects.iter_mut() {
+      effect.reload(device, file, source)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,291 @@
+use std::collections::{HashMap, HashSet};
+
+use super::shader::ShaderError;
+
+/// 预处理后的着色器源码，记录每一行来自哪个文件的哪一行
+#[derive(Clone, Debug)]
+pub struct Preprocessed {
+  pub source: String,
+  pub files: Vec<String>, // 用到的文件，第一个是入口文件
+  lines: Vec<(usize, u32)>, // 输出的第 i 行对应的文件下标和行号（从 1 开始）
+}
+
+impl Preprocessed {
+  /// 输出的第 `line` 行（从 1 开始）在原文件中的位置
+  pub fn origin(&self, line: u32) -> Option<(&str, u32)> {
+    let (file, line) = self.lines.get((line as usize).checked_sub(1)?)?;
+    Some((self.files[*file].as_str(), *line))
+  }
+
+  /// 把预处理后源码上的错误位置换算回原文件
+  pub fn map_error(&self, mut error: ShaderError) -> ShaderError {
+    if let Some((file, line)) = self.origin(error.line) {
+      error.file = file.to_string();
+      error.line = line;
+    }
+    error
+  }
+}
+
+// #ifdef 的嵌套状态
+struct Condition {
+  parent_active: bool, // 外层是否生效
+  taken: bool, // 当前分支的条件
+  in_else: bool,
+}
+
+struct Preprocessor<'a> {
+  load: &'a dyn Fn(&str) -> Option<&'a str>,
+  defines: HashMap<String, String>, // 宏名到替换文本，只用于 #ifdef 的宏替换文本为空
+  included: HashSet<String>, // 同一个文件只引入一次
+  stack: Vec<String>, // 正在处理的 include 链，用于发现循环引用
+  out: Preprocessed,
+}
+
+/// 预处理 WGSL：支持 `#include "file"`、`#define NAME [value]`、`#undef`、`#ifdef`/`#ifndef`/`#else`/`#endif`
+///
+/// - `defines`: 预先定义的宏，例如着色器变体的特性开关
+/// - `load`: 按文件名读取源码
+///
+/// 每个文件只会被引入一次；带值的宏会替换之后出现的同名标识符。
+pub fn preprocess<'a>(name: &str, defines: &[&str], load: &'a dyn Fn(&str) -> Option<&'a str>) -> Result<Preprocessed, ShaderError> {
+  let mut preprocessor = Preprocessor {
+    load,
+    defines: defines.iter().map(|d| (d.to_string(), String::new())).collect(),
+    included: HashSet::new(),
+    stack: vec![],
+    out: Preprocessed { source: String::new(), files: vec![], lines: vec![] },
+  };
+  preprocessor.process(name, None)?;
+  Ok(preprocessor.out)
+}
+
+fn error(file: &str, line: u32, message: String) -> ShaderError {
+  ShaderError { file: file.to_string(), line, column: 0, message }
+}
+
+impl Preprocessor<'_> {
+  // `from`: 引入该文件的 #include 所在的文件和行号
+  fn process(&mut self, name: &str, from: Option<(&str, u32)>) -> Result<(), ShaderError> {
+    let (from_file, from_line) = from.unwrap_or((name, 0));
+    if self.stack.iter().any(|n| n == name) {
+      return Err(error(from_file, from_line, format!("循环引用 {}", name)));
+    }
+    if !self.included.insert(name.to_string()) {
+      return Ok(());
+    }
+    let Some(source) = (self.load)(name) else {
+      return Err(error(from_file, from_line, format!("找不到文件 {}", name)));
+    };
+    self.stack.push(name.to_string());
+    let file = self.out.files.len();
+    self.out.files.push(name.to_string());
+
+    let mut conditions: Vec<Condition> = vec![];
+    let mut last_line = 0;
+    for (i, text) in source.lines().enumerate() {
+      let line = i as u32 + 1;
+      last_line = line;
+      let active = conditions.last().map(|c| c.parent_active && c.taken != c.in_else).unwrap_or(true);
+      let Some(directive) = text.trim_start().strip_prefix('#') else {
+        if active {
+          self.out.source.push_str(&self.substitute(text));
+          self.out.source.push('\n');
+          self.out.lines.push((file, line));
+        }
+        continue
+      };
+      let mut parts = directive.trim().splitn(2, char::is_whitespace);
+      let command = parts.next().unwrap_or_default();
+      let argument = parts.next().unwrap_or_default().trim();
+      match command {
+        "ifdef" | "ifndef" => {
+          if argument.is_empty() {
+            return Err(error(name, line, format!("#{} 缺少宏名", command)));
+          }
+          let defined = self.defines.contains_key(argument);
+          conditions.push(Condition { parent_active: active, taken: defined == (command == "ifdef"), in_else: false });
+        },
+        "else" => match conditions.last_mut() {
+          Some(c) if !c.in_else => c.in_else = true,
+          _ => return Err(error(name, line, "多余的 #else".to_string())),
+        },
+        "endif" => {
+          if conditions.pop().is_none() {
+            return Err(error(name, line, "多余的 #endif".to_string()));
+          }
+        },
+        _ if !active => {},
+        "define" => {
+          let mut parts = argument.splitn(2, char::is_whitespace);
+          let Some(macro_name) = parts.next().filter(|n| !n.is_empty()) else {
+            return Err(error(name, line, "#define 缺少宏名".to_string()));
+          };
+          let value = parts.next().unwrap_or_default().trim();
+          let value = self.substitute(value);
+          self.defines.insert(macro_name.to_string(), value);
+        },
+        "undef" => {
+          self.defines.remove(argument);
+        },
+        "include" => {
+          let Some(path) = argument.strip_prefix('"').and_then(|a| a.strip_suffix('"')) else {
+            return Err(error(name, line, format!("#include 的文件名需要用引号括起来：{}", argument)));
+          };
+          self.process(path, Some((name, line)))?;
+        },
+        _ => return Err(error(name, line, format!("未知的预处理指令 #{}", command))),
+      }
+    }
+    if !conditions.is_empty() {
+      return Err(error(name, last_line, "缺少 #endif".to_string()));
+    }
+    self.stack.pop();
+    Ok(())
+  }
+
+  // 替换带值的宏，只替换完整的标识符
+  fn substitute(&self, text: &str) -> String {
+    if self.defines.values().all(|v| v.is_empty()) {
+      return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut ident = String::new();
+    for c in text.chars().chain(std::iter::once('\n')) {
+      if c.is_ascii_alphanumeric() || c == '_' {
+        ident.push(c);
+        continue
+      }
+      if !ident.is_empty() {
+        match self.defines.get(&ident) {
+          // 数字开头的不是标识符
+          Some(value) if !value.is_empty() && !ident.starts_with(|c: char| c.is_ascii_digit()) => out.push_str(value),
+          _ => out.push_str(&ident),
+        }
+        ident.clear();
+      }
+      if c != '\n' {
+        out.push(c);
+      }
+    }
+    out
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::render::shader::validate;
+
+  fn run(files: &[(&'static str, &'static str)], defines: &[&str]) -> Result<Preprocessed, ShaderError> {
+    let files = files.to_vec();
+    let load = move |name: &str| files.iter().find(|(n, _)| *n == name).map(|(_, s)| *s);
+    // 入口文件总是 main.wgsl
+    preprocess("main.wgsl", defines, &load)
+  }
+
+  fn lines(output: &Preprocessed) -> Vec<&str> {
+    output.source.lines().collect()
+  }
+
+  #[test]
+  fn includes_each_file_once() {
+    let output = run(&[
+      ("main.wgsl", "#include \"a.wgsl\"\n#include \"b.wgsl\"\nmain"),
+      ("a.wgsl", "#include \"common.wgsl\"\na"),
+      ("b.wgsl", "#include \"common.wgsl\"\nb"),
+      ("common.wgsl", "common"),
+    ], &[]).unwrap();
+    assert_eq!(lines(&output), vec!["common", "a", "b", "main"]);
+    assert_eq!(output.files, vec!["main.wgsl", "a.wgsl", "common.wgsl", "b.wgsl"]);
+  }
+
+  #[test]
+  fn include_errors() {
+    let err = run(&[
+      ("main.wgsl", "#include \"a.wgsl\""),
+      ("a.wgsl", "a\n#include \"b.wgsl\""),
+      ("b.wgsl", "#include \"a.wgsl\""),
+    ], &[]).unwrap_err();
+    assert!(err.message.contains("循环引用 a.wgsl"), "{}", err);
+    assert_eq!((err.file.as_str(), err.line), ("b.wgsl", 1));
+
+    let err = run(&[("main.wgsl", "x\n#include \"missing.wgsl\"")], &[]).unwrap_err();
+    assert!(err.message.contains("missing.wgsl"));
+    assert_eq!((err.file.as_str(), err.line), ("main.wgsl", 2));
+
+    let err = run(&[("main.wgsl", "#include missing.wgsl")], &[]).unwrap_err();
+    assert!(err.message.contains("引号"));
+  }
+
+  #[test]
+  fn defines_replace_whole_identifiers() {
+    let output = run(&[(
+      "main.wgsl",
+      "#define SIZE 4\n#define DOUBLE SIZE * 2\nlet a = SIZE;\nlet b = SIZE_X + MY_SIZE + SIZE4 + 4SIZE;\nlet c = DOUBLE;\n#undef SIZE\nlet d = SIZE;",
+    )], &[]).unwrap();
+    // 宏的值在定义时展开
+    assert_eq!(lines(&output), vec!["let a = 4;", "let b = SIZE_X + MY_SIZE + SIZE4 + 4SIZE;", "let c = 4 * 2;", "let d = SIZE;"]);
+  }
+
+  #[test]
+  fn nested_conditions() {
+    let source = "\
+#ifdef A
+a
+#ifndef B
+a_not_b
+#else
+a_b
+#endif
+#else
+not_a
+#ifdef B
+not_a_b
+#endif
+#endif
+always";
+    let files = [("main.wgsl", source)];
+    assert_eq!(lines(&run(&files, &[]).unwrap()), vec!["not_a", "always"]);
+    assert_eq!(lines(&run(&files, &["A"]).unwrap()), vec!["a", "a_not_b", "always"]);
+    assert_eq!(lines(&run(&files, &["A", "B"]).unwrap()), vec!["a", "a_b", "always"]);
+    assert_eq!(lines(&run(&files, &["B"]).unwrap()), vec!["not_a", "not_a_b", "always"]);
+
+    // 不生效的分支中的 #define 和 #include 被忽略
+    let output = run(&[("main.wgsl", "#ifdef A\n#define X 1\n#include \"missing.wgsl\"\n#endif\nX")], &[]).unwrap();
+    assert_eq!(lines(&output), vec!["X"]);
+  }
+
+  #[test]
+  fn unmatched_directives() {
+    for (source, message, line) in [
+      ("a\n#else", "多余的 #else", 2),
+      ("#ifdef A\n#else\n#else\n#endif", "多余的 #else", 3),
+      ("#endif", "多余的 #endif", 1),
+      ("#ifdef A\na\nb", "缺少 #endif", 3),
+      ("#ifdef", "缺少宏名", 1),
+      ("#pragma once", "未知的预处理指令", 1),
+    ] {
+      let err = run(&[("main.wgsl", source)], &[]).unwrap_err();
+      assert!(err.message.contains(message), "{:?}: {}", source, err);
+      assert_eq!(err.line, line, "{:?}", source);
+    }
+  }
+
+  #[test]
+  fn maps_naga_errors_back_to_included_files() {
+    let output = run(&[
+      ("main.wgsl", "// 入口\n#include \"math.wgsl\"\n\nfn main_value() -> f32 {\n  return half(2.0);\n}"),
+      ("math.wgsl", "fn half(x: f32) -> f32 {\n  return x * 0.5;\n}\n\nfn broken() -> f32 {\n  return undefined_name;\n}"),
+    ], &[]).unwrap();
+    assert_eq!(output.origin(1), Some(("main.wgsl", 1)));
+    assert_eq!(output.origin(2), Some(("math.wgsl", 1)));
+    assert_eq!(output.origin(9), Some(("main.wgsl", 3)));
+    assert_eq!(output.origin(0), None);
+    assert_eq!(output.origin(100), None);
+
+    let err = output.map_error(validate("main.wgsl", &output.source).unwrap_err());
+    assert_eq!((err.file.as_str(), err.line), ("math.wgsl", 6), "{}", err);
+    assert!(err.message.contains("undefined_name") || err.message.contains("no definition"), "{}", err);
+  }
+}
@@ -0,0 +1,619 @@
+use std::collections::VecDeque;
+use std::ops::AddAssign;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use wgpu::*;
+use web_time::Instant;
+
+use crate::text::layout::TextStyle;
+
+use super::text::TextRenderer;
+
+/// 滚动统计和 Chrome trace 保留的帧数
+pub const PROFILE_HISTORY: usize = 240;
+// 每帧最多的时间戳查询数，每个 pass 使用两个
+const MAX_QUERIES: u32 = 256;
+// resolve 的目标偏移需要按 QUERY_RESOLVE_BUFFER_ALIGNMENT 对齐，每次 resolve 的起点是这么多个查询的倍数
+const QUERY_ALIGNMENT: u32 = (QUERY_RESOLVE_BUFFER_ALIGNMENT / QUERY_SIZE as u64) as u32;
+// GPU 结果读回的缓冲区数量，结果一般晚 1～2 帧可用
+const READBACK_SLOTS: usize = 3;
+// 读回缓冲区的映射状态
+const MAP_WAITING: u8 = 0;
+const MAP_READY: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+/// 一帧的绘制调用数和三角形数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+  pub draw_calls: u32,
+  pub triangles: u64,
+}
+
+impl DrawStats {
+  /// 记录一次三角形列表的绘制
+  pub fn add_draw(&mut self, vertices: u32) {
+    self.draw_calls += 1;
+    self.triangles += vertices as u64 / 3;
+  }
+}
+
+impl AddAssign for DrawStats {
+  fn add_assign(&mut self, other: Self) {
+    self.draw_calls += other.draw_calls;
+    self.triangles += other.triangles;
+  }
+}
+
+/// 最近 `PROFILE_HISTORY` 个样本的统计
+#[derive(Clone, Debug, Default)]
+pub struct RollingStats {
+  samples: VecDeque<f32>,
+}
+
+impl RollingStats {
+  pub fn push(&mut self, value: f32) {
+    if self.samples.len() == PROFILE_HISTORY {
+      self.samples.pop_front();
+    }
+    self.samples.push_back(value);
+  }
+
+  pub fn last(&self) -> f32 {
+    self.samples.back().copied().unwrap_or(0.0)
+  }
+
+  pub fn min(&self) -> f32 {
+    self.samples.iter().copied().reduce(f32::min).unwrap_or(0.0)
+  }
+
+  pub fn max(&self) -> f32 {
+    self.samples.iter().copied().reduce(f32::max).unwrap_or(0.0)
+  }
+
+  pub fn avg(&self) -> f32 {
+    if self.samples.is_empty() {
+      return 0.0
+    }
+    self.samples.iter().sum::<f32>() / self.samples.len() as f32
+  }
+
+  /// 第 99 百分位数（最近邻取整）
+  pub fn p99(&self) -> f32 {
+    if self.samples.is_empty() {
+      return 0.0
+    }
+    let mut sorted: Vec<f32> = self.samples.iter().copied().collect();
+    sorted.sort_by(f32::total_cmp);
+    let rank = (sorted.len() as f32 * 0.99).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+  }
+
+  pub fn samples(&self) -> impl Iterator<Item = f32> + '_ {
+    self.samples.iter().copied()
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Track {
+  Cpu,
+  Gpu,
+}
+
+#[derive(Clone, Debug)]
+struct TraceEvent {
+  name: &'static str,
+  track: Track,
+  start: f64, // 距 Profiler 创建的微秒数
+  duration: f64, // 微秒
+}
+
+struct FrameRecord {
+  index: u64,
+  start: f64, // 微秒
+  draw_stats: DrawStats,
+  target_allocations: u32,
+  events: Vec<TraceEvent>,
+}
+
+/// 已提交读回、等待映射完成的一帧 GPU 时间戳
+struct PendingFrame {
+  frame: u64,
+  labels: Vec<(&'static str, u32)>,
+}
+
+struct ReadbackSlot {
+  buffer: Buffer,
+  state: Arc<AtomicU8>, // MAP_WAITING、MAP_READY 或 MAP_FAILED
+  pending: Option<PendingFrame>,
+}
+
+/// 一帧中一个 pass 的 GPU 时间，单位为纳秒，起点为这一帧第一个 pass 开始的时间
+struct GpuSpan {
+  name: &'static str,
+  start: f64,
+  duration: f64,
+}
+
+/// 用时间戳查询测量渲染图中每个 pass 的 GPU 时间
+///
+/// 渲染图在每个 pass 前后写入时间戳（需要 `TIMESTAMP_QUERY_INSIDE_ENCODERS`），帧结束时复制到读回缓冲区，
+/// 映射完成后再读取，不会等待 GPU。一帧内渲染图可以执行多次（截图、录制、分块渲染），
+/// 每次执行 resolve 到 resolve 缓冲区中对齐的一段，之后的查询从下一个对齐位置开始。
+pub struct GpuTimer {
+  query_set: QuerySet,
+  resolve_buffer: Buffer,
+  period: f32, // 每个时间戳单位的纳秒数
+  labels: Vec<(&'static str, u32)>, // 本帧的 pass 名称和开始时间戳的查询下标，结束时间戳是下一个查询
+  next_query: u32, // 本帧下一个可用的查询
+  resolved: u32, // 本帧已经 resolve 到的查询下标，总是 `QUERY_ALIGNMENT` 的倍数
+  slots: Vec<ReadbackSlot>,
+}
+
+impl GpuTimer {
+  /// 设备需要开启 `GpuTimer::FEATURES`
+  pub const FEATURES: Features = Features::TIMESTAMP_QUERY.union(Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
+  /// 设备不支持时间戳查询时返回 None
+  pub fn new(device: &Device, queue: &Queue) -> Option<Self> {
+    if !device.features().contains(Self::FEATURES) {
+      return None
+    }
+    let size = MAX_QUERIES as u64 * QUERY_SIZE as u64;
+    let query_set = device.create_query_set(&QuerySetDescriptor {
+      label: Some("Profiler Query Set"),
+      ty: QueryType::Timestamp,
+      count: MAX_QUERIES,
+    });
+    let resolve_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Profiler Resolve Buffer"),
+      size,
+      usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+    let slots = (0..READBACK_SLOTS).map(|_| ReadbackSlot {
+      buffer: device.create_buffer(&BufferDescriptor {
+        label: Some("Profiler Readback Buffer"),
+        size,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+      }),
+      state: Arc::new(AtomicU8::new(MAP_WAITING)),
+      pending: None,
+    }).collect();
+    Some(Self { query_set, resolve_buffer, period: queue.get_timestamp_period(), labels: vec![], next_query: 0, resolved: 0, slots })
+  }
+
+  /// 在 pass 开始前写入时间戳，查询用完时返回 None
+  pub fn begin_pass(&mut self, encoder: &mut CommandEncoder, name: &'static str) -> Option<u32> {
+    let index = self.next_query;
+    if index + 2 > MAX_QUERIES {
+      return None
+    }
+    encoder.write_timestamp(&self.query_set, index);
+    self.labels.push((name, index));
+    self.next_query += 2;
+    Some(index)
+  }
+
+  pub fn end_pass(&mut self, encoder: &mut CommandEncoder, index: u32) {
+    encoder.write_timestamp(&self.query_set, index + 1);
+  }
+
+  /// 把本次记录的查询结果写入 resolve 缓冲区，每个 CommandEncoder 提交前调用
+  pub fn resolve(&mut self, encoder: &mut CommandEncoder) {
+    let Some((queries, offset)) = resolve_range(self.resolved, self.next_query) else {
+      return
+    };
+    encoder.resolve_query_set(&self.query_set, queries, &self.resolve_buffer, offset);
+    self.next_query = align_query(self.next_query).min(MAX_QUERIES);
+    self.resolved = self.next_query;
+  }
+
+  /// 复制本帧的结果到空闲的读回缓冲区并开始映射，没有空闲缓冲区时丢弃这一帧
+  fn end_frame(&mut self, device: &Device, queue: &Queue, frame: u64) {
+    let labels = std::mem::take(&mut self.labels);
+    self.next_query = 0;
+    self.resolved = 0;
+    if labels.is_empty() {
+      return
+    }
+    let Some(slot) = self.slots.iter_mut().find(|s| s.pending.is_none()) else {
+      return
+    };
+    let size = readback_size(&labels);
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Profiler Readback") });
+    encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &slot.buffer, 0, size);
+    queue.submit(Some(encoder.finish()));
+    let state = slot.state.clone();
+    slot.buffer.slice(..size).map_async(MapMode::Read, move |result| {
+      state.store(if result.is_ok() { MAP_READY } else { MAP_FAILED }, Ordering::Release);
+    });
+    slot.pending = Some(PendingFrame { frame, labels });
+  }
+
+  /// 取出已经映射完成的帧，映射失败的帧丢弃，缓冲区可以重新使用
+  fn collect(&mut self, device: &Device) -> Vec<(u64, Vec<GpuSpan>)> {
+    let _ = device.poll(PollType::Poll);
+    let mut frames = vec![];
+    for slot in self.slots.iter_mut() {
+      let state = slot.state.swap(MAP_WAITING, Ordering::Acquire);
+      if state == MAP_WAITING {
+        continue
+      }
+      let Some(pending) = slot.pending.take() else {
+        continue
+      };
+      if state == MAP_FAILED {
+        println!("读取第 {} 帧的 GPU 时间戳失败", pending.frame);
+        continue
+      }
+      let size = readback_size(&pending.labels);
+      let timestamps: Vec<u64> = bytemuck::cast_slice(&slot.buffer.slice(..size).get_mapped_range()).to_vec();
+      slot.buffer.unmap();
+      let origin = pending.labels.iter().map(|(_, q)| timestamps[*q as usize]).min().unwrap_or(0);
+      let to_ns = |ticks: u64| ticks as f64 * self.period as f64;
+      let spans = pending.labels.iter().map(|&(name, q)| {
+        let (begin, end) = (timestamps[q as usize], timestamps[q as usize + 1]);
+        GpuSpan { name, start: to_ns(begin.saturating_sub(origin)), duration: to_ns(end.saturating_sub(begin)) }
+      }).collect();
+      frames.push((pending.frame, spans));
+    }
+    frames.sort_by_key(|(frame, _)| *frame);
+    frames
+  }
+}
+
+// 查询下标向上取整到可以作为 resolve 起点的位置
+fn align_query(index: u32) -> u32 {
+  index.next_multiple_of(QUERY_ALIGNMENT)
+}
+
+// 要 resolve 的查询范围和在 resolve 缓冲区中的偏移，`resolved` 是对齐的起点，`next` 是下一个未使用的查询
+fn resolve_range(resolved: u32, next: u32) -> Option<(std::ops::Range<u32>, BufferAddress)> {
+  (next > resolved).then(|| (resolved..next, resolved as BufferAddress * QUERY_SIZE as BufferAddress))
+}
+
+// 读回到最后一个 pass 的结束时间戳为止的字节数
+fn readback_size(labels: &[(&'static str, u32)]) -> BufferAddress {
+  labels.iter().map(|(_, q)| (*q as BufferAddress + 2) * QUERY_SIZE as BufferAddress).max().unwrap_or(0)
+}
+
+/// 帧性能分析：CPU 作用域、渲染图 pass 的 GPU 时间、绘制调用和三角形数
+///
+/// 每帧先 `begin_frame`，CPU 代码用 `begin_scope`/`end_scope` 包起来，帧结束时 `end_frame`。
+/// 统计名称为 `cpu:作用域名`、`gpu:pass 名`，另外有 `frame`（帧间隔）、`cpu`、`gpu` 三个总计，
+/// 以及 `draw_calls`、`triangles`、`target_allocations` 三个计数。
+pub struct Profiler {
+  pub show_overlay: bool,
+  pub draw_stats: DrawStats, // 本帧累计，由绘制代码加上
+  pub target_allocations: u32, // 本帧新建的渲染目标数，尺寸或格式不变时应为 0
+  gpu: Option<GpuTimer>,
+  origin: Instant,
+  frame: u64,
+  frame_start: Option<Instant>,
+  scopes: Vec<(&'static str, Instant)>, // 未结束的 CPU 作用域
+  events: Vec<TraceEvent>, // 本帧的 CPU 事件
+  stats: Vec<(String, RollingStats)>, // 按第一次出现的顺序
+  history: VecDeque<FrameRecord>,
+}
+
+impl Profiler {
+  pub fn new(device: &Device, queue: &Queue) -> Self {
+    let gpu = GpuTimer::new(device, queue);
+    if gpu.is_none() {
+      println!("设备不支持时间戳查询，性能分析只有 CPU 时间");
+    }
+    Self {
+      show_overlay: false,
+      draw_stats: DrawStats::default(),
+      target_allocations: 0,
+      gpu,
+      origin: Instant::now(),
+      frame: 0,
+      frame_start: None,
+      scopes: vec![],
+      events: vec![],
+      stats: vec![],
+      history: VecDeque::new(),
+    }
+  }
+
+  /// 渲染图执行时传入，设备不支持时间戳查询时为 None
+  pub fn gpu_timer(&mut self) -> Option<&mut GpuTimer> {
+    self.gpu.as_mut()
+  }
+
+  fn micros(&self, time: Instant) -> f64 {
+    time.duration_since(self.origin).as_secs_f64() * 1e6
+  }
+
+  fn push_stat(&mut self, name: &str, value: f32) {
+    match self.stats.iter_mut().find(|(n, _)| n == name) {
+      Some((_, stats)) => stats.push(value),
+      None => {
+        let mut stats = RollingStats::default();
+        stats.push(value);
+        self.stats.push((name.to_string(), stats));
+      },
+    }
+  }
+
+  pub fn stats(&self, name: &str) -> Option<&RollingStats> {
+    self.stats.iter().find(|(n, _)| n == name).map(|(_, s)| s)
+  }
+
+  pub fn begin_frame(&mut self) {
+    let now = Instant::now();
+    if let Some(start) = self.frame_start {
+      self.push_stat("frame", now.duration_since(start).as_secs_f32() * 1000.0);
+    }
+    self.frame_start = Some(now);
+    self.scopes.clear();
+    self.events.clear();
+    self.draw_stats = DrawStats::default();
+    self.target_allocations = 0;
+  }
+
+  pub fn begin_scope(&mut self, name: &'static str) {
+    self.scopes.push((name, Instant::now()));
+  }
+
+  /// 结束最近一个 `begin_scope`
+  pub fn end_scope(&mut self) {
+    let Some((name, start)) = self.scopes.pop() else {
+      return
+    };
+    let duration = start.elapsed().as_secs_f64() * 1e6;
+    self.events.push(TraceEvent { name, track: Track::Cpu, start: self.micros(start), duration });
+  }
+
+  /// 结束本帧：汇总 CPU 时间和绘制统计，提交 GPU 时间戳的读回，并取出之前的帧已经完成的 GPU 时间
+  pub fn end_frame(&mut self, device: &Device, queue: &Queue) {
+    while !self.scopes.is_empty() {
+      self.end_scope();
+    }
+    let Some(frame_start) = self.frame_start else {
+      return
+    };
+    let start = self.micros(frame_start);
+    let cpu = frame_start.elapsed().as_secs_f32() * 1000.0;
+    self.push_stat("cpu", cpu);
+    // 同名作用域在一帧内的时间相加
+    let mut cpu_totals: Vec<(&'static str, f64)> = vec![];
+    for event in self.events.iter() {
+      match cpu_totals.iter_mut().find(|(n, _)| *n == event.name) {
+        Some((_, total)) => *total += event.duration,
+        None => cpu_totals.push((event.name, event.duration)),
+      }
+    }
+    for (name, total) in cpu_totals {
+      self.push_stat(&format!("cpu:{}", name), (total / 1000.0) as f32);
+    }
+    self.push_stat("draw_calls", self.draw_stats.draw_calls as f32);
+    self.push_stat("triangles", self.draw_stats.triangles as f32);
+    self.push_stat("target_allocations", self.target_allocations as f32);
+
+    let mut events = std::mem::take(&mut self.events);
+    events.insert(0, TraceEvent { name: "frame", track: Track::Cpu, start, duration: cpu as f64 * 1000.0 });
+    if self.history.len() == PROFILE_HISTORY {
+      self.history.pop_front();
+    }
+    self.history.push_back(FrameRecord { index: self.frame, start, draw_stats: self.draw_stats, target_allocations: self.target_allocations, events });
+
+    let finished = match self.gpu.as_mut() {
+      Some(gpu) => {
+        gpu.end_frame(device, queue, self.frame);
+        gpu.collect(device)
+      },
+      None => vec![],
+    };
+    for (frame, spans) in finished {
+      self.add_gpu_frame(frame, spans);
+    }
+    self.frame += 1;
+  }
+
+  fn add_gpu_frame(&mut self, frame: u64, spans: Vec<GpuSpan>) {
+    let total = spans.iter().map(|s| s.start + s.duration).fold(0.0, f64::max);
+    self.push_stat("gpu", (total / 1e6) as f32);
+    let mut totals: Vec<(&'static str, f64)> = vec![];
+    for span in spans.iter() {
+      match totals.iter_mut().find(|(n, _)| *n == span.name) {
+        Some((_, t)) => *t += span.duration,
+        None => totals.push((span.name, span.duration)),
+      }
+    }
+    for (name, t) in totals {
+      self.push_stat(&format!("gpu:{}", name), (t / 1e6) as f32);
+    }
+    // GPU 时钟与 CPU 时钟没有对齐，trace 中按这一帧 CPU 开始的时间放置
+    if let Some(record) = self.history.iter_mut().find(|r| r.index == frame) {
+      let start = record.start;
+      record.events.extend(spans.iter().map(|s| TraceEvent { name: s.name, track: Track::Gpu, start: start + s.start / 1000.0, duration: s.duration / 1000.0 }));
+    }
+  }
+
+  /// 在屏幕右上角绘制统计文字和帧间隔曲线，`width` 为屏幕宽度
+  pub fn queue_overlay(&self, text_renderer: &mut TextRenderer, width: f32) {
+    if !self.show_overlay {
+      return
+    }
+    const PANEL_WIDTH: f32 = 420.0;
+    const GRAPH_HEIGHT: f32 = 80.0;
+    const LINE_HEIGHT: f32 = 20.0;
+    let style = TextStyle::default();
+    let x = width - PANEL_WIDTH - 10.0;
+    let mut lines = vec![];
+    let summary = |name: &str, label: &str| self.stats(name).map(|s| format!("{} {:.2} ms（最小 {:.2} / 平均 {:.2} / p99 {:.2}）", label, s.last(), s.min(), s.avg(), s.p99()));
+    lines.extend(summary("frame", "帧间隔"));
+    lines.extend(summary("cpu", "CPU"));
+    match summary("gpu", "GPU") {
+      Some(line) => lines.push(line),
+      None if self.gpu.is_none() => lines.push("GPU 不支持时间戳查询".to_string()),
+      None => {},
+    }
+    if let (Some(draws), Some(triangles)) = (self.stats("draw_calls"), self.stats("triangles")) {
+      lines.push(format!("绘制调用 {:.0}  三角形 {:.0}", draws.last(), triangles.last()));
+    }
+    if let Some(allocations) = self.stats("target_allocations") {
+      lines.push(format!("新建渲染目标 {:.0}（最近 {} 帧共 {:.0}）", allocations.last(), PROFILE_HISTORY, allocations.samples().sum::<f32>()));
+    }
+    for (name, stats) in self.stats.iter().filter(|(n, _)| n.starts_with("cpu:") || n.starts_with("gpu:")) {
+      lines.push(format!("  {} 平均 {:.3} / p99 {:.3} ms", name, stats.avg(), stats.p99()));
+    }
+    let height = GRAPH_HEIGHT + lines.len() as f32 * LINE_HEIGHT + 20.0;
+    text_renderer.queue_rect(x, 10.0, PANEL_WIDTH, height, [0.0, 0.0, 0.0, 0.6]);
+
+    // 帧间隔曲线，满高度为 33.3 ms，超过 16.7 ms 的帧标红
+    if let Some(frames) = self.stats("frame") {
+      let bar_width = PANEL_WIDTH / PROFILE_HISTORY as f32;
+      let graph_bottom = 10.0 + GRAPH_HEIGHT;
+      text_renderer.queue_rect(x, graph_bottom - GRAPH_HEIGHT / 2.0, PANEL_WIDTH, 1.0, [1.0, 1.0, 1.0, 0.3]);
+      for (i, ms) in frames.samples().enumerate() {
+        let h = (ms / 33.3).min(1.0) * GRAPH_HEIGHT;
+        let color = if ms > 16.7 { [1.0, 0.3, 0.3, 0.9] } else { [0.3, 1.0, 0.4, 0.9] };
+        text_renderer.queue_rect(x + i as f32 * bar_width, graph_bottom - h, bar_width.max(1.0), h, color);
+      }
+    }
+    for (i, line) in lines.iter().enumerate() {
+      text_renderer.queue_text(line, x + 8.0, 20.0 + GRAPH_HEIGHT + i as f32 * LINE_HEIGHT, &style, [1.0, 1.0, 1.0, 1.0]);
+    }
+  }
+
+  /// 导出最近 `PROFILE_HISTORY` 帧为 Chrome trace JSON，可以在 chrome://tracing 或 Perfetto 中打开
+  pub fn export_trace(&self, path: &Path) -> Result<(), String> {
+    let trace = trace_json(&self.history);
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir).map_err(|e| format!("创建目录 {} 失败：{}", dir.display(), e))?;
+    }
+    let text = serde_json::to_string(&trace).map_err(|e| e.to_string())?;
+    std::fs::write(path, text).map_err(|e| format!("写入 {} 失败：{}", path.display(), e))
+  }
+}
+
+// Chrome trace 格式：CPU、GPU 各一条轨道，每帧再加上绘制统计和渲染目标创建次数两个计数器
+fn trace_json(history: &VecDeque<FrameRecord>) -> serde_json::Value {
+  let mut events = vec![
+    serde_json::json!({ "name": "thread_name", "ph": "M", "pid": 1, "tid": 1, "args": { "name": "CPU" } }),
+    serde_json::json!({ "name": "thread_name", "ph": "M", "pid": 1, "tid": 2, "args": { "name": "GPU" } }),
+  ];
+  for record in history.iter() {
+    for event in record.events.iter() {
+      let tid = match event.track {
+        Track::Cpu => 1,
+        Track::Gpu => 2,
+      };
+      events.push(serde_json::json!({
+        "name": event.name, "cat": "frame", "ph": "X", "pid": 1, "tid": tid,
+        "ts": event.start, "dur": event.duration, "args": { "frame": record.index },
+      }));
+    }
+    events.push(serde_json::json!({
+      "name": "draw", "ph": "C", "pid": 1, "ts": record.start,
+      "args": { "draw_calls": record.draw_stats.draw_calls, "triangles": record.draw_stats.triangles },
+    }));
+    events.push(serde_json::json!({
+      "name": "render_targets", "ph": "C", "pid": 1, "ts": record.start,
+      "args": { "created": record.target_allocations },
+    }));
+  }
+  serde_json::json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resolve_offsets_are_aligned() {
+    assert_eq!(QUERY_ALIGNMENT, 32);
+    assert_eq!([0, 1, 31, 32, 33].map(align_query), [0, 32, 32, 32, 64]);
+
+    // 一帧内执行两次渲染图：第一次 3 个 pass，第二次 2 个 pass，从对齐的位置开始
+    assert_eq!(resolve_range(0, 6), Some((0..6, 0)));
+    let second = align_query(6);
+    let (queries, offset) = resolve_range(second, second + 4).unwrap();
+    assert_eq!(queries, 32..36);
+    assert_eq!(offset, 256);
+    assert_eq!(offset % QUERY_RESOLVE_BUFFER_ALIGNMENT, 0);
+    // 没有新的查询时不 resolve
+    assert_eq!(resolve_range(32, 32), None);
+
+    let labels = [("scene", 0), ("post", 2), ("ui", 4), ("scene", 32), ("post", 34)];
+    assert_eq!(readback_size(&labels), 36 * QUERY_SIZE as u64);
+    assert_eq!(readback_size(&[]), 0);
+  }
+
+  #[test]
+  fn rolling_stats() {
+    let mut stats = RollingStats::default();
+    assert_eq!((stats.last(), stats.min(), stats.max(), stats.avg(), stats.p99()), (0.0, 0.0, 0.0, 0.0, 0.0));
+
+    // 1..=100 的乱序样本：最近邻取整的第 99 百分位数是第 99 个
+    for i in 0..100 {
+      stats.push(((i * 37) % 100 + 1) as f32);
+    }
+    assert_eq!(stats.p99(), 99.0);
+    assert_eq!((stats.min(), stats.max(), stats.avg()), (1.0, 100.0, 50.5));
+    assert_eq!(stats.last(), ((99 * 37) % 100 + 1) as f32);
+
+    // 样本不足 100 个时取最大值
+    let mut small = RollingStats::default();
+    for v in [3.0, 1.0, 2.0] {
+      small.push(v);
+    }
+    assert_eq!(small.p99(), 3.0);
+
+    // 超出窗口后丢弃最早的样本
+    let mut stats = RollingStats::default();
+    for i in 0..PROFILE_HISTORY + 10 {
+      stats.push(i as f32);
+    }
+    assert_eq!(stats.samples().count(), PROFILE_HISTORY);
+    assert_eq!(stats.samples().next(), Some(10.0));
+    assert_eq!(stats.min(), 10.0);
+    assert_eq!(stats.last(), (PROFILE_HISTORY + 9) as f32);
+  }
+
+  #[test]
+  fn trace_events() {
+    let mut history = VecDeque::new();
+    history.push_back(FrameRecord {
+      index: 7,
+      start: 1000.0,
+      draw_stats: DrawStats { draw_calls: 3, triangles: 120 },
+      target_allocations: 2,
+      events: vec![
+        TraceEvent { name: "frame", track: Track::Cpu, start: 1000.0, duration: 500.0 },
+        TraceEvent { name: "scene", track: Track::Gpu, start: 1100.0, duration: 250.0 },
+      ],
+    });
+    let trace = trace_json(&history);
+    assert_eq!(trace["displayTimeUnit"], "ms");
+    let events = trace["traceEvents"].as_array().unwrap();
+    assert_eq!(events.len(), 6);
+
+    // 轨道名称
+    assert_eq!(events[0], serde_json::json!({ "name": "thread_name", "ph": "M", "pid": 1, "tid": 1, "args": { "name": "CPU" } }));
+    assert_eq!(events[1]["tid"], 2);
+    assert_eq!(events[1]["args"]["name"], "GPU");
+
+    assert_eq!(events[2], serde_json::json!({
+      "name": "frame", "cat": "frame", "ph": "X", "pid": 1, "tid": 1, "ts": 1000.0, "dur": 500.0, "args": { "frame": 7 },
+    }));
+    assert_eq!(events[3], serde_json::json!({
+      "name": "scene", "cat": "frame", "ph": "X", "pid": 1, "tid": 2, "ts": 1100.0, "dur": 250.0, "args": { "frame": 7 },
+    }));
+
+    assert_eq!(events[4], serde_json::json!({
+      "name": "draw", "ph": "C", "pid": 1, "ts": 1000.0, "args": { "draw_calls": 3, "triangles": 120 },
+    }));
+    assert_eq!(events[5], serde_json::json!({
+      "name": "render_targets", "ph": "C", "pid": 1, "ts": 1000.0, "args": { "created": 2 },
+    }));
+
+    // 没有记录时只有轨道名称
+    assert_eq!(trace_json(&VecDeque::new())["traceEvents"].as_array().unwrap().len(), 2);
+  }
+}
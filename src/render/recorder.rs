@@ -0,0 +1,280 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::constants::{RECORD_DIR, RECORD_FPS, RECORD_HEIGHT, RECORD_WIDTH};
+
+use super::screenshot::save_png;
+
+/// 录制输出的格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+  PngSequence, // 目录中按帧号命名的 PNG 文件
+  Y4m, // 未压缩的 YUV4MPEG2 视频，4:2:0 全范围 BT.601，可以直接交给 ffmpeg 编码
+}
+
+/// 录制参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecorderConfig {
+  pub width: u32,
+  pub height: u32,
+  pub fps: u32,
+  pub format: RecordFormat,
+}
+
+impl Default for RecorderConfig {
+  fn default() -> Self {
+    Self { width: RECORD_WIDTH, height: RECORD_HEIGHT, fps: RECORD_FPS, format: RecordFormat::PngSequence }
+  }
+}
+
+impl RecorderConfig {
+  /// 解析 `宽x高@帧率[:png|:y4m]`，例如 `1920x1080@60:y4m`，省略的部分使用默认值
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let mut config = Self::default();
+    let (rest, format) = match text.trim().split_once(':') {
+      Some((rest, format)) => (rest, Some(format)),
+      None => (text.trim(), None),
+    };
+    let (size, fps) = match rest.split_once('@') {
+      Some((size, fps)) => (size, Some(fps)),
+      None => (rest, None),
+    };
+    if !size.is_empty() {
+      let (w, h) = size.split_once('x').ok_or_else(|| format!("录制尺寸格式错误：{}", size))?;
+      config.width = w.parse().map_err(|_| format!("录制宽度错误：{}", w))?;
+      config.height = h.parse().map_err(|_| format!("录制高度错误：{}", h))?;
+    }
+    if let Some(fps) = fps {
+      config.fps = fps.parse().map_err(|_| format!("录制帧率错误：{}", fps))?;
+    }
+    config.format = match format {
+      None | Some("png") => RecordFormat::PngSequence,
+      Some("y4m") => RecordFormat::Y4m,
+      Some(other) => return Err(format!("不支持的录制格式：{}", other)),
+    };
+    if config.width == 0 || config.height == 0 || config.fps == 0 {
+      return Err(format!("录制参数无效：{}", text));
+    }
+    Ok(config)
+  }
+}
+
+enum Output {
+  Png { dir: PathBuf },
+  Y4m { file: BufWriter<File>, path: PathBuf },
+}
+
+struct Recording {
+  config: RecorderConfig,
+  output: Output,
+  frames: u32,
+}
+
+/// 录制帧序列，录制期间每帧按固定的模拟时间步长推进，不使用实际经过的时间
+///
+/// 每帧由 `WgpuCtx::draw` 按录制尺寸离屏渲染（不包括文字）后写入，相同的输入在软件适配器上逐位一致。
+#[derive(Default)]
+pub struct Recorder {
+  recording: Option<Recording>,
+}
+
+impl Recorder {
+  /// 开始录制到 `RECORD_DIR`，文件名包含视图名称和开始时间；正在录制时先结束之前的录制
+  pub fn start(&mut self, config: RecorderConfig, view: &str) -> Result<(), String> {
+    let time = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let view = if view.is_empty() { "scene" } else { view };
+    let name = format!("{}_{}_{}x{}_{}fps", view, time, config.width, config.height, config.fps);
+    self.start_in(config, Path::new(RECORD_DIR), &name)
+  }
+
+  // 开始录制到 `dir` 中名为 `name` 的目录（PNG）或文件（Y4M）
+  fn start_in(&mut self, config: RecorderConfig, dir: &Path, name: &str) -> Result<(), String> {
+    self.stop();
+    let output = match config.format {
+      RecordFormat::PngSequence => {
+        let dir = dir.join(name);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("创建录制目录 {} 失败：{}", dir.display(), e))?;
+        Output::Png { dir }
+      },
+      RecordFormat::Y4m => {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建录制目录 {} 失败：{}", dir.display(), e))?;
+        let path = dir.join(format!("{}.y4m", name));
+        let file = File::create(&path).map_err(|e| format!("创建录制文件 {} 失败：{}", path.display(), e))?;
+        let mut file = BufWriter::new(file);
+        writeln!(file, "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg", config.width, config.height, config.fps)
+          .map_err(|e| format!("写入录制文件 {} 失败：{}", path.display(), e))?;
+        Output::Y4m { file, path }
+      },
+    };
+    println!("开始录制：{}x{} {} 帧/秒 {:?}", config.width, config.height, config.fps, config.format);
+    self.recording = Some(Recording { config, output, frames: 0 });
+    Ok(())
+  }
+
+  /// 结束录制并关闭文件，没有在录制时什么都不做
+  pub fn stop(&mut self) {
+    let Some(recording) = self.recording.take() else {
+      return
+    };
+    let path = match recording.output {
+      Output::Png { dir } => dir,
+      Output::Y4m { mut file, path } => {
+        if let Err(e) = file.flush() {
+          println!("写入录制文件 {} 失败：{}", path.display(), e);
+        }
+        path
+      },
+    };
+    println!("录制结束：{} 帧，保存在 {}", recording.frames, path.display());
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self.recording.is_some()
+  }
+
+  /// 录制期间每帧的模拟时间（秒），没有录制时返回 None，使用实际经过的时间
+  pub fn frame_time(&self) -> Option<f32> {
+    self.recording.as_ref().map(|r| 1.0 / r.config.fps as f32)
+  }
+
+  /// 录制的画面尺寸
+  pub fn size(&self) -> Option<(u32, u32)> {
+    self.recording.as_ref().map(|r| (r.config.width, r.config.height))
+  }
+
+  /// 写入一帧 RGBA 像素，尺寸与 `size` 一致；写入失败时结束录制
+  pub fn write_frame(&mut self, pixels: &[u8]) -> Result<(), String> {
+    let Some(recording) = self.recording.as_mut() else {
+      return Ok(())
+    };
+    let RecorderConfig { width, height, .. } = recording.config;
+    let result = match &mut recording.output {
+      Output::Png { dir } => save_png(&dir.join(format!("frame_{:06}.png", recording.frames)), width, height, pixels),
+      Output::Y4m { file, path } => {
+        file.write_all(b"FRAME\n")
+          .and_then(|_| file.write_all(&rgba_to_yuv420(width, height, pixels)))
+          .map_err(|e| format!("写入录制文件 {} 失败：{}", path.display(), e))
+      },
+    };
+    match result {
+      Ok(()) => recording.frames += 1,
+      Err(_) => self.stop(),
+    }
+    result
+  }
+}
+
+impl Drop for Recorder {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+/// RGBA 转为 Y、U、V 三个平面，色度按 2x2 平均（奇数尺寸向上取整），使用整数运算保证结果稳定
+fn rgba_to_yuv420(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+  let (w, h) = (width as usize, height as usize);
+  let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+  let mut out = vec![0u8; w * h + cw * ch * 2];
+  let (y_plane, chroma) = out.split_at_mut(w * h);
+  let (u_plane, v_plane) = chroma.split_at_mut(cw * ch);
+  let rgb = |x: usize, y: usize| {
+    let i = (y * w + x) * 4;
+    (pixels[i] as i32, pixels[i + 1] as i32, pixels[i + 2] as i32)
+  };
+  // BT.601 全范围系数乘以 2^16
+  for y in 0..h {
+    for x in 0..w {
+      let (r, g, b) = rgb(x, y);
+      y_plane[y * w + x] = ((19595 * r + 38470 * g + 7471 * b + 32768) >> 16).clamp(0, 255) as u8;
+    }
+  }
+  for cy in 0..ch {
+    for cx in 0..cw {
+      let (mut r, mut g, mut b, mut n) = (0, 0, 0, 0);
+      for y in cy * 2..(cy * 2 + 2).min(h) {
+        for x in cx * 2..(cx * 2 + 2).min(w) {
+          let (pr, pg, pb) = rgb(x, y);
+          (r, g, b, n) = (r + pr, g + pg, b + pb, n + 1);
+        }
+      }
+      let (r, g, b) = ((r + n / 2) / n, (g + n / 2) / n, (b + n / 2) / n);
+      u_plane[cy * cw + cx] = (((-11059 * r - 21709 * g + 32768 * b + 32768) >> 16) + 128).clamp(0, 255) as u8;
+      v_plane[cy * cw + cx] = (((32768 * r - 27439 * g - 5329 * b + 32768) >> 16) + 128).clamp(0, 255) as u8;
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_config() {
+    assert_eq!(RecorderConfig::parse("1920x1080@60:y4m"), Ok(RecorderConfig { width: 1920, height: 1080, fps: 60, format: RecordFormat::Y4m }));
+    assert_eq!(RecorderConfig::parse(" 640x480 "), Ok(RecorderConfig { width: 640, height: 480, ..RecorderConfig::default() }));
+    assert_eq!(RecorderConfig::parse("@24"), Ok(RecorderConfig { fps: 24, ..RecorderConfig::default() }));
+    assert_eq!(RecorderConfig::parse(":png"), Ok(RecorderConfig::default()));
+    assert_eq!(RecorderConfig::parse(""), Ok(RecorderConfig::default()));
+    for text in ["1920", "ax1080", "1920x1080@", "1920x1080:mp4", "0x1080", "1920x1080@0"] {
+      assert!(RecorderConfig::parse(text).is_err(), "{}", text);
+    }
+  }
+
+  #[test]
+  fn yuv_of_solid_colors() {
+    // 全范围 BT.601：白 (255, 128, 128)，黑 (0, 128, 128)，红 (76, 85, 255)，绿 (150, 44, 21)，蓝 (29, 255, 107)
+    for (rgb, yuv) in [([255, 255, 255], [255, 128, 128]), ([0, 0, 0], [0, 128, 128]), ([255, 0, 0], [76, 85, 255]), ([0, 255, 0], [150, 44, 21]), ([0, 0, 255], [29, 255, 107])] {
+      let pixels: Vec<u8> = (0..4).flat_map(|_| [rgb[0], rgb[1], rgb[2], 255]).collect();
+      assert_eq!(rgba_to_yuv420(2, 2, &pixels), vec![yuv[0], yuv[0], yuv[0], yuv[0], yuv[1], yuv[2]], "{:?}", rgb);
+    }
+  }
+
+  #[test]
+  fn yuv_chroma_averages_blocks_with_odd_sizes() {
+    // 3x1：第一个色度块平均前两个像素，第二个块只有最后一个像素
+    let pixels = [255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255];
+    let out = rgba_to_yuv420(3, 1, &pixels);
+    assert_eq!(out.len(), 3 + 2 * 2);
+    assert_eq!(&out[..3], &[76, 29, 29]);
+    // 平均色 (128, 0, 128) 的 U、V 为 170、182，蓝色为 255、107
+    assert_eq!(&out[3..], &[170, 255, 182, 107]);
+  }
+
+  // 按帧号生成的渐变画面，代替离屏渲染的结果
+  fn frame(width: u32, height: u32, index: u32) -> Vec<u8> {
+    (0..width * height).flat_map(|i| {
+      let (x, y) = (i % width, i / width);
+      [(x * 255 / width) as u8, (y * 255 / height) as u8, (index * 40) as u8, 255]
+    }).collect()
+  }
+
+  fn record_y4m(dir: &Path, name: &str, config: RecorderConfig, frames: u32) -> Vec<u8> {
+    let mut recorder = Recorder::default();
+    recorder.start_in(config, dir, name).unwrap();
+    for i in 0..frames {
+      recorder.write_frame(&frame(config.width, config.height, i)).unwrap();
+    }
+    recorder.stop();
+    std::fs::read(dir.join(format!("{}.y4m", name))).unwrap()
+  }
+
+  #[test]
+  fn y4m_sequences_are_identical() {
+    let dir = std::env::temp_dir().join(format!("kidar_recorder_test_{}", std::process::id()));
+    let config = RecorderConfig { width: 6, height: 4, fps: 30, format: RecordFormat::Y4m };
+    let first = record_y4m(&dir, "first", config, 5);
+    let second = record_y4m(&dir, "second", config, 5);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(first, second);
+    let header = b"YUV4MPEG2 W6 H4 F30:1 Ip A1:1 C420jpeg\n";
+    assert!(first.starts_with(header));
+    let frame_size = b"FRAME\n".len() + 6 * 4 + 3 * 2 * 2;
+    assert_eq!(first.len(), header.len() + frame_size * 5);
+    let last = &first[first.len() - frame_size..];
+    assert!(last.starts_with(b"FRAME\n"));
+    assert_eq!(&last[6..], rgba_to_yuv420(6, 4, &frame(6, 4, 4)).as_slice());
+  }
+}
@@ -0,0 +1,251 @@
+use std::path::{Path, PathBuf};
+
+use nalgebra::Matrix4;
+use wgpu::*;
+
+use crate::constants::SCREENSHOT_DIR;
+
+/// 截图输出的纹理格式，高分辨率截图离屏渲染到这个格式
+pub const SCREENSHOT_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// 一次截图请求，在下一帧绘制时处理
+#[derive(Clone, Debug)]
+pub struct ScreenshotRequest {
+  pub view: String, // 当前视图名称，写入文件名
+  pub size: Option<(u32, u32)>, // None 时保存窗口画面（包括文字），否则按当前相机重新渲染到这个尺寸
+}
+
+/// 把纹理复制到可映射的缓冲区，再读回 CPU
+///
+/// 缓冲区每行按 `COPY_BYTES_PER_ROW_ALIGNMENT` 对齐，读回时去掉对齐的部分，BGRA 转为 RGBA。
+pub struct Readback {
+  buffer: Buffer,
+  width: u32,
+  height: u32,
+  padded_bytes_per_row: u32,
+  bgra: bool,
+}
+
+impl Readback {
+  /// 只支持 8 位的 RGBA/BGRA 格式
+  pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat) -> Result<Self, String> {
+    let bgra = match format {
+      TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+      TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+      _ => return Err(format!("截图不支持纹理格式 {:?}", format)),
+    };
+    let padded_bytes_per_row = (width * 4).div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Screenshot Buffer"),
+      size: padded_bytes_per_row as u64 * height as u64,
+      usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+    Ok(Self { buffer, width, height, padded_bytes_per_row, bgra })
+  }
+
+  /// 记录复制命令，`texture` 需要有 `COPY_SRC` 用途
+  pub fn copy(&self, encoder: &mut CommandEncoder, texture: &Texture) {
+    encoder.copy_texture_to_buffer(
+      texture.as_image_copy(),
+      TexelCopyBufferInfo {
+        buffer: &self.buffer,
+        layout: TexelCopyBufferLayout {
+          offset: 0,
+          bytes_per_row: Some(self.padded_bytes_per_row),
+          rows_per_image: Some(self.height),
+        },
+      },
+      Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+    );
+  }
+
+  /// 等待复制完成并返回紧密排列的 RGBA 像素，会阻塞到 GPU 执行完已提交的命令
+  ///
+  /// 浏览器中不能阻塞等待映射，返回错误。
+  pub fn read(self, device: &Device) -> Result<Vec<u8>, String> {
+    if cfg!(target_arch = "wasm32") {
+      return Err("浏览器中不支持同步读回纹理".to_string());
+    }
+    let slice = self.buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+      let _ = sender.send(result);
+    });
+    device.poll(PollType::Wait).map_err(|e| format!("等待截图数据失败：{}", e))?;
+    receiver.recv().map_err(|e| e.to_string())?.map_err(|e| format!("映射截图缓冲区失败：{}", e))?;
+
+    let row_bytes = (self.width * 4) as usize;
+    let mut pixels = Vec::with_capacity(row_bytes * self.height as usize);
+    {
+      let data = slice.get_mapped_range();
+      for row in data.chunks(self.padded_bytes_per_row as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+      }
+    }
+    self.buffer.unmap();
+    if self.bgra {
+      for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+      }
+    }
+    Ok(pixels)
+  }
+}
+
+/// 高分辨率截图中的一块，像素坐标以整张图片左上角为原点
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+  pub x: u32,
+  pub y: u32,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl Tile {
+  /// 左乘到相机矩阵上，把这一块在整张图片中的范围放大到整个裁剪空间
+  pub fn clip_offset(&self, full_width: u32, full_height: u32) -> Matrix4<f32> {
+    let (fw, fh) = (full_width as f32, full_height as f32);
+    let sx = fw / self.width as f32;
+    let sy = fh / self.height as f32;
+    // 这一块中心的 NDC 坐标，y 轴向上而像素行向下
+    let cx = (2.0 * self.x as f32 + self.width as f32) / fw - 1.0;
+    let cy = 1.0 - (2.0 * self.y as f32 + self.height as f32) / fh;
+    // 着色器中手动除以 w，平移量乘上 w 后除法结果才是平移
+    Matrix4::new(
+      sx, 0.0, 0.0, -cx * sx,
+      0.0, sy, 0.0, -cy * sy,
+      0.0, 0.0, 1.0, 0.0,
+      0.0, 0.0, 0.0, 1.0,
+    )
+  }
+}
+
+/// 把 `width` x `height` 的图片切成不超过 `max_size` 的块，尺寸尽量平均
+pub fn tiles(width: u32, height: u32, max_size: u32) -> Vec<Tile> {
+  let split = |size: u32| {
+    let count = size.div_ceil(max_size).max(1);
+    (0..count).map(move |i| (size * i / count, size * (i + 1) / count - size * i / count))
+  };
+  split(height).flat_map(|(y, h)| split(width).map(move |(x, w)| Tile { x, y, width: w, height: h })).collect()
+}
+
+/// 把一块的像素复制到整张图片中
+pub fn blit_tile(image: &mut [u8], full_width: u32, tile: &Tile, pixels: &[u8]) {
+  let row_bytes = (tile.width * 4) as usize;
+  for (row, src) in pixels.chunks_exact(row_bytes).enumerate() {
+    let start = (((tile.y as usize + row) * full_width as usize) + tile.x as usize) * 4;
+    image[start..start + row_bytes].copy_from_slice(src);
+  }
+}
+
+/// 截图文件路径：`SCREENSHOT_DIR/视图名_年月日_时分秒_毫秒_宽x高.png`
+pub fn screenshot_path(view: &str, width: u32, height: u32) -> PathBuf {
+  let time = chrono::Local::now().format("%Y%m%d_%H%M%S_%3f");
+  let view = if view.is_empty() { "scene" } else { view };
+  Path::new(SCREENSHOT_DIR).join(format!("{}_{}_{}x{}.png", view, time, width, height))
+}
+
+/// 写入 8 位 sRGB 的 RGBA PNG 文件，目录不存在时创建
+pub fn save_png(path: &Path, width: u32, height: u32, pixels: &[u8]) -> Result<(), String> {
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建截图目录 {} 失败：{}", dir.display(), e))?;
+  }
+  let file = std::fs::File::create(path).map_err(|e| format!("创建截图文件 {} 失败：{}", path.display(), e))?;
+  let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+  let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+  writer.write_image_data(pixels).map_err(|e| format!("写入截图 {} 失败：{}", path.display(), e))
+}
+
+/// 在后台线程编码并写入文件，不阻塞渲染；浏览器中没有线程，直接写入
+pub fn save_png_async(path: PathBuf, width: u32, height: u32, pixels: Vec<u8>) {
+  let save = move || {
+    match save_png(&path, width, height, &pixels) {
+      Ok(()) => println!("截图已保存：{}", path.display()),
+      Err(e) => println!("{}", e),
+    }
+  };
+  #[cfg(not(target_arch = "wasm32"))]
+  std::thread::spawn(save);
+  #[cfg(target_arch = "wasm32")]
+  save();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use nalgebra::Vector4;
+
+  #[test]
+  fn tiles_cover_image_once() {
+    for (width, height, max_size) in [(1000, 700, 256), (257, 256, 256), (256, 256, 256), (5, 3, 1), (100, 50, 4096)] {
+      let tiles = tiles(width, height, max_size);
+      let mut covered = vec![0u8; (width * height) as usize];
+      for tile in tiles.iter() {
+        assert!(tile.width > 0 && tile.height > 0);
+        assert!(tile.width <= max_size && tile.height <= max_size, "{:?}", tile);
+        assert!(tile.x + tile.width <= width && tile.y + tile.height <= height, "{:?}", tile);
+        for y in tile.y..tile.y + tile.height {
+          for x in tile.x..tile.x + tile.width {
+            covered[(y * width + x) as usize] += 1;
+          }
+        }
+      }
+      assert!(covered.iter().all(|&c| c == 1), "{}x{} / {}", width, height, max_size);
+      let expected = width.div_ceil(max_size) * height.div_ceil(max_size);
+      assert_eq!(tiles.len() as u32, expected);
+    }
+    // 尺寸尽量平均
+    let widths: Vec<u32> = tiles(1000, 10, 256).iter().map(|t| t.width).collect();
+    assert_eq!(widths, vec![250, 250, 250, 250]);
+  }
+
+  #[test]
+  fn clip_offset_maps_tile_corners_to_ndc() {
+    let (width, height) = (1000, 700);
+    // 整张图片中像素坐标对应的 NDC 坐标
+    let ndc = |x: u32, y: u32| Vector4::new(2.0 * x as f32 / width as f32 - 1.0, 1.0 - 2.0 * y as f32 / height as f32, 0.5, 1.0);
+    for tile in tiles(width, height, 256) {
+      let m = tile.clip_offset(width, height);
+      for (x, y, expected) in [
+        (tile.x, tile.y, (-1.0, 1.0)),
+        (tile.x + tile.width, tile.y, (1.0, 1.0)),
+        (tile.x, tile.y + tile.height, (-1.0, -1.0)),
+        (tile.x + tile.width, tile.y + tile.height, (1.0, -1.0)),
+      ] {
+        // w 不为 1 时除以 w 后结果相同
+        for w in [1.0, 2.5] {
+          let p = m * (ndc(x, y) * w);
+          assert!((p.x / p.w - expected.0).abs() < 1e-5 && (p.y / p.w - expected.1).abs() < 1e-5, "{:?} {:?}", tile, p);
+          assert!((p.z / p.w - 0.5).abs() < 1e-6);
+        }
+      }
+    }
+    // 只有一块时不变换
+    let tile = tiles(640, 480, 4096)[0];
+    assert_eq!(tile.clip_offset(640, 480), Matrix4::identity());
+  }
+
+  #[test]
+  fn blit_tiles_into_image() {
+    let (width, height) = (7, 5);
+    let mut image = vec![0u8; (width * height * 4) as usize];
+    // 每个像素的值由坐标决定，渲染出的块按行紧密排列
+    let pixel = |x: u32, y: u32| [x as u8, y as u8, (x * 16 + y) as u8, 255];
+    for tile in tiles(width, height, 3) {
+      let pixels: Vec<u8> = (tile.y..tile.y + tile.height)
+        .flat_map(|y| (tile.x..tile.x + tile.width).flat_map(move |x| pixel(x, y)))
+        .collect();
+      blit_tile(&mut image, width, &tile, &pixels);
+    }
+    for y in 0..height {
+      for x in 0..width {
+        let i = ((y * width + x) * 4) as usize;
+        assert_eq!(image[i..i + 4], pixel(x, y), "({}, {})", x, y);
+      }
+    }
+  }
+}
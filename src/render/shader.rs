@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::ops::BitOr;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use web_time::Instant;
+
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use wgpu::Device;
+
+use crate::constants::SHADER_DIR_ENV;
+
+use super::preprocess::preprocess;
+
+// 内置着色器，不开启热重载或磁盘上的着色器无效时使用
+const EMBEDDED: &[(&str, &str)] = &[
+  ("common.wgsl", include_str!("../template/common.wgsl")),
+  ("shader.wgsl", include_str!("../template/shader.wgsl")),
+  ("text.wgsl", include_str!("../template/text.wgsl")),
+  ("morph.wgsl", include_str!("../template/morph.wgsl")),
+  ("fullscreen.wgsl", include_str!("../template/fullscreen.wgsl")),
+  ("post_common.wgsl", include_str!("../template/post_common.wgsl")),
+  ("blit.wgsl", include_str!("../template/blit.wgsl")),
+  ("tonemap.wgsl", include_str!("../template/tonemap.wgsl")),
+  ("bloom.wgsl", include_str!("../template/bloom.wgsl")),
+  ("fxaa.wgsl", include_str!("../template/fxaa.wgsl")),
+  ("ssao.wgsl", include_str!("../template/ssao.wgsl")),
+  ("ssao_blur.wgsl", include_str!("../template/ssao_blur.wgsl")),
+  ("oit.wgsl", include_str!("../template/oit.wgsl")),
+];
+// 检查文件是否修改的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 着色器编译错误，行列号从 1 开始，未知时为 0
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+  pub file: String,
+  pub line: u32,
+  pub column: u32,
+  pub message: String,
+}
+
+impl fmt::Display for ShaderError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}:{}: {}", self.file, self.line, self.column, self.message)
+  }
+}
+
+/// 用 naga 解析并校验 WGSL
+pub fn validate(file: &str, source: &str) -> Result<(), ShaderError> {
+  let module = naga::front::wgsl::parse_str(source).map_err(|e| {
+    let location = e.location(source);
+    ShaderError {
+      file: file.to_string(),
+      line: location.map(|l| l.line_number).unwrap_or(0),
+      column: location.map(|l| l.line_position).unwrap_or(0),
+      message: e.message().to_string(),
+    }
+  })?;
+  Validator::new(ValidationFlags::all(), Capabilities::all()).validate(&module).map_err(|e| {
+    // 最后一个 span 指向最内层出错的表达式，比外层的函数更准确
+    let location = e.spans().last().map(|(span, _)| span.location(source));
+    ShaderError {
+      file: file.to_string(),
+      line: location.map(|l| l.line_number).unwrap_or(0),
+      column: location.map(|l| l.line_position).unwrap_or(0),
+      message: error_chain(e.as_inner()),
+    }
+  })?;
+  Ok(())
+}
+
+// 校验错误的外层只说明哪个函数无效，把内层原因也拼接上
+fn error_chain(error: &dyn std::error::Error) -> String {
+  let mut message = error.to_string();
+  let mut source = error.source();
+  while let Some(e) = source {
+    message = format!("{}: {}", message, e);
+    source = e.source();
+  }
+  message
+}
+
+/// 在 wgpu 错误作用域中创建资源，创建失败时返回错误而不是 panic
+#[cfg(not(target_arch = "wasm32"))]
+pub fn checked<T>(device: &Device, create: impl FnOnce() -> T) -> Result<T, String> {
+  device.push_error_scope(wgpu::ErrorFilter::Validation);
+  let value = create();
+  match pollster::block_on(device.pop_error_scope()) {
+    Some(e) => Err(e.to_string()),
+    None => Ok(value),
+  }
+}
+
+/// 浏览器中错误作用域只能异步等待，直接创建，错误由设备的未捕获错误回调输出到控制台
+#[cfg(target_arch = "wasm32")]
+pub fn checked<T>(_device: &Device, create: impl FnOnce() -> T) -> Result<T, String> {
+  Ok(create())
+}
+
+/// 着色器变体的特性开关，每个开关对应一个预处理宏，例如 `SKINNED` 对应 `#ifdef SKINNED`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderFeatures(u32);
+
+impl ShaderFeatures {
+  pub const NONE: Self = Self(0);
+  pub const SKINNED: Self = Self(1);
+  pub const OIT: Self = Self(2);
+
+  // 每一位对应的宏名
+  const DEFINES: [&'static str; 2] = ["SKINNED", "OIT"];
+
+  pub fn contains(&self, other: Self) -> bool {
+    self.0 & other.0 == other.0
+  }
+
+  /// 开启的特性对应的宏名
+  pub fn defines(&self) -> Vec<&'static str> {
+    Self::DEFINES.iter().enumerate().filter(|(i, _)| self.0 & (1 << i) != 0).map(|(_, d)| *d).collect()
+  }
+}
+
+impl BitOr for ShaderFeatures {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    Self(self.0 | rhs.0)
+  }
+}
+
+struct ShaderFile {
+  source: String, // 最近一次读取的源码，可能无效
+  modified: Option<SystemTime>, // 最近一次读取时文件的修改时间
+}
+
+// 预处理并校验通过的着色器变体
+struct Variant {
+  source: String,
+  files: Vec<String>, // 用到的文件，其中任何一个修改都要重新编译
+}
+
+/// 着色器源码管理，按入口文件和特性开关缓存预处理后的变体
+///
+/// 默认使用编译进程序的着色器；设置环境变量 `KIDAR_SHADER_DIR` 为着色器目录（例如 `src/template`）时
+/// 从磁盘读取，并通过 `poll` 检查文件修改。修改后的源码校验失败时保留上一次可用的变体，并记录错误。
+pub struct ShaderLibrary {
+  dir: Option<PathBuf>, // 热重载目录，None 表示不开启热重载
+  files: HashMap<&'static str, ShaderFile>,
+  variants: HashMap<(&'static str, ShaderFeatures), Variant>,
+  errors: HashMap<(&'static str, ShaderFeatures), ShaderError>,
+  last_poll: Option<Instant>,
+}
+
+impl ShaderLibrary {
+  /// 只使用内置着色器
+  pub fn embedded() -> Self {
+    Self {
+      dir: None,
+      files: EMBEDDED.iter().map(|(name, source)| (*name, ShaderFile { source: source.to_string(), modified: None })).collect(),
+      variants: HashMap::new(),
+      errors: HashMap::new(),
+      last_poll: None,
+    }
+  }
+
+  /// 从 `dir` 读取着色器并开启热重载
+  pub fn hot_reload(dir: impl Into<PathBuf>) -> Self {
+    let mut library = Self::embedded();
+    library.dir = Some(dir.into());
+    library.read_files(true);
+    library
+  }
+
+  /// 根据环境变量决定是否开启热重载
+  pub fn from_env() -> Self {
+    match std::env::var(SHADER_DIR_ENV) {
+      Ok(dir) if !dir.is_empty() => {
+        println!("着色器热重载已开启：{}", dir);
+        Self::hot_reload(dir)
+      },
+      _ => Self::embedded(),
+    }
+  }
+
+  pub fn is_hot_reload(&self) -> bool {
+    self.dir.is_some()
+  }
+
+  /// 预处理后的着色器源码，`name` 是 template 目录下的入口文件名
+  ///
+  /// 第一次请求时编译并缓存；磁盘上的源码无效时记录错误，改用内置着色器。
+  pub fn variant(&mut self, name: &str, features: ShaderFeatures) -> &str {
+    let Some((name, _)) = EMBEDDED.iter().find(|(n, _)| *n == name) else {
+      panic!("未知的着色器 {}", name);
+    };
+    let key = (*name, features);
+    if !self.variants.contains_key(&key) {
+      let variant = self.compile(name, features, false).unwrap_or_else(|e| {
+        println!("着色器错误 {}", e);
+        self.errors.insert(key, e);
+        self.compile(name, features, true).unwrap_or_else(|e| panic!("内置着色器无效 {}", e))
+      });
+      self.variants.insert(key, variant);
+    }
+    &self.variants[&key].source
+  }
+
+  // 预处理并用 naga 校验，错误位置换算回原文件；`embedded` 为 true 时使用内置源码
+  fn compile(&self, name: &str, features: ShaderFeatures, embedded: bool) -> Result<Variant, ShaderError> {
+    let load = |file: &str| -> Option<&str> {
+      if embedded {
+        EMBEDDED.iter().find(|(n, _)| *n == file).map(|(_, s)| *s)
+      } else {
+        self.files.get(file).map(|f| f.source.as_str())
+      }
+    };
+    let output = preprocess(name, &features.defines(), &load)?;
+    validate(name, &output.source).map_err(|e| output.map_error(e))?;
+    Ok(Variant { source: output.source, files: output.files })
+  }
+
+  /// 预处理并校验不在着色器目录中的源码，例如自定义后处理效果，`#include` 从着色器目录中查找
+  pub fn compile_source(&self, name: &str, source: &str) -> Result<String, ShaderError> {
+    let load = |file: &str| -> Option<&str> {
+      if file == name {
+        Some(source)
+      } else {
+        self.files.get(file).map(|f| f.source.as_str())
+      }
+    };
+    let output = preprocess(name, &[], &load)?;
+    validate(name, &output.source).map_err(|e| output.map_error(e))?;
+    Ok(output.source)
+  }
+
+  /// 当前没有修复的编译错误
+  pub fn errors(&self) -> impl Iterator<Item = &ShaderError> {
+    self.errors.values()
+  }
+
+  /// 记录创建管线时 wgpu 报告的错误，这类错误没有行号
+  pub fn report(&mut self, name: &'static str, features: ShaderFeatures, message: String) {
+    let error = ShaderError { file: name.to_string(), line: 0, column: 0, message };
+    println!("着色器错误 {}", error);
+    self.errors.insert((name, features), error);
+  }
+
+  /// 检查文件修改并重新编译受影响的变体，返回编译通过的变体；未开启热重载或未到检查间隔时返回空
+  pub fn poll(&mut self) -> Vec<(&'static str, ShaderFeatures)> {
+    let now = Instant::now();
+    if self.dir.is_none() || self.last_poll.is_some_and(|t| now.duration_since(t) < POLL_INTERVAL) {
+      return vec![];
+    }
+    self.last_poll = Some(now);
+    let changed_files = self.read_files(false);
+    if changed_files.is_empty() {
+      return vec![];
+    }
+    let affected: Vec<(&'static str, ShaderFeatures)> = self.variants.iter()
+      .filter(|(_, v)| v.files.iter().any(|f| changed_files.contains(&f.as_str())))
+      .map(|(key, _)| *key)
+      .collect();
+    let mut changed = vec![];
+    for key in affected {
+      match self.compile(key.0, key.1, false) {
+        Ok(variant) => {
+          println!("着色器已更新：{} {:?}", key.0, key.1.defines());
+          self.variants.insert(key, variant);
+          self.errors.remove(&key);
+          changed.push(key);
+        },
+        Err(e) => {
+          println!("着色器错误 {}", e);
+          self.errors.insert(key, e);
+        },
+      }
+    }
+    changed
+  }
+
+  // 重新读取修改过的文件，返回内容有变化的文件；`force` 为 true 时读取全部文件
+  fn read_files(&mut self, force: bool) -> Vec<&'static str> {
+    let Some(dir) = self.dir.clone() else {
+      return vec![];
+    };
+    let mut changed = vec![];
+    for (name, file) in self.files.iter_mut() {
+      let path = dir.join(name);
+      let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+      if !force && (modified.is_none() || modified == file.modified) {
+        continue
+      }
+      file.modified = modified;
+      match fs::read_to_string(&path) {
+        Ok(source) if source != file.source => {
+          file.source = source;
+          changed.push(*name);
+        },
+        Ok(_) => {},
+        Err(e) => println!("读取着色器 {} 失败：{}", path.display(), e),
+      }
+    }
+    changed
+  }
+}
@@ -0,0 +1,197 @@
+use nalgebra::Matrix4;
+use wgpu::*;
+
+use crate::anim::skeleton::MAX_JOINTS;
+use crate::render::{pipeline::{create_state_pipeline, RenderState}, profiler::DrawStats, shader::checked, vertex::SkinnedVertex};
+
+// 每个网格的关节矩阵占用的字节数，是 256 的倍数，可以直接作为动态偏移
+const JOINT_BLOCK_SIZE: u64 = (MAX_JOINTS * std::mem::size_of::<[[f32; 4]; 4]>()) as u64;
+
+// 本帧待绘制的蒙皮网格
+struct SkinnedDraw {
+  vertices: Vec<SkinnedVertex>,
+  joints: Vec<[[f32; 4]; 4]>,
+}
+
+/// 在 GPU 上做蒙皮的渲染器，网格每帧通过 `queue` 提交，绘制后清空
+pub struct SkinnedRenderer {
+  pipeline: RenderPipeline,
+  joint_layout: BindGroupLayout,
+  joint_buffer: Buffer,
+  joint_bind_group: BindGroup,
+  joint_capacity: u64,
+  vertex_buffer: Buffer,
+  vertex_capacity: u64,
+  queued: Vec<SkinnedDraw>,
+}
+
+impl SkinnedRenderer {
+  pub fn new(device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, source: &str) -> Self {
+    let joint_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("Joint Bind Group Layout"),
+      entries: &[
+        BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::VERTEX,
+          ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: BufferSize::new(JOINT_BLOCK_SIZE),
+          },
+          count: None,
+        },
+      ],
+    });
+    let joint_capacity = JOINT_BLOCK_SIZE * 4;
+    let joint_buffer = Self::create_buffer(device, "Joint Buffer", joint_capacity, BufferUsages::UNIFORM);
+    let joint_bind_group = Self::create_joint_bind_group(device, &joint_layout, &joint_buffer);
+    let vertex_capacity = (std::mem::size_of::<SkinnedVertex>() * 1024) as u64;
+    Self {
+      pipeline: create_state_pipeline(device, texture_format, &[bind_group_layout, &joint_layout], source, &RenderState::skinned(), None),
+      joint_layout,
+      joint_buffer,
+      joint_bind_group,
+      joint_capacity,
+      vertex_buffer: Self::create_buffer(device, "Skinned Vertex Buffer", vertex_capacity, BufferUsages::VERTEX),
+      vertex_capacity,
+      queued: vec![],
+    }
+  }
+
+  /// 设备能否在 GPU 上蒙皮：每个网格的关节矩阵放在一个 uniform 绑定中，按动态偏移寻址
+  ///
+  /// WebGL2 中顶点着色器的 uniform 向量常常只有 256 个，64 个关节矩阵加上相机矩阵就会超出，也改为 CPU 蒙皮。
+  pub fn is_supported(adapter: &Adapter, device: &Device) -> bool {
+    let limits = device.limits();
+    adapter.get_info().backend != Backend::Gl
+      && limits.max_uniform_buffer_binding_size as u64 >= JOINT_BLOCK_SIZE
+      && JOINT_BLOCK_SIZE.is_multiple_of(limits.min_uniform_buffer_offset_alignment as u64)
+  }
+
+  /// 用新的着色器源码重建管线，失败时保留原来的管线
+  pub fn rebuild_pipeline(&mut self, device: &Device, texture_format: TextureFormat, bind_group_layout: &BindGroupLayout, source: &str) -> Result<(), String> {
+    self.pipeline = checked(device, || {
+      create_state_pipeline(device, texture_format, &[bind_group_layout, &self.joint_layout], source, &RenderState::skinned(), None)
+    })?;
+    Ok(())
+  }
+
+  fn create_buffer(device: &Device, label: &str, size: u64, usage: BufferUsages) -> Buffer {
+    device.create_buffer(&BufferDescriptor {
+      label: Some(label),
+      size,
+      usage: usage | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    })
+  }
+
+  fn create_joint_bind_group(device: &Device, layout: &BindGroupLayout, buffer: &Buffer) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+      label: Some("Joint Bind Group"),
+      layout,
+      entries: &[
+        BindGroupEntry {
+          binding: 0,
+          resource: BindingResource::Buffer(BufferBinding { buffer, offset: 0, size: BufferSize::new(JOINT_BLOCK_SIZE) }),
+        },
+      ],
+    })
+  }
+
+  /// 提交一个蒙皮网格，`joint_matrices` 超过 `MAX_JOINTS` 的部分被忽略
+  pub fn queue(&mut self, vertices: &[SkinnedVertex], joint_matrices: &[Matrix4<f32>]) {
+    let mut joints = vec![[[0.0; 4]; 4]; MAX_JOINTS];
+    for (dst, m) in joints.iter_mut().zip(joint_matrices.iter()) {
+      *dst = (*m).into();
+    }
+    self.queued.push(SkinnedDraw { vertices: vertices.to_vec(), joints });
+  }
+
+  /// 本帧提交的网格的绘制统计
+  pub fn draw_stats(&self) -> DrawStats {
+    let mut stats = DrawStats::default();
+    for d in self.queued.iter() {
+      stats.add_draw(d.vertices.len() as u32);
+    }
+    stats
+  }
+
+  /// 清空本帧提交的网格，在一帧的所有绘制（包括截图）完成后调用
+  pub fn clear(&mut self) {
+    self.queued.clear();
+  }
+
+  /// 在已有画面上绘制本帧提交的网格（不清除颜色和深度），同一帧可以绘制多次
+  #[allow(clippy::too_many_arguments)]
+  pub fn draw(
+    &mut self,
+    device: &Device,
+    queue: &Queue,
+    encoder: &mut CommandEncoder,
+    view: &TextureView,
+    depth_view: &TextureView,
+    bind_group: &BindGroup,
+  ) {
+    if self.queued.is_empty() {
+      return
+    }
+    let draws = &self.queued;
+
+    // 缓冲区不够时按 2 倍扩容
+    let joint_size = JOINT_BLOCK_SIZE * draws.len() as u64;
+    if joint_size > self.joint_capacity {
+      self.joint_capacity = joint_size.next_power_of_two();
+      self.joint_buffer = Self::create_buffer(device, "Joint Buffer", self.joint_capacity, BufferUsages::UNIFORM);
+      self.joint_bind_group = Self::create_joint_bind_group(device, &self.joint_layout, &self.joint_buffer);
+    }
+    let vertex_size: u64 = draws.iter().map(|d| std::mem::size_of_val(d.vertices.as_slice()) as u64).sum();
+    if vertex_size > self.vertex_capacity {
+      self.vertex_capacity = vertex_size.next_power_of_two();
+      self.vertex_buffer = Self::create_buffer(device, "Skinned Vertex Buffer", self.vertex_capacity, BufferUsages::VERTEX);
+    }
+
+    let mut ranges = vec![];
+    let mut vertex_offset = 0u64;
+    for (i, d) in draws.iter().enumerate() {
+      queue.write_buffer(&self.joint_buffer, JOINT_BLOCK_SIZE * i as u64, bytemuck::cast_slice(&d.joints));
+      if !d.vertices.is_empty() {
+        queue.write_buffer(&self.vertex_buffer, vertex_offset, bytemuck::cast_slice(&d.vertices));
+      }
+      let start = (vertex_offset / std::mem::size_of::<SkinnedVertex>() as u64) as u32;
+      ranges.push(start..start + d.vertices.len() as u32);
+      vertex_offset += std::mem::size_of_val(d.vertices.as_slice()) as u64;
+    }
+
+    let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("Skinned Pass"),
+      depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+        view: depth_view,
+        depth_ops: Some(Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        }),
+        stencil_ops: None,
+      }),
+      timestamp_writes: None,
+      occlusion_query_set: None,
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        },
+      })]
+    });
+    r_pass.set_pipeline(&self.pipeline);
+    r_pass.set_bind_group(0, bind_group, &[]);
+    r_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    for (i, range) in ranges.into_iter().enumerate() {
+      if range.is_empty() {
+        continue
+      }
+      r_pass.set_bind_group(1, &self.joint_bind_group, &[(JOINT_BLOCK_SIZE * i as u64) as u32]);
+      r_pass.draw(range, 0..1);
+    }
+  }
+}
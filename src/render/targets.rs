@@ -0,0 +1,97 @@
+use wgpu::*;
+
+use super::graph::TextureDesc;
+
+// 连续这么多帧没有用到的渲染目标会被释放，例如截图分块或者窗口尺寸变化之前的纹理
+const KEEP_FRAMES: u64 = 3;
+
+struct Target {
+  name: &'static str, // 创建时的资源名称，同描述的纹理优先给同名资源使用
+  desc: TextureDesc,
+  texture: Texture,
+  view: TextureView,
+  in_use: bool, // 已经分配给当前正在执行的渲染图
+  last_used: u64, // 最近一次使用的帧序号
+}
+
+/// 渲染目标管理：跨帧保存深度、HDR、后处理中间结果等和画面尺寸相关的纹理，渲染图执行时按描述取用
+///
+/// 只有尺寸、格式、采样数等描述变化时才创建新的纹理，旧的纹理在连续 `KEEP_FRAMES` 帧没有用到后释放。
+/// 每次 `RenderGraph::execute` 之间纹理可以重复使用，同一帧内多次渲染（截图、录制）不会互相覆盖，因为每次都单独提交。
+#[derive(Default)]
+pub struct RenderTargets {
+  targets: Vec<Target>,
+  frame: u64,
+  created: u32, // 上次 `take_created` 之后新建的纹理数
+}
+
+impl RenderTargets {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// 开始执行一个渲染图，之前分配的纹理都可以重新使用
+  pub fn begin(&mut self) {
+    for target in self.targets.iter_mut() {
+      target.in_use = false;
+    }
+  }
+
+  /// 取一个符合描述、当前渲染图中还没有分配的纹理，没有时创建
+  pub fn acquire(&mut self, device: &Device, name: &'static str, desc: TextureDesc) -> (Texture, TextureView) {
+    let free = |t: &Target| !t.in_use && t.desc == desc;
+    let index = self.targets.iter().position(|t| free(t) && t.name == name)
+      .or_else(|| self.targets.iter().position(free));
+    let index = index.unwrap_or_else(|| {
+      let texture = device.create_texture(&TextureDescriptor {
+        label: Some(name),
+        size: Extent3d { width: desc.width.max(1), height: desc.height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: desc.mip_level_count,
+        sample_count: desc.sample_count,
+        dimension: TextureDimension::D2,
+        format: desc.format,
+        usage: desc.usage,
+        view_formats: &[],
+      });
+      let view = texture.create_view(&TextureViewDescriptor::default());
+      self.targets.push(Target { name, desc, texture, view, in_use: false, last_used: self.frame });
+      self.created += 1;
+      self.targets.len() - 1
+    });
+    let target = &mut self.targets[index];
+    target.in_use = true;
+    target.last_used = self.frame;
+    (target.texture.clone(), target.view.clone())
+  }
+
+  /// 最近一次分配给 `name` 的纹理视图，渲染图之外需要访问上一帧的结果时使用
+  pub fn view(&self, name: &str) -> Option<&TextureView> {
+    self.targets.iter().filter(|t| t.name == name).max_by_key(|t| t.last_used).map(|t| &t.view)
+  }
+
+  /// 结束一帧，释放长时间没有用到的纹理
+  pub fn end_frame(&mut self) {
+    let frame = self.frame;
+    self.targets.retain(|t| frame - t.last_used < KEEP_FRAMES);
+    self.frame += 1;
+  }
+
+  /// 释放所有纹理，窗口尺寸变化后旧尺寸的纹理不会再用到
+  pub fn clear(&mut self) {
+    self.targets.clear();
+  }
+
+  /// 取出新建纹理的数量，用于性能统计
+  pub fn take_created(&mut self) -> u32 {
+    std::mem::take(&mut self.created)
+  }
+
+  /// 当前保存的纹理数
+  pub fn len(&self) -> usize {
+    self.targets.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.targets.is_empty()
+  }
+}
@@ -0,0 +1,338 @@
+use nalgebra::Vector3;
+use wgpu::*;
+
+use crate::render::{camera::Camera, pipeline::create_text_pipeline, shader::checked};
+use crate::text::{atlas::GlyphAtlas, font::Font, layout::{layout_text, TextAlign, TextStyle}};
+
+// 字形图集的宽高
+const ATLAS_SIZE: u32 = 1024;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct TextVertex {
+  pub position: [f32; 2], // 屏幕像素坐标
+  pub uv: [f32; 2],
+  pub color: [f32; 4],
+}
+
+unsafe impl bytemuck::Zeroable for TextVertex {}
+unsafe impl bytemuck::Pod for TextVertex {}
+
+pub fn create_text_vertex_buffer_layout() -> VertexBufferLayout<'static> {
+  VertexBufferLayout {
+    array_stride: std::mem::size_of::<TextVertex>() as BufferAddress,
+    step_mode: VertexStepMode::Vertex,
+    attributes: &[
+      VertexAttribute {
+        offset: 0,
+        shader_location: 0,
+        format: VertexFormat::Float32x2,
+      },
+      VertexAttribute {
+        offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+        shader_location: 1,
+        format: VertexFormat::Float32x2,
+      },
+      VertexAttribute {
+        offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+        shader_location: 2,
+        format: VertexFormat::Float32x4,
+      },
+    ],
+  }
+}
+
+// 等待绘制的图元，按添加顺序绘制，后添加的在上层
+enum QueuedItem {
+  Text {
+    text: String,
+    x: f32,
+    y: f32,
+    style: TextStyle,
+    color: [f32; 4],
+  },
+  Rect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    color: [f32; 4],
+  },
+}
+
+/// 文字渲染器
+///
+/// 每帧通过 `queue_text`（屏幕坐标）或 `queue_label`（世界坐标锚点）添加文字，`draw` 时统一排版、
+/// 光栅化到字形图集并绘制，绘制后清空队列。`queue_rect` 可以绘制纯色矩形，用于界面背景。
+pub struct TextRenderer {
+  font: Font,
+  atlas: GlyphAtlas,
+  atlas_texture: Texture,
+  screen_buffer: Buffer,
+  bind_group_layout: BindGroupLayout,
+  bind_group: BindGroup,
+  pipeline: RenderPipeline,
+  vertex_buffer: Buffer,
+  capacity: u64, // 顶点缓冲区大小（字节）
+  queued: Vec<QueuedItem>,
+}
+
+impl TextRenderer {
+  pub fn new(device: &Device, texture_format: TextureFormat, font: Font, source: &str) -> Self {
+    let atlas_texture = device.create_texture(&TextureDescriptor {
+      label: Some("Glyph Atlas"),
+      size: Extent3d { width: ATLAS_SIZE, height: ATLAS_SIZE, depth_or_array_layers: 1 },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: TextureDimension::D2,
+      format: TextureFormat::R8Unorm,
+      usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+      view_formats: &[],
+    });
+    let atlas_view = atlas_texture.create_view(&TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&SamplerDescriptor {
+      label: Some("Glyph Sampler"),
+      mag_filter: FilterMode::Linear,
+      min_filter: FilterMode::Linear,
+      ..Default::default()
+    });
+    let screen_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Text Screen Uniform"),
+      size: std::mem::size_of::<[f32; 4]>() as u64,
+      usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("Text Bind Group Layout"),
+      entries: &[
+        BindGroupLayoutEntry {
+          binding: 0,
+          visibility: ShaderStages::VERTEX,
+          ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 1,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+        BindGroupLayoutEntry {
+          binding: 2,
+          visibility: ShaderStages::FRAGMENT,
+          ty: BindingType::Sampler(SamplerBindingType::Filtering),
+          count: None,
+        },
+      ],
+    });
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+      label: Some("Text Bind Group"),
+      layout: &bind_group_layout,
+      entries: &[
+        BindGroupEntry { binding: 0, resource: screen_buffer.as_entire_binding() },
+        BindGroupEntry { binding: 1, resource: BindingResource::TextureView(&atlas_view) },
+        BindGroupEntry { binding: 2, resource: BindingResource::Sampler(&sampler) },
+      ],
+    });
+    let pipeline = create_text_pipeline(device, texture_format, &bind_group_layout, create_text_vertex_buffer_layout(), source);
+
+    let capacity = (std::mem::size_of::<TextVertex>() * 6 * 256) as u64;
+    Self {
+      font,
+      atlas: GlyphAtlas::new(ATLAS_SIZE),
+      atlas_texture,
+      screen_buffer,
+      bind_group_layout,
+      bind_group,
+      pipeline,
+      vertex_buffer: Self::create_buffer(device, capacity),
+      capacity,
+      queued: vec![],
+    }
+  }
+
+  /// 用新的着色器源码重建管线，失败时保留原来的管线
+  pub fn rebuild_pipeline(&mut self, device: &Device, texture_format: TextureFormat, source: &str) -> Result<(), String> {
+    self.pipeline = checked(device, || {
+      create_text_pipeline(device, texture_format, &self.bind_group_layout, create_text_vertex_buffer_layout(), source)
+    })?;
+    Ok(())
+  }
+
+  fn create_buffer(device: &Device, size: u64) -> Buffer {
+    device.create_buffer(&BufferDescriptor {
+      label: Some("Text Vertex Buffer"),
+      size,
+      usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    })
+  }
+
+  /// 在屏幕坐标 (x, y) 处绘制文字，(x, y) 为文本框左上角
+  pub fn queue_text(&mut self, text: &str, x: f32, y: f32, style: &TextStyle, color: [f32; 4]) {
+    self.queued.push(QueuedItem::Text {
+      text: text.to_string(),
+      x,
+      y,
+      style: *style,
+      color,
+    });
+  }
+
+  /// 清空本帧提交的文字和矩形，跳过一帧时调用
+  pub fn clear(&mut self) {
+    self.queued.clear();
+  }
+
+  /// 在屏幕坐标绘制纯色矩形，(x, y) 为左上角
+  pub fn queue_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: [f32; 4]) {
+    self.queued.push(QueuedItem::Rect { x, y, w, h, color });
+  }
+
+  /// 在世界坐标 `anchor` 上方绘制始终朝向屏幕的标签，锚点不在视野内时不绘制
+  pub fn queue_label(&mut self, text: &str, anchor: Vector3<f32>, camera: &Camera, style: &TextStyle, color: [f32; 4]) {
+    let Some((sx, sy)) = camera.world_to_screen(anchor) else {
+      return
+    };
+    let layout = layout_text(&self.font, text, style);
+    let box_width = style.max_width.unwrap_or(layout.width);
+    let mut style = *style;
+    style.align = TextAlign::Center;
+    style.max_width = Some(box_width);
+    self.queue_text(text, sx - box_width / 2.0, sy - layout.height, &style, color);
+  }
+
+  // 把队列中的图元转换为顶点，图集已满时返回 None
+  fn build_vertices(&mut self) -> Option<Vec<TextVertex>> {
+    let mut vertices = vec![];
+    for item in self.queued.iter() {
+      match item {
+        QueuedItem::Text { text, x, y, style, color } => {
+          let layout = layout_text(&self.font, text, style);
+          for g in layout.glyphs.iter() {
+            if g.ch.is_whitespace() {
+              continue
+            }
+            let glyph = self.atlas.glyph(&self.font, g.ch, style.size)?;
+            if glyph.width == 0.0 || glyph.height == 0.0 {
+              continue
+            }
+            let left = x + g.x + glyph.xmin;
+            let top = y + g.y - glyph.ymin - glyph.height;
+            push_quad(&mut vertices, [left, top, left + glyph.width, top + glyph.height], glyph.uv, *color);
+          }
+        },
+        QueuedItem::Rect { x, y, w, h, color } => {
+          let [u, v] = self.atlas.white_uv();
+          push_quad(&mut vertices, [*x, *y, x + w, y + h], [u, v, u, v], *color);
+        },
+      }
+    }
+    Some(vertices)
+  }
+
+  /// 绘制本帧队列中的文字并清空队列
+  pub fn draw(
+    &mut self,
+    device: &Device,
+    queue: &Queue,
+    encoder: &mut CommandEncoder,
+    view: &TextureView,
+    screen_width: f32,
+    screen_height: f32,
+  ) {
+    if self.queued.is_empty() {
+      return
+    }
+    // 图集满了就清空重建一次，仍然放不下时跳过本帧
+    let vertex_list = match self.build_vertices() {
+      Some(v) => v,
+      None => {
+        self.atlas.clear();
+        match self.build_vertices() {
+          Some(v) => v,
+          None => {
+            println!("TextRenderer: 字形图集空间不足");
+            self.queued.clear();
+            return
+          }
+        }
+      }
+    };
+    self.queued.clear();
+    if vertex_list.is_empty() {
+      return
+    }
+
+    if self.atlas.dirty {
+      queue.write_texture(
+        TexelCopyTextureInfo {
+          texture: &self.atlas_texture,
+          mip_level: 0,
+          origin: Origin3d::ZERO,
+          aspect: TextureAspect::All,
+        },
+        &self.atlas.pixels,
+        TexelCopyBufferLayout {
+          offset: 0,
+          bytes_per_row: Some(self.atlas.size),
+          rows_per_image: Some(self.atlas.size),
+        },
+        Extent3d { width: self.atlas.size, height: self.atlas.size, depth_or_array_layers: 1 },
+      );
+      self.atlas.dirty = false;
+    }
+    queue.write_buffer(&self.screen_buffer, 0, bytemuck::cast_slice(&[screen_width, screen_height, 0.0, 0.0]));
+
+    let size = std::mem::size_of_val(vertex_list.as_slice()) as u64;
+    if size > self.capacity {
+      self.capacity = size.next_power_of_two();
+      self.vertex_buffer = Self::create_buffer(device, self.capacity);
+    }
+    queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertex_list));
+
+    let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("Text Pass"),
+      depth_stencil_attachment: None,
+      timestamp_writes: None,
+      occlusion_query_set: None,
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        },
+      })]
+    });
+    r_pass.set_pipeline(&self.pipeline);
+    r_pass.set_bind_group(0, &self.bind_group, &[]);
+    r_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    r_pass.draw(0..vertex_list.len() as u32, 0..1);
+  }
+}
+
+// 添加一个矩形（两个三角形）
+// - `rect`: left, top, right, bottom
+// - `uv`: u0, v0, u1, v1
+fn push_quad(vertices: &mut Vec<TextVertex>, rect: [f32; 4], uv: [f32; 4], color: [f32; 4]) {
+  let [left, top, right, bottom] = rect;
+  let [u0, v0, u1, v1] = uv;
+  vertices.extend_from_slice(&[
+    TextVertex { position: [left, top], uv: [u0, v0], color },
+    TextVertex { position: [left, bottom], uv: [u0, v1], color },
+    TextVertex { position: [right, bottom], uv: [u1, v1], color },
+    TextVertex { position: [right, bottom], uv: [u1, v1], color },
+    TextVertex { position: [right, top], uv: [u1, v0], color },
+    TextVertex { position: [left, top], uv: [u0, v0], color },
+  ]);
+}
@@ -0,0 +1,74 @@
+use wgpu::*;
+
+use super::post::FullscreenPass;
+
+/// OIT 累积目标：rgb 为按权重累加的预乘颜色，a 为权重之和
+pub const OIT_ACCUM_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// OIT 透明度目标：所有半透明表面 (1 - alpha) 的乘积，即背景透过的比例
+pub const OIT_REVEAL_FORMAT: TextureFormat = TextureFormat::R16Float;
+
+/// 半透明网格的绘制方式，两种方式都在不透明物体之后绘制，只做深度测试不写入深度
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransparencyMode {
+  #[default]
+  Sorted, // 按到相机的距离从远到近排序后 alpha 混合，相互穿插的网格可能顺序错误
+  WeightedOit, // 加权混合的顺序无关透明，不需要排序，相交的玻璃也没有跳变，颜色是近似结果
+}
+
+impl TransparencyMode {
+  pub fn next(&self) -> Self {
+    match self {
+      TransparencyMode::Sorted => TransparencyMode::WeightedOit,
+      TransparencyMode::WeightedOit => TransparencyMode::Sorted,
+    }
+  }
+}
+
+/// 把 OIT 的累积结果按透过比例混合到场景颜色上
+pub struct OitCompositor {
+  pass: FullscreenPass,
+}
+
+impl OitCompositor {
+  /// - `source`: oit.wgsl
+  pub fn new(device: &Device, source: &str) -> Self {
+    let texture_entry = |binding| BindGroupLayoutEntry {
+      binding,
+      visibility: ShaderStages::FRAGMENT,
+      ty: BindingType::Texture {
+        sample_type: TextureSampleType::Float { filterable: false },
+        view_dimension: TextureViewDimension::D2,
+        multisampled: false,
+      },
+      count: None,
+    };
+    let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some("OIT Composite Bind Group Layout"),
+      entries: &[texture_entry(0), texture_entry(1)],
+    });
+    let pass = FullscreenPass::with_layout(device, "OIT Composite", source, "fs_composite", layout).blended(BlendState::ALPHA_BLENDING);
+    Self { pass }
+  }
+
+  /// 确保输出格式为 `format` 的管线已经创建
+  pub fn prepare(&mut self, device: &Device, format: TextureFormat) {
+    self.pass.prepare(device, format);
+  }
+
+  pub fn reload(&mut self, device: &Device, source: &str) -> Result<(), String> {
+    self.pass.rebuild(device, source)
+  }
+
+  /// 叠加到 `output` 上，`accum`、`reveal` 为 OIT pass 的两个输出
+  pub fn draw(&self, device: &Device, encoder: &mut CommandEncoder, output: &TextureView, format: TextureFormat, accum: &TextureView, reveal: &TextureView) {
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+      label: Some("OIT Composite Bind Group"),
+      layout: self.pass.layout(),
+      entries: &[
+        BindGroupEntry { binding: 0, resource: BindingResource::TextureView(accum) },
+        BindGroupEntry { binding: 1, resource: BindingResource::TextureView(reveal) },
+      ],
+    });
+    self.pass.draw_bind_group(encoder, output, format, &bind_group);
+  }
+}
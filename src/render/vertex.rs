@@ -2,7 +2,7 @@
 #[derive(Clone, Copy, Debug)]
 pub struct Vertex {
   pub position: [f32; 3],
-  pub color: [f32; 3],
+  pub color: [f32; 4], // rgba，alpha 小于 1 时需要按半透明状态绘制
   // tex_coords非必填
   pub tex_coords: [f32; 2],
 }
@@ -13,42 +13,42 @@ unsafe impl bytemuck::Pod for Vertex {}
 pub const VERTEX_LIST: &[Vertex] = &[
   Vertex {
     position: [200.0, 200.0, 5.0],
-    color: [0.5, 0.0, 0.0],
+    color: [0.5, 0.0, 0.0, 1.0],
     tex_coords: [0.4131759, 0.00759614],
   },
   Vertex {
     position: [200.0, 400.0, 5.0],
-    color: [0.0, 0.5, 0.0],
+    color: [0.0, 0.5, 0.0, 1.0],
     tex_coords: [0.0048659444, 0.43041354],
   },
   Vertex {
     position: [400.0, 200.0, 5.0],
-    color: [0.5, 0.0, 0.5],
+    color: [0.5, 0.0, 0.5, 1.0],
     tex_coords: [0.28081453, 0.949397],
   },
   Vertex {
     position: [400.0, 400.0, 5.0],
-    color: [0.0, 0.5, 0.5],
+    color: [0.0, 0.5, 0.5, 1.0],
     tex_coords: [0.28081453, 0.949397],
   },
   Vertex {
     position: [200.0, 200.0, 200.0],
-    color: [0.5, 0.0, 0.0],
+    color: [0.5, 0.0, 0.0, 1.0],
     tex_coords: [0.4131759, 0.00759614],
   },
   Vertex {
     position: [200.0, 400.0, 200.0],
-    color: [0.0, 0.5, 0.0],
+    color: [0.0, 0.5, 0.0, 1.0],
     tex_coords: [0.0048659444, 0.43041354],
   },
   Vertex {
     position: [400.0, 200.0, 200.0],
-    color: [0.5, 0.0, 0.5],
+    color: [0.5, 0.0, 0.5, 1.0],
     tex_coords: [0.28081453, 0.949397],
   },
   Vertex {
     position: [400.0, 400.0, 200.0],
-    color: [0.0, 0.5, 0.5],
+    color: [0.0, 0.5, 0.5, 1.0],
     tex_coords: [0.28081453, 0.949397],
   },
 ];
@@ -88,13 +88,61 @@ pub fn create_vertex_buffer_layout() -> wgpu::VertexBufferLayout<'static> {
       wgpu::VertexAttribute {
         offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
         shader_location: 1,
+        format: wgpu::VertexFormat::Float32x4,
+      },
+      wgpu::VertexAttribute {
+        offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+        shader_location: 2,
+        format: wgpu::VertexFormat::Float32x2,
+      },
+    ],
+  }
+}
+/// 带骨骼权重的顶点，每个顶点最多受 4 个关节影响（对应 glTF 的 JOINTS_0、WEIGHTS_0）
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SkinnedVertex {
+  pub position: [f32; 3],
+  pub color: [f32; 4],
+  pub tex_coords: [f32; 2],
+  pub joints: [u32; 4], // 关节下标
+  pub weights: [f32; 4], // 权重，和为 1
+}
+
+unsafe impl bytemuck::Zeroable for SkinnedVertex {}
+unsafe impl bytemuck::Pod for SkinnedVertex {}
+
+// 前三个属性与 `Vertex` 相同
+pub fn create_skinned_vertex_buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+  wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<SkinnedVertex>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[
+      wgpu::VertexAttribute {
+        offset: 0,
+        shader_location: 0,
         format: wgpu::VertexFormat::Float32x3,
       },
       wgpu::VertexAttribute {
-        offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+        offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+        shader_location: 1,
+        format: wgpu::VertexFormat::Float32x4,
+      },
+      wgpu::VertexAttribute {
+        offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
         shader_location: 2,
         format: wgpu::VertexFormat::Float32x2,
       },
+      wgpu::VertexAttribute {
+        offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+        shader_location: 3,
+        format: wgpu::VertexFormat::Uint32x4,
+      },
+      wgpu::VertexAttribute {
+        offset: std::mem::size_of::<[f32; 13]>() as wgpu::BufferAddress,
+        shader_location: 4,
+        format: wgpu::VertexFormat::Float32x4,
+      },
     ],
   }
-}
\ No newline at end of file
+}
@@ -1,13 +1,15 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use nalgebra::Vector3;
+use nalgebra::{Matrix4, Vector3};
 use util::{BufferInitDescriptor, DeviceExt};
 use winit::{dpi::PhysicalSize, window::Window};
 use wgpu::*;
 
-use crate::render::{camera::Camera, pipeline::create_pipeline, vertex::*};
+use crate::render::{camera::Camera, pipeline::RenderState, pipeline_cache::PipelineCache, shader::{ShaderFeatures, ShaderLibrary}, text::TextRenderer, vertex::*};
+use crate::text::font::Font;
 
-use super::{camera::CameraMove, draw::create_depth_texture};
+use super::{camera::CameraMove, debug_draw::{DebugDraw, DebugRenderer}, draw::{create_vertex_buffer, sort_batches, RenderBatch, INITIAL_VERTEX_CAPACITY}, graph::{RenderGraph, TextureDesc}, morph::MorphRenderer, post::{PostChain, PostFrame, PostTarget, SceneDepth, HDR_FORMAT}, profiler::{GpuTimer, Profiler}, recorder::Recorder, screenshot::{blit_tile, save_png_async, screenshot_path, tiles, Readback, ScreenshotRequest, SCREENSHOT_FORMAT}, skinned::SkinnedRenderer, targets::RenderTargets, transparent::{OitCompositor, TransparencyMode, OIT_ACCUM_FORMAT, OIT_REVEAL_FORMAT}};
 
 pub struct WgpuCtx<'window> {
   pub vw: u32, // 屏幕高度
@@ -17,21 +19,44 @@ pub struct WgpuCtx<'window> {
   pub queue: Queue,
   pub surface_config: SurfaceConfiguration,
   pub adapter: Adapter,
-  pub render_pipeline: RenderPipeline,
+  pub pipelines: PipelineCache, // 主顶点列表按渲染状态使用的管线
+  pub batches: Vec<RenderBatch>, // 本帧主顶点列表的分批，为空时全部按默认状态绘制，绘制后清空
+  pub bind_group_layout: BindGroupLayout, // 相机 uniform（group 0），重建管线时使用
+  pub shaders: ShaderLibrary, // 着色器源码，开启热重载时每帧检查修改
   pub vertex_buffer: Buffer,
   pub vertex_index_buffer: Buffer,
   pub vertex_uniform_buffer: Buffer,
   pub bind_group: BindGroup,
   pub camera: Camera,
-  pub vertex_len: u32
+  pub vertex_len: u32,
+  pub vertex_capacity: u64, // 主顶点缓冲区大小（字节），顶点不够放时在 `update_vertex_buffer` 中扩容
+  pub debug_draw: DebugDraw, // 调试图元，每帧绘制在场景之上
+  pub debug_renderer: DebugRenderer,
+  pub skinned_renderer: Option<SkinnedRenderer>, // 蒙皮网格，每帧通过 `queue` 提交；不支持 GPU 蒙皮时为空，用 `skin_vertices` 在 CPU 上计算
+  pub morph_renderer: Option<MorphRenderer>, // 变形网格，先 `upload` 再每帧 `queue`；顶点着色器不支持 storage buffer（WebGL2）时为空
+  pub text_renderer: Option<TextRenderer>, // 加载字体后才能绘制文字
+  pub post: PostChain, // 场景先绘制到 HDR 纹理，经过后处理链再写入交换链
+  pub transparency: TransparencyMode, // 半透明批次的绘制方式
+  pub oit: OitCompositor,
+  pub screenshot: Option<ScreenshotRequest>, // 下一帧处理的截图请求
+  pub recorder: Recorder, // 录制期间每帧额外离屏渲染一次并写入
+  pub profiler: Profiler, // 每帧由 App 开始和结束，渲染图的每个 pass 记录 GPU 时间
+  pub render_targets: RenderTargets, // 渲染图使用的深度、HDR 等纹理，跨帧保存
+  pub clear_color: Color, // 背景色
+  pub minimized: bool, // 窗口尺寸为 0（最小化）时不绘制，交换链保持之前的配置
+  device_lost: Arc<AtomicBool>, // 设备丢失（驱动重置、GPU 被移除等）后由 App 重新创建整个上下文
 }
 
 impl<'window> WgpuCtx<'window> {
 
+  /// 浏览器中只能异步初始化，原生程序通过 `new` 阻塞等待
   pub async fn new_async(window: Arc<Window>) -> Self {
     // 构建wgpu上下文
-    // 创建一个wgpu实例
+    // 创建一个wgpu实例，浏览器中先检测 WebGPU，不可用时使用 WebGL2
+    #[cfg(not(target_arch = "wasm32"))]
     let instance = wgpu::Instance::default();
+    #[cfg(target_arch = "wasm32")]
+    let instance = wgpu::util::new_instance_with_webgpu_detection(&InstanceDescriptor::default()).await;
     
     // 初始化一个画布表面
     let surface = instance.create_surface(window.clone()).unwrap();
@@ -46,17 +71,44 @@ impl<'window> WgpuCtx<'window> {
     let (device, queue) = adapter.request_device(&DeviceDescriptor {
       label: None,
       trace: Trace::Off,
-      required_features: wgpu::Features::POLYGON_MODE_LINE,
-      required_limits: wgpu::Limits::default(),
+      // 线框、管线缓存和时间戳查询只有部分后端支持，支持时才开启
+      required_features: adapter.features() & (wgpu::Features::POLYGON_MODE_LINE | wgpu::Features::PIPELINE_CACHE | GpuTimer::FEATURES),
+      // WebGL2 达不到默认限制，按适配器的实际能力请求
+      required_limits: if adapter.get_info().backend == Backend::Gl {
+        wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits())
+      } else {
+        wgpu::Limits::default()
+      },
       memory_hints: Default::default(),
     }).await.expect("Failed to create device");
+    // 浏览器中错误作用域不能同步检查，未捕获的错误输出到控制台，不让设备 panic
+    #[cfg(target_arch = "wasm32")]
+    device.on_uncaptured_error(Box::new(|error| web_sys::console::error_1(&error.to_string().into())));
+    let device_lost = Arc::new(AtomicBool::new(false));
+    {
+      let device_lost = device_lost.clone();
+      device.set_device_lost_callback(move |reason, message| {
+        // 上下文正常释放时也会调用，这时不需要恢复
+        if reason != DeviceLostReason::Destroyed {
+          println!("GPU 设备丢失：{:?} {}", reason, message);
+          device_lost.store(true, Ordering::Relaxed);
+        }
+      });
+    }
 
+    // 画布在页面布局之前尺寸可能为 0，表面至少配置为 1x1
     let window_size = window.inner_size();
-    let width = window_size.width;
-    let height = window_size.height;
+    let minimized = window_size.width == 0 || window_size.height == 0;
+    let max_size = device.limits().max_texture_dimension_2d;
+    let width = window_size.width.clamp(1, max_size);
+    let height = window_size.height.clamp(1, max_size);
     println!("width: {}, height: {}", width, height);
     // 设置表面配置对象
-    let surface_config = surface.get_default_config(&adapter, width, height).unwrap();
+    let mut surface_config = surface.get_default_config(&adapter, width, height).unwrap();
+    // 交换链支持复制时截图直接读回窗口画面
+    if surface.get_capabilities(&adapter).usages.contains(TextureUsages::COPY_SRC) {
+      surface_config.usage |= TextureUsages::COPY_SRC;
+    }
     // 将表面配置对象应用到表面
     surface.configure(&device, &surface_config);
 
@@ -78,14 +130,11 @@ impl<'window> WgpuCtx<'window> {
     });
 
     // 创建渲染管线
-    let render_pipeline = create_pipeline(&device, surface_config.format, &bind_group_layout);
+    let mut shaders = ShaderLibrary::from_env();
+    let mut pipelines = PipelineCache::new(&device, &adapter.get_info(), HDR_FORMAT);
+    pipelines.prepare(&device, &mut shaders, &bind_group_layout, &RenderState::default()).expect("创建默认渲染管线失败");
     // 创建顶点缓存器
-    let vertex_buffer = device.create_buffer(&BufferDescriptor {
-      label: None,
-      size: 32000, // 当前一个顶点是32个字节，这里最大支持1000个顶点
-      usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-      mapped_at_creation: false
-    });
+    let vertex_buffer = create_vertex_buffer(&device, INITIAL_VERTEX_CAPACITY);
     // 创建顶点索引缓存器
     let vertex_index_buffer = device.create_buffer_init(&BufferInitDescriptor {
       label: None,
@@ -93,8 +142,8 @@ impl<'window> WgpuCtx<'window> {
       usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
     });
 
-    let screen_width = window_size.width as f32;
-    let screen_height = window_size.height as f32;
+    let screen_width = width as f32;
+    let screen_height = height as f32;
     // 创建相机
     let camera = Camera::new(
       Vector3::new(5100.0, 2200.0, 0.0), // 相机位置
@@ -116,6 +165,23 @@ impl<'window> WgpuCtx<'window> {
     });
 
     let bind_group = camera.bind_group(&device, &bind_group_layout, &vertex_uniform_buffer);
+    // 场景相关的管线都输出到 HDR 纹理，只有文字直接绘制到交换链
+    let debug_renderer = DebugRenderer::new(&device, HDR_FORMAT, &bind_group_layout, shaders.variant("shader.wgsl", ShaderFeatures::NONE));
+    let skinned_renderer = if SkinnedRenderer::is_supported(&adapter, &device) {
+      Some(SkinnedRenderer::new(&device, HDR_FORMAT, &bind_group_layout, shaders.variant("shader.wgsl", ShaderFeatures::SKINNED)))
+    } else {
+      println!("设备不支持 GPU 蒙皮，蒙皮网格在 CPU 上计算");
+      None
+    };
+    let post = PostChain::new(&device, &mut shaders);
+    let morph_renderer = if device.limits().max_storage_buffers_per_shader_stage > 0 && adapter.get_downlevel_capabilities().flags.contains(DownlevelFlags::VERTEX_STORAGE) {
+      Some(MorphRenderer::new(&device, HDR_FORMAT, &bind_group_layout, shaders.variant("morph.wgsl", ShaderFeatures::NONE)))
+    } else {
+      println!("设备不支持顶点着色器中的 storage buffer，变形网格在 CPU 上计算");
+      None
+    };
+    let oit = OitCompositor::new(&device, shaders.variant("oit.wgsl", ShaderFeatures::NONE));
+    let profiler = Profiler::new(&device, &queue);
 
     return WgpuCtx {
         vw: width,
@@ -125,37 +191,305 @@ impl<'window> WgpuCtx<'window> {
         queue: queue,
         surface_config: surface_config,
         adapter: adapter,
-        render_pipeline,
+        pipelines,
+        batches: vec![],
+        bind_group_layout,
+        shaders,
         vertex_buffer,
         vertex_index_buffer,
         vertex_uniform_buffer,
         bind_group,
         camera,
-        vertex_len: 0
+        vertex_len: 0,
+        vertex_capacity: INITIAL_VERTEX_CAPACITY,
+        debug_draw: DebugDraw::new(),
+        debug_renderer,
+        skinned_renderer,
+        morph_renderer,
+        text_renderer: None,
+        post,
+        transparency: TransparencyMode::default(),
+        oit,
+        screenshot: None,
+        recorder: Recorder::default(),
+        profiler,
+        render_targets: RenderTargets::new(),
+        clear_color: Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 },
+        minimized,
+        device_lost,
       };
   }
 
+  #[cfg(not(target_arch = "wasm32"))]
   pub fn new (window: Arc<Window>) -> Self {
     pollster::block_on(Self::new_async(window))
   }
+
+  /// 窗口尺寸变化时重新配置交换链，尺寸为 0（最小化）时只记录状态，恢复后再配置
+  ///
+  /// 超过设备最大纹理尺寸的部分会被裁掉；渲染图中和尺寸相关的纹理按 `vw`、`vh` 创建，不需要单独处理。
+  pub fn resize(&mut self, width: u32, height: u32) {
+    self.minimized = width == 0 || height == 0;
+    if self.minimized {
+      return
+    }
+    let max_size = self.device.limits().max_texture_dimension_2d;
+    let (width, height) = (width.min(max_size), height.min(max_size));
+    if (width, height) == (self.vw, self.vh) {
+      return
+    }
+    self.vw = width;
+    self.vh = height;
+    self.surface_config.width = width;
+    self.surface_config.height = height;
+    self.surface.configure(&self.device, &self.surface_config);
+    self.camera.set_screen_size(width as f32, height as f32);
+    self.render_targets.clear();
+  }
+
+  /// 设备是否已经丢失，丢失后上下文中的所有 GPU 资源都不能再使用
+  pub fn is_device_lost(&self) -> bool {
+    self.device_lost.load(Ordering::Relaxed)
+  }
+
+  /// 加载字体文件，成功后可以通过 `text_renderer` 绘制文字
+  pub fn load_font(&mut self, path: &str) -> Result<(), String> {
+    self.use_font(Font::from_file(path)?);
+    Ok(())
+  }
+
+  /// 用 `font` 创建文字渲染器，替换之前的字体
+  pub fn use_font(&mut self, font: Font) {
+    self.text_renderer = Some(TextRenderer::new(&self.device, self.surface_config.format, font, self.shaders.variant("text.wgsl", ShaderFeatures::NONE)));
+  }
+
+  /// 开启着色器热重载时检查文件修改（包括被 #include 的文件），并重建使用了受影响变体的管线
+  ///
+  /// 源码先经过 naga 校验，创建管线失败时保留原来的管线，错误记录在 `shaders` 中。
+  pub fn reload_shaders(&mut self) {
+    for (name, features) in self.shaders.poll() {
+      let source = self.shaders.variant(name, features).to_string();
+      let source = source.as_str();
+      let result = match name {
+        "shader.wgsl" => {
+          let mut result = self.pipelines.rebuild(&self.device, &self.bind_group_layout, features, source);
+          if features == ShaderFeatures::NONE {
+            result = result.and_then(|_| self.debug_renderer.rebuild_pipeline(&self.device, HDR_FORMAT, &self.bind_group_layout, source));
+          }
+          if let (true, Some(skinned_renderer)) = (features.contains(ShaderFeatures::SKINNED), self.skinned_renderer.as_mut()) {
+            result = result.and_then(|_| skinned_renderer.rebuild_pipeline(&self.device, HDR_FORMAT, &self.bind_group_layout, source));
+          }
+          result
+        },
+        "morph.wgsl" => match self.morph_renderer.as_mut() {
+          Some(morph_renderer) => morph_renderer.rebuild_pipeline(&self.device, HDR_FORMAT, &self.bind_group_layout, source),
+          None => Ok(()),
+        },
+        "oit.wgsl" => self.oit.reload(&self.device, source),
+        "text.wgsl" => match self.text_renderer.as_mut() {
+          Some(text_renderer) => text_renderer.rebuild_pipeline(&self.device, self.surface_config.format, source),
+          None => Ok(()),
+        },
+        _ => self.post.reload(&self.device, name, source),
+      };
+      if let Err(e) = result {
+        self.shaders.report(name, features, e);
+      }
+    }
+  }
 }
 
 impl<'window> WgpuCtx<'window> {
+  /// 取出本帧的分批并创建缺少的管线
+  ///
+  /// 排列顺序见 `sort_batches`。
+  pub fn prepare_batches(&mut self) -> Vec<RenderBatch> {
+    let mut batches = if self.batches.is_empty() {
+      vec![RenderBatch::new(RenderState::default(), 0..self.vertex_len)]
+    } else {
+      std::mem::take(&mut self.batches)
+    };
+    sort_batches(&mut batches, self.camera.position(), self.transparency);
+    for batch in batches.iter() {
+      if let Err(e) = self.pipelines.prepare(&self.device, &mut self.shaders, &self.bind_group_layout, &batch.state) {
+        println!("创建渲染管线失败，改用默认状态 {:?}: {}", batch.state, e);
+      }
+    }
+    batches
+  }
+
+  /// 用渲染图绘制一帧：场景、蒙皮、变形、半透明批次、调试图元依次绘制到 HDR 纹理，经过后处理链写入交换链，最后叠加文字
+  ///
+  /// 有截图请求时在显示之前读回交换链画面，或者按请求的尺寸重新渲染；正在录制时按录制尺寸重新渲染并写入。
+  ///
+  /// 最小化时或者取不到交换链画面时跳过这一帧：交换链过期、丢失时重新配置，内存不足时按设备丢失处理。
   pub fn draw(&mut self) {
-    let frame = self.surface.get_current_texture().unwrap();
-    // 设置纹理
-    let view = frame.texture.create_view(&TextureViewDescriptor::default());
-    // println!("WgpuCtx::draw: {:?}", view);
-    let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+    if self.minimized {
+      self.clear_queued();
+      return
+    }
+    let frame = match self.surface.get_current_texture() {
+      Ok(frame) => frame,
+      Err(e) => {
+        self.clear_queued();
+        match e {
+          SurfaceError::Lost | SurfaceError::Outdated => {
+            println!("交换链已失效（{}），重新配置", e);
+            self.surface.configure(&self.device, &self.surface_config);
+          },
+          SurfaceError::OutOfMemory => {
+            println!("获取交换链画面时内存不足，重新创建设备");
+            self.device_lost.store(true, Ordering::Relaxed);
+          },
+          SurfaceError::Timeout | SurfaceError::Other => println!("获取交换链画面失败：{}，跳过这一帧", e),
+        }
+        return
+      },
+    };
+    let batches = self.prepare_batches();
+    self.count_draw_stats(&batches);
+    let request = self.screenshot.take();
+    // 窗口截图直接复制交换链，交换链不支持复制时改为离屏重新渲染
+    let window_capture = request.as_ref().is_some_and(|r| r.size.is_none() && self.surface_config.usage.contains(TextureUsages::COPY_SRC));
+    let readback = if window_capture {
+      match Readback::new(&self.device, self.vw, self.vh, self.surface_config.format) {
+        Ok(readback) => Some(readback),
+        Err(e) => {
+          println!("{}，改为离屏渲染", e);
+          None
+        },
+      }
+    } else {
+      None
+    };
 
-    let depth_view = create_depth_texture(self, "depth_texture").1;
+    let pvm = self.camera.pvm_matrix();
+    let (width, height, format) = (self.vw, self.vh, self.surface_config.format);
+    if let Err(e) = self.render_frame(&frame.texture, format, &batches, width, height, pvm, true, readback.as_ref()) {
+      println!("渲染图错误：{}", e);
+    }
 
-    // 此处使用作用域，将pass限制在一定范围内，出作用域后会自动调用drop清理资源。
-    {
+    if let Some(request) = request {
+      let result = match readback {
+        Some(readback) => readback.read(&self.device).map(|pixels| (width, height, pixels)),
+        None => {
+          let (width, height) = request.size.unwrap_or((width, height));
+          self.render_offscreen(&batches, width, height).map(|pixels| (width, height, pixels))
+        },
+      };
+      match result {
+        Ok((width, height, pixels)) => save_png_async(screenshot_path(&request.view, width, height), width, height, pixels),
+        Err(e) => println!("截图失败：{}", e),
+      }
+    }
+    if let Some((width, height)) = self.recorder.size() {
+      let result = self.render_offscreen(&batches, width, height).and_then(|pixels| self.recorder.write_frame(&pixels));
+      if let Err(e) = result {
+        println!("录制失败：{}", e);
+        self.recorder.stop();
+      }
+    }
+    self.clear_queued();
+    self.render_targets.end_frame();
+    let suboptimal = frame.suboptimal;
+    frame.present(); // 替换当前帧画面，显示最新的图像
+    // 交换链仍然可用但和窗口不再匹配（例如移到了另一个显示器），显示后重新配置
+    if suboptimal {
+      self.surface.configure(&self.device, &self.surface_config);
+    }
+  }
+
+  // 统计本帧的绘制次数和三角形数，截图和录制重新渲染的画面不重复统计
+  fn count_draw_stats(&mut self, batches: &[RenderBatch]) {
+    let stats = &mut self.profiler.draw_stats;
+    for batch in batches.iter() {
+      stats.add_draw(batch.range.len() as u32);
+    }
+    if let Some(skinned_renderer) = self.skinned_renderer.as_ref() {
+      *stats += skinned_renderer.draw_stats();
+    }
+    if let Some(morph_renderer) = self.morph_renderer.as_ref() {
+      *stats += morph_renderer.draw_stats();
+    }
+    *stats += self.debug_draw.draw_stats();
+  }
+
+  // 清空本帧提交的绘制内容，跳过的帧不能留到下一帧重复绘制
+  fn clear_queued(&mut self) {
+    self.batches.clear();
+    if let Some(skinned_renderer) = self.skinned_renderer.as_mut() {
+      skinned_renderer.clear();
+    }
+    if let Some(morph_renderer) = self.morph_renderer.as_mut() {
+      morph_renderer.clear();
+    }
+    if let Some(text_renderer) = self.text_renderer.as_mut() {
+      text_renderer.clear();
+    }
+  }
+
+  /// 按当前相机离屏渲染 `width` x `height` 的画面（不包括文字），返回 RGBA 像素
+  ///
+  /// 超过 `max_texture_dimension_2d` 时分块渲染，每块的相机矩阵只显示对应的部分再拼接；
+  /// 画面范围与窗口相同，宽高比不同时会被拉伸。屏幕空间的后处理（泛光、SSAO 等）在块的边缘可能有接缝。
+  pub fn render_offscreen(&mut self, batches: &[RenderBatch], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    if width == 0 || height == 0 {
+      return Err(format!("截图尺寸无效：{}x{}", width, height));
+    }
+    let result = self.render_tiles(batches, width, height);
+    // 恢复窗口使用的相机矩阵
+    self.queue.write_buffer(&self.vertex_uniform_buffer, 0, bytemuck::cast_slice(&[self.camera.uniform_obj()]));
+    result
+  }
+
+  fn render_tiles(&mut self, batches: &[RenderBatch], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let max_size = self.device.limits().max_texture_dimension_2d;
+    let mut image = vec![0u8; width as usize * height as usize * 4];
+    for tile in tiles(width, height, max_size) {
+      let offset = tile.clip_offset(width, height);
+      self.queue.write_buffer(&self.vertex_uniform_buffer, 0, bytemuck::cast_slice(&[self.camera.offset_uniform_obj(offset)]));
+      let target = self.device.create_texture(&TextureDescriptor {
+        label: Some("Screenshot Texture"),
+        size: Extent3d { width: tile.width, height: tile.height, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: SCREENSHOT_FORMAT,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        view_formats: &[],
+      });
+      let readback = Readback::new(&self.device, tile.width, tile.height, SCREENSHOT_FORMAT)?;
+      let pvm = offset * self.camera.pvm_matrix();
+      self.render_frame(&target, SCREENSHOT_FORMAT, batches, tile.width, tile.height, pvm, false, Some(&readback))?;
+      blit_tile(&mut image, width, &tile, &readback.read(&self.device)?);
+    }
+    Ok(image)
+  }
+
+  /// 用渲染图绘制一帧到 `target`；`pvm` 与 uniform 中的相机矩阵一致，用于后处理重建位置，`readback` 不为空时复制最终画面
+  ///
+  /// 新的 pass 通过 `RenderGraph::add_pass` 声明读写的纹理后加入，不需要关心执行顺序和临时纹理的创建；
+  /// 临时纹理从 `render_targets` 中取用，尺寸和格式不变时每帧不再分配新的纹理。
+  #[allow(clippy::too_many_arguments)]
+  fn render_frame(&mut self, target: &Texture, format: TextureFormat, batches: &[RenderBatch], width: u32, height: u32, pvm: Matrix4<f32>, with_text: bool, readback: Option<&Readback>) -> Result<(), String> {
+    let Self {
+      device, queue, pipelines, bind_group, vertex_buffer, clear_color,
+      skinned_renderer, morph_renderer, debug_renderer, debug_draw, text_renderer, post, camera, oit, transparency, profiler, render_targets, ..
+    } = self;
+    let (device, queue, pipelines, bind_group, vertex_buffer) = (&*device, &*queue, &*pipelines, &*bind_group, &*vertex_buffer);
+    // prepare_batches 已经把不透明批次排在前面
+    let split = batches.iter().position(|b| b.is_transparent()).unwrap_or(batches.len());
+    let (opaque, transparent) = batches.split_at(split);
+
+    let mut graph = RenderGraph::new();
+    let output = graph.import("output", target);
+    let hdr = graph.create("hdr", TextureDesc::new(width, height, HDR_FORMAT));
+    let depth = graph.create("depth", TextureDesc::new(width, height, TextureFormat::Depth32Float));
+    graph.add_pass("scene").write(hdr).write(depth).execute(move |encoder, res| {
       let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-        label: None,
-        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment { 
-          view: &depth_view,
+        label: Some("Scene Pass"),
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+          view: res.view(depth),
           depth_ops: Some(Operations {
             load: LoadOp::Clear(1.0),
             store: StoreOp::Store,
@@ -165,28 +499,120 @@ impl<'window> WgpuCtx<'window> {
         timestamp_writes: None,
         occlusion_query_set: None,
         color_attachments: &[Some(RenderPassColorAttachment {
-          view: &view,
+          view: res.view(hdr),
           resolve_target: None,
           ops: Operations {
-            load: LoadOp::Clear(Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 }),
+            load: LoadOp::Clear(*clear_color),
             store: StoreOp::Store,
           },
         })]
       });
-      // println!("r_pass: {:#?}", &self.bind_group.into());
-      r_pass.set_pipeline(&self.render_pipeline);
-      r_pass.set_bind_group(0, &self.bind_group, &[]);
-      r_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-      r_pass.draw(0..self.vertex_len, 0..1);
-      // r_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-      // r_pass.set_index_buffer(self.vertex_index_buffer.slice(..), IndexFormat::Uint16);
-      // r_pass.draw_indexed(0..VERTEX_INDEX_LIST.len() as u32, 0, 0..1);
-      // r_pass.draw(0..VERTEX_LIST.len() as u32, 0..1);
-    }
-
-    // 上面的pass结束后，才能调用finish
-    self.queue.submit(Some(encoder.finish())); // 提交命令到GPU
-    frame.present(); // 替换当前帧画面，显示最新的图像
-  } 
+      r_pass.set_bind_group(0, &*bind_group, &[]);
+      r_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+      for batch in opaque.iter() {
+        r_pass.set_pipeline(pipelines.get_or_default(&batch.state));
+        r_pass.draw(batch.range.clone(), 0..1);
+      }
+    });
+    if let Some(skinned_renderer) = skinned_renderer.as_mut() {
+      graph.add_pass("skinned").write(hdr).write(depth).execute(|encoder, res| {
+        skinned_renderer.draw(device, queue, encoder, res.view(hdr), res.view(depth), bind_group);
+      });
+    }
+    if let Some(morph_renderer) = morph_renderer.as_mut() {
+      graph.add_pass("morph").write(hdr).write(depth).execute(|encoder, res| {
+        morph_renderer.draw(encoder, res.view(hdr), res.view(depth), bind_group);
+      });
+    }
+    if !transparent.is_empty() {
+      match transparency {
+        TransparencyMode::Sorted => {
+          graph.add_pass("transparent").write(hdr).write(depth).execute(move |encoder, res| {
+            let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+              label: Some("Transparent Pass"),
+              depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: res.view(depth),
+                depth_ops: Some(Operations { load: LoadOp::Load, store: StoreOp::Store }),
+                stencil_ops: None,
+              }),
+              timestamp_writes: None,
+              occlusion_query_set: None,
+              color_attachments: &[Some(RenderPassColorAttachment {
+                view: res.view(hdr),
+                resolve_target: None,
+                ops: Operations { load: LoadOp::Load, store: StoreOp::Store },
+              })]
+            });
+            r_pass.set_bind_group(0, &*bind_group, &[]);
+            r_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            for batch in transparent.iter() {
+              r_pass.set_pipeline(pipelines.get_or_default(&batch.state));
+              r_pass.draw(batch.range.clone(), 0..1);
+            }
+          });
+        },
+        TransparencyMode::WeightedOit => {
+          // 半透明表面累加到两个临时纹理，再合成到场景颜色上
+          oit.prepare(device, HDR_FORMAT);
+          let accum = graph.create("oit_accum", TextureDesc::new(width, height, OIT_ACCUM_FORMAT));
+          let reveal = graph.create("oit_reveal", TextureDesc::new(width, height, OIT_REVEAL_FORMAT));
+          graph.add_pass("oit").write(accum).write(reveal).write(depth).execute(move |encoder, res| {
+            let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+              label: Some("OIT Pass"),
+              depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: res.view(depth),
+                depth_ops: Some(Operations { load: LoadOp::Load, store: StoreOp::Store }),
+                stencil_ops: None,
+              }),
+              timestamp_writes: None,
+              occlusion_query_set: None,
+              color_attachments: &[
+                Some(RenderPassColorAttachment {
+                  view: res.view(accum),
+                  resolve_target: None,
+                  ops: Operations { load: LoadOp::Clear(Color::TRANSPARENT), store: StoreOp::Store },
+                }),
+                Some(RenderPassColorAttachment {
+                  view: res.view(reveal),
+                  resolve_target: None,
+                  ops: Operations { load: LoadOp::Clear(Color::WHITE), store: StoreOp::Store },
+                }),
+              ]
+            });
+            r_pass.set_bind_group(0, &*bind_group, &[]);
+            r_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            for batch in transparent.iter() {
+              r_pass.set_pipeline(pipelines.get_or_default(&batch.state));
+              r_pass.draw(batch.range.clone(), 0..1);
+            }
+          });
+          let oit = &*oit;
+          graph.add_pass("oit_composite").read(accum).read(reveal).write(hdr).execute(move |encoder, res| {
+            oit.draw(device, encoder, res.view(hdr), HDR_FORMAT, res.view(accum), res.view(reveal));
+          });
+        },
+      }
+    }
+    graph.add_pass("debug").write(hdr).write(depth).execute(|encoder, res| {
+      debug_renderer.draw(device, queue, encoder, res.view(hdr), res.view(depth), bind_group, debug_draw);
+    });
+    let scene = SceneDepth { depth, normals: None, pvm, eye: camera.position() };
+    let frame_info = PostFrame { device, width, height, scene: Some(scene) };
+    post.add_passes(&mut graph, frame_info, PostTarget { id: hdr, format: HDR_FORMAT }, PostTarget { id: output, format });
+    if let Some(text_renderer) = text_renderer.as_mut().filter(|_| with_text) {
+      let (text_width, text_height) = (width as f32, height as f32);
+      graph.add_pass("text").write(output).execute(move |encoder, res| {
+        text_renderer.draw(device, queue, encoder, res.view(output), text_width, text_height);
+      });
+    }
+    if let Some(readback) = readback {
+      graph.add_pass("readback").read(output).execute(move |encoder, res| {
+        readback.copy(encoder, res.texture(output));
+      });
+    }
+    let result = graph.execute_timed(device, queue, render_targets, profiler.gpu_timer());
+    profiler.target_allocations += render_targets.take_created();
+    result
+  }
 
 }
@@ -0,0 +1,148 @@
+use serde_json::Value;
+
+use super::model::Scene;
+
+/// 当前场景文件版本，修改场景结构时加 1，并在 `MIGRATIONS` 中添加上一版本的迁移函数
+pub const SCENE_VERSION: u32 = 1;
+
+// 迁移函数：(旧版本, 把旧版本 JSON 升级到下一版本)
+type Migration = fn(&mut Value) -> Result<(), String>;
+const MIGRATIONS: &[(u32, Migration)] = &[(0, migrate_v0)];
+
+// 版本 0 的元素没有材质，没有颜色时使用的颜色
+const V0_DEFAULT_COLOR: [f32; 3] = [0.8, 0.8, 0.8];
+
+/// 把旧版本的场景 JSON 逐级升级到当前版本，没有 version 字段的文件视为版本 0
+pub fn migrate(value: &mut Value) -> Result<(), String> {
+  let mut version = match value.get("version") {
+    Some(v) => v.as_u64().ok_or("场景文件的 version 字段不是整数")? as u32,
+    None => 0,
+  };
+  if version > SCENE_VERSION {
+    return Err(format!("场景文件版本 {} 高于当前支持的版本 {}", version, SCENE_VERSION));
+  }
+  while version < SCENE_VERSION {
+    let (_, migration) = MIGRATIONS.iter().find(|(from, _)| *from == version)
+      .ok_or(format!("缺少从版本 {} 升级的迁移", version))?;
+    migration(value)?;
+    version += 1;
+    value["version"] = Value::from(version);
+  }
+  Ok(())
+}
+
+// 版本 0 → 1：元素上的 `color` 改为引用材质，相同颜色的元素共用一个名为 `color_rrggbb` 的材质
+fn migrate_v0(value: &mut Value) -> Result<(), String> {
+  let scene = value.as_object_mut().ok_or("场景文件不是 JSON 对象")?;
+  let mut materials = match scene.remove("materials") {
+    Some(Value::Array(materials)) => materials,
+    Some(_) => return Err("materials 字段不是数组".to_string()),
+    None => vec![],
+  };
+  if let Some(elements) = scene.get_mut("elements").and_then(Value::as_array_mut) {
+    for el in elements.iter_mut() {
+      let el = el.as_object_mut().ok_or("元素不是 JSON 对象")?;
+      if el.contains_key("material") {
+        continue
+      }
+      let color = match el.remove("color") {
+        Some(color) => serde_json::from_value::<[f32; 3]>(color).map_err(|e| format!("元素颜色格式错误: {}", e))?,
+        None => V0_DEFAULT_COLOR,
+      };
+      let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+      let name = format!("color_{:02x}{:02x}{:02x}", r, g, b);
+      if !materials.iter().any(|m| m["name"] == name.as_str()) {
+        materials.push(serde_json::json!({ "name": name, "color": color }));
+      }
+      el.insert("material".to_string(), Value::from(name));
+    }
+  }
+  scene.insert("materials".to_string(), Value::Array(materials));
+  Ok(())
+}
+
+pub fn scene_from_json(text: &str) -> Result<Scene, String> {
+  let mut value: Value = serde_json::from_str(text).map_err(|e| format!("场景文件格式错误: {}", e))?;
+  migrate(&mut value)?;
+  serde_json::from_value(value).map_err(|e| format!("场景文件内容错误: {}", e))
+}
+
+pub fn scene_to_json(scene: &Scene) -> Result<String, String> {
+  let mut scene = scene.clone();
+  scene.version = SCENE_VERSION;
+  serde_json::to_string_pretty(&scene).map_err(|e| e.to_string())
+}
+
+/// 读取场景文件，旧版本会自动迁移
+pub fn load_scene(path: &str) -> Result<Scene, String> {
+  let text = std::fs::read_to_string(path).map_err(|e| format!("读取场景文件 {} 失败: {}", path, e))?;
+  scene_from_json(&text)
+}
+
+/// 保存场景文件，总是写入当前版本
+pub fn save_scene(scene: &Scene, path: &str) -> Result<(), String> {
+  let text = scene_to_json(scene)?;
+  if let Some(dir) = std::path::Path::new(path).parent() {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+  }
+  std::fs::write(path, text).map_err(|e| format!("保存场景文件 {} 失败: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::scene::model::{Element, Material, Shape, Transform};
+
+  #[test]
+  fn round_trip() {
+    let mut scene = Scene::new("round trip");
+    scene.materials.push(Material { opacity: 0.5, ..Material::new("glass", [0.2, 0.4, 0.6]) });
+    scene.elements.push(Element {
+      id: 7,
+      name: "box".to_string(),
+      shape: Shape::Cube { size: [1.0, 2.0, 3.0] },
+      transform: Transform { rotation: [10.0, 20.0, 30.0], ..Transform::at(1.5, -2.0, 3.25) },
+      material: "glass".to_string(),
+    });
+    scene.elements.push(Element {
+      id: 8,
+      name: String::new(),
+      shape: Shape::Mesh { positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] },
+      transform: Transform::default(),
+      material: "glass".to_string(),
+    });
+    let text = scene_to_json(&scene).unwrap();
+    assert_eq!(scene_from_json(&text).unwrap(), scene);
+  }
+
+  #[test]
+  fn shipped_home_scene_is_current() {
+    let scene = scene_from_json(include_str!("../../assets/scenes/home.json")).unwrap();
+    assert_eq!(scene.version, SCENE_VERSION);
+    assert!(!scene.elements.is_empty());
+    assert!(scene.elements.iter().all(|el| scene.material(&el.material).is_some()));
+  }
+
+  #[test]
+  fn migrates_v0_fixture() {
+    let scene = scene_from_json(include_str!("../../tests/fixtures/scene_v0.json")).unwrap();
+    assert_eq!(scene.version, SCENE_VERSION);
+    assert_eq!(scene.name, "v0");
+    assert_eq!(scene.camera.pitch, -10.0);
+    // 相同颜色的元素共用一个材质，没有颜色的元素使用默认颜色
+    assert_eq!(scene.materials, vec![Material::new("color_ff0000", [1.0, 0.0, 0.0]), Material::new("color_cccccc", V0_DEFAULT_COLOR)]);
+    let materials: Vec<&str> = scene.elements.iter().map(|el| el.material.as_str()).collect();
+    assert_eq!(materials, vec!["color_ff0000", "color_ff0000", "color_cccccc"]);
+    assert_eq!(scene.elements[0].transform.position, [0.0, 50.0, 0.0]);
+    assert_eq!(scene.elements[1].name, "second");
+
+    // 迁移后保存再读取结果不变
+    assert_eq!(scene_from_json(&scene_to_json(&scene).unwrap()).unwrap(), scene);
+  }
+
+  #[test]
+  fn rejects_newer_versions() {
+    let text = format!("{{ \"version\": {} }}", SCENE_VERSION + 1);
+    assert!(scene_from_json(&text).unwrap_err().contains("高于"));
+  }
+}
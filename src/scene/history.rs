@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+
+use super::model::{Element, Material, Scene, Shape, Transform};
+
+// 默认最多保留的撤销步数
+const DEFAULT_LIMIT: usize = 100;
+
+/// 元素上可修改的属性
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+  Name(String),
+  Material(String), // 材质名称
+  Shape(Shape),
+}
+
+/// 可撤销的场景修改，保存修改前后的值
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+  AddElement { index: usize, element: Element },
+  RemoveElement { index: usize, element: Element },
+  SetTransform { id: u32, before: Transform, after: Transform },
+  SetMaterial { before: Material, after: Material }, // 按修改前的名称查找材质
+  SetProperty { id: u32, before: Property, after: Property },
+}
+
+impl Command {
+  /// 删除下标为 `index` 的元素，下标越界时返回 None
+  pub fn remove_element(scene: &Scene, index: usize) -> Option<Command> {
+    scene.elements.get(index).map(|element| Command::RemoveElement { index, element: element.clone() })
+  }
+
+  /// 修改元素变换，找不到元素时返回 None
+  pub fn set_transform(scene: &Scene, id: u32, after: Transform) -> Option<Command> {
+    scene.element(id).map(|el| Command::SetTransform { id, before: el.transform, after })
+  }
+
+  /// 修改材质颜色，找不到材质时返回 None
+  pub fn set_material_color(scene: &Scene, name: &str, color: [f32; 3]) -> Option<Command> {
+    scene.material(name).map(|m| Command::SetMaterial { before: m.clone(), after: Material { color, ..m.clone() } })
+  }
+
+  /// 修改元素属性，找不到元素时返回 None
+  pub fn set_property(scene: &Scene, id: u32, after: Property) -> Option<Command> {
+    let el = scene.element(id)?;
+    let before = match after {
+      Property::Name(_) => Property::Name(el.name.clone()),
+      Property::Material(_) => Property::Material(el.material.clone()),
+      Property::Shape(_) => Property::Shape(el.shape.clone()),
+    };
+    Some(Command::SetProperty { id, before, after })
+  }
+
+  /// 执行修改
+  pub fn apply(&self, scene: &mut Scene) {
+    match self {
+      Command::AddElement { index, element } => insert_element(scene, *index, element),
+      Command::RemoveElement { element, .. } => remove_element(scene, element.id),
+      Command::SetTransform { id, after, .. } => set_transform(scene, *id, after),
+      Command::SetMaterial { before, after } => set_material(scene, &before.name, after),
+      Command::SetProperty { id, after, .. } => set_property(scene, *id, after),
+    }
+  }
+
+  /// 撤销修改，场景需要处于 `apply` 之后的状态
+  pub fn revert(&self, scene: &mut Scene) {
+    match self {
+      Command::AddElement { element, .. } => remove_element(scene, element.id),
+      Command::RemoveElement { index, element } => insert_element(scene, *index, element),
+      Command::SetTransform { id, before, .. } => set_transform(scene, *id, before),
+      Command::SetMaterial { before, after } => set_material(scene, &after.name, before),
+      Command::SetProperty { id, before, .. } => set_property(scene, *id, before),
+    }
+  }
+
+  /// 把紧接着的同类修改合并到自身（例如拖动滑块时的连续修改），成功返回 true
+  pub fn merge(&mut self, next: &Command) -> bool {
+    match (self, next) {
+      (Command::SetTransform { id, after, .. }, Command::SetTransform { id: next_id, after: next_after, .. }) if id == next_id => {
+        *after = *next_after;
+        true
+      },
+      (Command::SetMaterial { after, .. }, Command::SetMaterial { before: next_before, after: next_after }) if after.name == next_before.name => {
+        *after = next_after.clone();
+        true
+      },
+      (Command::SetProperty { id, after, .. }, Command::SetProperty { id: next_id, after: next_after, .. })
+        if id == next_id && std::mem::discriminant(after) == std::mem::discriminant(next_after) => {
+        *after = next_after.clone();
+        true
+      },
+      _ => false,
+    }
+  }
+}
+
+fn insert_element(scene: &mut Scene, index: usize, element: &Element) {
+  let index = index.min(scene.elements.len());
+  scene.elements.insert(index, element.clone());
+}
+
+fn remove_element(scene: &mut Scene, id: u32) {
+  scene.elements.retain(|e| e.id != id);
+}
+
+fn set_transform(scene: &mut Scene, id: u32, transform: &Transform) {
+  if let Some(el) = scene.element_mut(id) {
+    el.transform = *transform;
+  }
+}
+
+fn set_material(scene: &mut Scene, name: &str, material: &Material) {
+  if let Some(m) = scene.materials.iter_mut().find(|m| m.name == name) {
+    *m = material.clone();
+  }
+}
+
+fn set_property(scene: &mut Scene, id: u32, property: &Property) {
+  let Some(el) = scene.element_mut(id) else {
+    return
+  };
+  match property {
+    Property::Name(name) => el.name = name.clone(),
+    Property::Material(material) => el.material = material.clone(),
+    Property::Shape(shape) => el.shape = shape.clone(),
+  }
+}
+
+/// 撤销/重做历史
+///
+/// 通过 `execute` 执行的修改会记录下来。连续的同类修改会合并成一步，直到调用 `seal`（例如松开鼠标结束拖动）。
+pub struct History {
+  undo_stack: VecDeque<Command>,
+  redo_stack: Vec<Command>,
+  limit: usize, // 最多保留的撤销步数
+  merging: bool, // 下一个修改是否可以合并到上一步
+}
+
+impl Default for History {
+  fn default() -> Self {
+    Self::new(DEFAULT_LIMIT)
+  }
+}
+
+impl History {
+  pub fn new(limit: usize) -> Self {
+    Self {
+      undo_stack: VecDeque::new(),
+      redo_stack: vec![],
+      limit: limit.max(1),
+      merging: false,
+    }
+  }
+
+  /// 执行修改并记录，会清空重做栈
+  pub fn execute(&mut self, scene: &mut Scene, command: Command) {
+    command.apply(scene);
+    self.redo_stack.clear();
+    if self.merging {
+      if let Some(last) = self.undo_stack.back_mut() {
+        if last.merge(&command) {
+          return
+        }
+      }
+    }
+    self.undo_stack.push_back(command);
+    if self.undo_stack.len() > self.limit {
+      self.undo_stack.pop_front();
+    }
+    self.merging = true;
+  }
+
+  /// 结束当前的连续修改，之后的修改单独记录
+  pub fn seal(&mut self) {
+    self.merging = false;
+  }
+
+  /// 撤销一步，没有可撤销的修改时返回 false
+  pub fn undo(&mut self, scene: &mut Scene) -> bool {
+    self.seal();
+    let Some(command) = self.undo_stack.pop_back() else {
+      return false
+    };
+    command.revert(scene);
+    self.redo_stack.push(command);
+    true
+  }
+
+  /// 重做一步，没有可重做的修改时返回 false
+  pub fn redo(&mut self, scene: &mut Scene) -> bool {
+    self.seal();
+    let Some(command) = self.redo_stack.pop() else {
+      return false
+    };
+    command.apply(scene);
+    self.undo_stack.push_back(command);
+    true
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo_stack.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo_stack.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.undo_stack.clear();
+    self.redo_stack.clear();
+    self.merging = false;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 测试用的固定种子伪随机数（xorshift64），结果可以复现
+  struct Rng(u64);
+
+  impl Rng {
+    fn next(&mut self) -> u64 {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+      (self.next() % n as u64) as usize
+    }
+
+    fn float(&mut self) -> f32 {
+      (self.next() % 1000) as f32 / 100.0
+    }
+  }
+
+  fn cube(id: u32, material: &str) -> Element {
+    Element { id, name: format!("cube_{}", id), shape: Shape::Cube { size: [1.0; 3] }, transform: Transform::default(), material: material.to_string() }
+  }
+
+  fn sample_scene() -> Scene {
+    let mut scene = Scene::new("test");
+    scene.materials = vec![Material::new("red", [1.0, 0.0, 0.0]), Material::new("blue", [0.0, 0.0, 1.0])];
+    scene.elements = (1..=3).map(|id| cube(id, "red")).collect();
+    scene
+  }
+
+  // 按当前场景生成一个随机修改，场景为空时只会添加元素
+  fn random_command(rng: &mut Rng, scene: &Scene) -> Command {
+    if scene.elements.is_empty() {
+      return Command::AddElement { index: 0, element: cube(scene.next_id(), "blue") };
+    }
+    let id = scene.elements[rng.below(scene.elements.len())].id;
+    match rng.below(6) {
+      0 => Command::AddElement { index: rng.below(scene.elements.len() + 1), element: cube(scene.next_id(), "blue") },
+      1 => Command::remove_element(scene, rng.below(scene.elements.len())).unwrap(),
+      2 => Command::set_transform(scene, id, Transform::at(rng.float(), rng.float(), rng.float())).unwrap(),
+      3 => {
+        let name = &scene.materials[rng.below(scene.materials.len())].name;
+        Command::set_material_color(scene, name, [rng.float(), rng.float(), rng.float()]).unwrap()
+      },
+      4 => Command::set_property(scene, id, Property::Name(format!("name_{}", rng.below(100)))).unwrap(),
+      _ => Command::set_property(scene, id, Property::Shape(Shape::Cube { size: [rng.float(); 3] })).unwrap(),
+    }
+  }
+
+  #[test]
+  fn random_commands_undo_and_redo() {
+    for seed in 1..=20u64 {
+      let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+      let original = sample_scene();
+      let mut scene = original.clone();
+      let mut history = History::new(1000);
+      for _ in 0..200 {
+        let command = random_command(&mut rng, &scene);
+        history.execute(&mut scene, command);
+        // 偶尔结束连续修改，合并和不合并的情况都会出现
+        if rng.below(3) == 0 {
+          history.seal();
+        }
+      }
+      let last = scene.clone();
+
+      while history.undo(&mut scene) {}
+      assert_eq!(scene, original, "seed {}", seed);
+      assert!(!history.can_undo());
+
+      while history.redo(&mut scene) {}
+      assert_eq!(scene, last, "seed {}", seed);
+      assert!(!history.can_redo());
+    }
+  }
+
+  #[test]
+  fn consecutive_changes_merge_until_sealed() {
+    let original = sample_scene();
+    let mut scene = original.clone();
+    let mut history = History::default();
+    for x in 1..=5 {
+      let command = Command::set_transform(&scene, 1, Transform::at(x as f32, 0.0, 0.0)).unwrap();
+      history.execute(&mut scene, command);
+    }
+    assert_eq!(history.undo_stack.len(), 1);
+    assert_eq!(scene.element(1).unwrap().transform, Transform::at(5.0, 0.0, 0.0));
+
+    // 结束拖动后的修改单独记录，撤销一步回到拖动结束时的状态
+    history.seal();
+    let command = Command::set_transform(&scene, 1, Transform::at(9.0, 0.0, 0.0)).unwrap();
+    history.execute(&mut scene, command);
+    assert_eq!(history.undo_stack.len(), 2);
+    history.undo(&mut scene);
+    assert_eq!(scene.element(1).unwrap().transform, Transform::at(5.0, 0.0, 0.0));
+    history.undo(&mut scene);
+    assert_eq!(scene, original);
+  }
+
+  #[test]
+  fn different_targets_do_not_merge() {
+    let mut scene = sample_scene();
+    let mut history = History::default();
+    let commands = [
+      Command::set_transform(&scene, 1, Transform::at(1.0, 0.0, 0.0)).unwrap(),
+      Command::set_transform(&scene, 2, Transform::at(2.0, 0.0, 0.0)).unwrap(),
+      Command::set_property(&scene, 2, Property::Name("a".to_string())).unwrap(),
+      Command::set_property(&scene, 2, Property::Material("blue".to_string())).unwrap(),
+      Command::set_material_color(&scene, "red", [0.5; 3]).unwrap(),
+      Command::set_material_color(&scene, "blue", [0.5; 3]).unwrap(),
+    ];
+    for command in commands {
+      history.execute(&mut scene, command);
+    }
+    assert_eq!(history.undo_stack.len(), 6);
+
+    // 同一个材质的连续修改合并，撤销时回到第一次修改之前的颜色
+    let mut history = History::default();
+    let mut scene = sample_scene();
+    for c in [0.2, 0.4, 0.6] {
+      let command = Command::set_material_color(&scene, "red", [c; 3]).unwrap();
+      history.execute(&mut scene, command);
+    }
+    assert_eq!(history.undo_stack.len(), 1);
+    history.undo(&mut scene);
+    assert_eq!(scene.material("red").unwrap().color, [1.0, 0.0, 0.0]);
+  }
+
+  #[test]
+  fn execute_clears_redo_and_respects_limit() {
+    let mut scene = sample_scene();
+    let mut history = History::new(3);
+    for id in 1..=3 {
+      history.seal();
+      let command = Command::set_property(&scene, id, Property::Name(format!("n{}", id))).unwrap();
+      history.execute(&mut scene, command);
+    }
+    history.seal();
+    let command = Command::set_property(&scene, 1, Property::Name("again".to_string())).unwrap();
+    history.execute(&mut scene, command);
+    // 超过上限时丢弃最早的一步
+    assert_eq!(history.undo_stack.len(), 3);
+    while history.undo(&mut scene) {}
+    assert_eq!(scene.element(1).unwrap().name, "n1");
+
+    history.redo(&mut scene);
+    assert!(history.can_redo());
+    let command = Command::set_transform(&scene, 3, Transform::at(1.0, 1.0, 1.0)).unwrap();
+    history.execute(&mut scene, command);
+    assert!(!history.can_redo());
+  }
+}
@@ -0,0 +1,108 @@
+use nalgebra::Vector3;
+
+// 二进制 STL：80 字节文件头 + 4 字节三角形数量，每个三角形 50 字节
+const STL_HEADER: usize = 84;
+const STL_TRIANGLE: usize = 50;
+
+/// 导入的网格：三角形列表已经移到包围盒中心，`center` 为原来的中心，作为元素的位置
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedMesh {
+  pub positions: Vec<[f32; 3]>,
+  pub center: [f32; 3],
+}
+
+/// 按文件内容识别格式并导入：二进制 STL、ASCII STL 或 Wavefront OBJ（只读取顶点位置和面）
+pub fn import_model(data: &[u8]) -> Result<ImportedMesh, String> {
+  let positions = if is_binary_stl(data) {
+    parse_binary_stl(data)
+  } else {
+    let text = std::str::from_utf8(data).map_err(|_| "无法识别的模型格式，支持 OBJ 和 STL".to_string())?;
+    if text.trim_start().starts_with("solid") && text.contains("facet") {
+      parse_ascii_stl(text)?
+    } else {
+      parse_obj(text)?
+    }
+  };
+  centered(positions)
+}
+
+/// 把三角形列表移到包围盒中心，顶点数不是 3 的倍数或没有顶点时返回错误
+pub fn centered(mut positions: Vec<[f32; 3]>) -> Result<ImportedMesh, String> {
+  if positions.is_empty() || !positions.len().is_multiple_of(3) {
+    return Err(format!("网格的顶点数 {} 不能组成三角形", positions.len()));
+  }
+  if positions.iter().flatten().any(|v| !v.is_finite()) {
+    return Err("网格中有无效的坐标".to_string());
+  }
+  let mut min = Vector3::repeat(f32::MAX);
+  let mut max = Vector3::repeat(f32::MIN);
+  for p in positions.iter() {
+    min = min.inf(&Vector3::from(*p));
+    max = max.sup(&Vector3::from(*p));
+  }
+  let center = (min + max) / 2.0;
+  for p in positions.iter_mut() {
+    *p = [p[0] - center.x, p[1] - center.y, p[2] - center.z];
+  }
+  Ok(ImportedMesh { positions, center: center.into() })
+}
+
+// ASCII STL 也以 solid 开头，用文件长度和三角形数量是否吻合来区分
+fn is_binary_stl(data: &[u8]) -> bool {
+  if data.len() < STL_HEADER {
+    return false;
+  }
+  // wasm32 上 usize 只有 32 位，按 u64 计算避免溢出
+  let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as u64;
+  data.len() as u64 == STL_HEADER as u64 + count * STL_TRIANGLE as u64
+}
+
+fn parse_binary_stl(data: &[u8]) -> Vec<[f32; 3]> {
+  let float = |b: &[u8], i: usize| f32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+  data[STL_HEADER..].chunks_exact(STL_TRIANGLE).flat_map(|triangle| {
+    // 每个三角形先是 12 字节的法线，再是三个顶点
+    (0..3).map(move |k| {
+      let p = 12 + k * 12;
+      [float(triangle, p), float(triangle, p + 4), float(triangle, p + 8)]
+    })
+  }).collect()
+}
+
+fn parse_ascii_stl(text: &str) -> Result<Vec<[f32; 3]>, String> {
+  text.lines().enumerate()
+    .filter_map(|(i, line)| line.trim().strip_prefix("vertex ").map(|rest| (i, rest)))
+    .map(|(i, rest)| parse_vec3(rest).ok_or_else(|| format!("STL 第 {} 行顶点格式错误", i + 1)))
+    .collect()
+}
+
+// 多边形按扇形拆成三角形，索引从 1 开始，负数表示从末尾倒数
+fn parse_obj(text: &str) -> Result<Vec<[f32; 3]>, String> {
+  let mut vertices: Vec<[f32; 3]> = vec![];
+  let mut positions = vec![];
+  for (i, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix("v ") {
+      vertices.push(parse_vec3(rest).ok_or_else(|| format!("OBJ 第 {} 行顶点格式错误", i + 1))?);
+    } else if let Some(rest) = line.strip_prefix("f ") {
+      let face = rest.split_whitespace().map(|item| {
+        let index: i64 = item.split('/').next().unwrap_or_default().parse().map_err(|_| format!("OBJ 第 {} 行面格式错误", i + 1))?;
+        let resolved = if index < 0 { vertices.len() as i64 + index } else { index - 1 };
+        vertices.get(resolved as usize).filter(|_| resolved >= 0).copied()
+          .ok_or_else(|| format!("OBJ 第 {} 行的顶点索引 {} 超出范围", i + 1, index))
+      }).collect::<Result<Vec<_>, String>>()?;
+      if face.len() < 3 {
+        return Err(format!("OBJ 第 {} 行的面少于 3 个顶点", i + 1));
+      }
+      for k in 1..face.len() - 1 {
+        positions.extend([face[0], face[k], face[k + 1]]);
+      }
+    }
+  }
+  Ok(positions)
+}
+
+fn parse_vec3(text: &str) -> Option<[f32; 3]> {
+  let mut values = text.split_whitespace().map(|v| v.parse::<f32>());
+  let p = [values.next()?.ok()?, values.next()?.ok()?, values.next()?.ok()?];
+  Some(p)
+}
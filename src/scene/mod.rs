@@ -0,0 +1,5 @@
+pub mod model;
+pub mod file;
+pub mod history;
+pub mod import;
+pub mod pick;
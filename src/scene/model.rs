@@ -0,0 +1,308 @@
+use nalgebra::{Matrix4, Rotation3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::element::cube::Cube;
+use crate::render::camera::Camera;
+use crate::render::draw::RenderBatch;
+use crate::render::pipeline::RenderState;
+use crate::render::vertex::Vertex;
+
+// 立方体六个面的明暗系数，没有光照时用来区分各个面
+const FACE_SHADES: [f32; 6] = [1.0, 0.8, 0.95, 0.6, 0.7, 0.85];
+// 网格按法线与这个方向的夹角计算明暗，morph.wgsl 中的 SHADE_DIRECTION 与它一致
+const MESH_SHADE_DIRECTION: [f32; 3] = [0.3, 1.0, -0.5];
+
+/// 世界坐标下单位法线对应的明暗系数（0.6～1.0），正反面相同
+pub fn mesh_shade(normal: &Vector3<f32>) -> f32 {
+  0.6 + 0.4 * normal.dot(&Vector3::from(MESH_SHADE_DIRECTION).normalize()).abs()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+  pub position: [f32; 3],
+  pub rotation: [f32; 3], // 欧拉角（角度），按 x、y、z 顺序旋转
+  pub scale: [f32; 3],
+}
+
+impl Default for Transform {
+  fn default() -> Self {
+    Self {
+      position: [0.0, 0.0, 0.0],
+      rotation: [0.0, 0.0, 0.0],
+      scale: [1.0, 1.0, 1.0],
+    }
+  }
+}
+
+impl Transform {
+  pub fn at(x: f32, y: f32, z: f32) -> Self {
+    Self { position: [x, y, z], ..Default::default() }
+  }
+
+  /// 模型矩阵：平移 * 旋转 * 缩放
+  pub fn matrix(&self) -> Matrix4<f32> {
+    let [rx, ry, rz] = self.rotation;
+    let rotation = Rotation3::from_euler_angles(rx.to_radians(), ry.to_radians(), rz.to_radians());
+    Matrix4::new_translation(&Vector3::from(self.position))
+      * rotation.to_homogeneous()
+      * Matrix4::new_nonuniform_scaling(&Vector3::from(self.scale))
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Shape {
+  Cube { size: [f32; 3] },
+  Mesh { positions: Vec<[f32; 3]> }, // 三角形列表，模型空间，导入时已移到包围盒中心
+}
+
+impl Shape {
+  /// 模型空间（缩放前）的包围盒尺寸
+  pub fn size(&self) -> Vector3<f32> {
+    match self {
+      Shape::Cube { size } => Vector3::from(*size),
+      Shape::Mesh { positions } => {
+        let mut min = Vector3::repeat(f32::MAX);
+        let mut max = Vector3::repeat(f32::MIN);
+        for p in positions.iter() {
+          min = min.inf(&Vector3::from(*p));
+          max = max.sup(&Vector3::from(*p));
+        }
+        if positions.is_empty() { Vector3::zeros() } else { max - min }
+      },
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Material {
+  pub name: String,
+  pub color: [f32; 3],
+  #[serde(default = "default_opacity")]
+  pub opacity: f32, // 不透明度，小于 1 时按半透明绘制
+}
+
+fn default_opacity() -> f32 {
+  1.0
+}
+
+impl Material {
+  pub fn new(name: &str, color: [f32; 3]) -> Self {
+    Self { name: name.to_string(), color, opacity: 1.0 }
+  }
+
+  pub fn is_transparent(&self) -> bool {
+    self.opacity < 1.0
+  }
+
+  /// 顶点使用的 rgba 颜色
+  pub fn rgba(&self) -> [f32; 4] {
+    [self.color[0], self.color[1], self.color[2], self.opacity.clamp(0.0, 1.0)]
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Element {
+  pub id: u32,
+  #[serde(default)]
+  pub name: String,
+  pub shape: Shape,
+  #[serde(default)]
+  pub transform: Transform,
+  pub material: String, // 材质名称
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LightKind {
+  Ambient,
+  Directional { direction: [f32; 3] },
+  Point { position: [f32; 3], range: f32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Light {
+  #[serde(flatten)]
+  pub kind: LightKind,
+  pub color: [f32; 3],
+  pub intensity: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraDesc {
+  pub position: [f32; 3],
+  pub yaw: f32, // 偏航角（角度）
+  pub pitch: f32, // 俯仰角（角度）
+  pub fov: f32, // 视场角（角度）
+  pub near: f32,
+  pub far: f32,
+}
+
+impl Default for CameraDesc {
+  fn default() -> Self {
+    Self {
+      position: [0.0, 0.0, 0.0],
+      yaw: 0.0,
+      pitch: 0.0,
+      fov: 45.0,
+      near: 0.1,
+      far: 100.0,
+    }
+  }
+}
+
+impl CameraDesc {
+  pub fn from_camera(camera: &Camera) -> Self {
+    let p = camera.position();
+    Self {
+      position: [p.x, p.y, p.z],
+      yaw: camera.yaw().to_degrees(),
+      pitch: camera.pitch().to_degrees(),
+      fov: camera.fov().to_degrees(),
+      near: camera.near(),
+      far: camera.far(),
+    }
+  }
+
+  pub fn apply(&self, camera: &mut Camera) {
+    camera.set_pose(Vector3::from(self.position), self.yaw.to_radians(), self.pitch.to_radians());
+    camera.set_projection(self.fov.to_radians(), self.near, self.far);
+  }
+}
+
+/// 场景描述，可以保存为 JSON 文件
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+  pub version: u32,
+  #[serde(default)]
+  pub name: String,
+  #[serde(default = "default_background")]
+  pub background: [f32; 4],
+  #[serde(default)]
+  pub camera: CameraDesc,
+  #[serde(default)]
+  pub lights: Vec<Light>,
+  #[serde(default)]
+  pub materials: Vec<Material>,
+  #[serde(default)]
+  pub elements: Vec<Element>,
+}
+
+fn default_background() -> [f32; 4] {
+  [0.1, 0.2, 0.3, 1.0]
+}
+
+impl Scene {
+  pub fn new(name: &str) -> Self {
+    Self {
+      version: super::file::SCENE_VERSION,
+      name: name.to_string(),
+      background: default_background(),
+      camera: CameraDesc::default(),
+      lights: vec![],
+      materials: vec![],
+      elements: vec![],
+    }
+  }
+
+  pub fn material(&self, name: &str) -> Option<&Material> {
+    self.materials.iter().find(|m| m.name == name)
+  }
+
+  pub fn element(&self, id: u32) -> Option<&Element> {
+    self.elements.iter().find(|e| e.id == id)
+  }
+
+  pub fn element_mut(&mut self, id: u32) -> Option<&mut Element> {
+    self.elements.iter_mut().find(|e| e.id == id)
+  }
+
+  /// 下一个可用的元素 id
+  pub fn next_id(&self) -> u32 {
+    self.elements.iter().map(|e| e.id + 1).max().unwrap_or(1)
+  }
+
+  /// 所有元素的包围盒，没有元素时返回 None
+  pub fn bounds(&self) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    self.elements.iter().map(|e| e.bounds()).reduce(|(min_a, max_a), (min_b, max_b)| (min_a.inf(&min_b), max_a.sup(&max_b)))
+  }
+
+  /// 生成所有元素的顶点，找不到材质时使用白色
+  pub fn vertices(&self) -> Vec<Vertex> {
+    let mut vertex_list = vec![];
+    for element in self.elements.iter() {
+      vertex_list.extend(element.vertices(self.element_color(element)));
+    }
+    vertex_list
+  }
+
+  /// 生成顶点和对应的绘制批次：不透明元素在前，合成一个批次；半透明元素各自一个批次，
+  /// 带上包围盒中心，由渲染器按到相机的距离排序
+  pub fn render_list(&self) -> (Vec<Vertex>, Vec<RenderBatch>) {
+    let mut vertex_list = vec![];
+    let (opaque, transparent): (Vec<&Element>, Vec<&Element>) = self.elements.iter()
+      .partition(|e| !self.material(&e.material).is_some_and(|m| m.is_transparent()));
+    for element in opaque {
+      vertex_list.extend(element.vertices(self.element_color(element)));
+    }
+    let mut batches = vec![RenderBatch::new(RenderState::default(), 0..vertex_list.len() as u32)];
+    for element in transparent {
+      let start = vertex_list.len() as u32;
+      vertex_list.extend(element.vertices(self.element_color(element)));
+      let (min, max) = element.bounds();
+      batches.push(RenderBatch {
+        center: Some((min + max) / 2.0),
+        ..RenderBatch::new(RenderState::transparent(), start..vertex_list.len() as u32)
+      });
+    }
+    (vertex_list, batches)
+  }
+
+  fn element_color(&self, element: &Element) -> [f32; 4] {
+    self.material(&element.material).map(|m| m.rgba()).unwrap_or([1.0, 1.0, 1.0, 1.0])
+  }
+}
+
+impl Element {
+  /// 世界坐标下的轴对齐包围盒
+  pub fn bounds(&self) -> (Vector3<f32>, Vector3<f32>) {
+    let vertices = self.vertices([1.0, 1.0, 1.0, 1.0]);
+    let mut min = Vector3::repeat(f32::MAX);
+    let mut max = Vector3::repeat(f32::MIN);
+    for v in vertices.iter() {
+      let p = Vector3::from(v.position);
+      min = min.inf(&p);
+      max = max.sup(&p);
+    }
+    (min, max)
+  }
+
+  /// 按变换和材质颜色生成世界坐标下的顶点
+  pub fn vertices(&self, color: [f32; 4]) -> Vec<Vertex> {
+    let mut vertices = match &self.shape {
+      Shape::Cube { size } => Cube::new(0.0, 0.0, 0.0, size[0], size[1], size[2], 1.0).pos,
+      Shape::Mesh { positions } => positions.iter().map(|p| Vertex { position: *p, color, tex_coords: [0.0, 0.0] }).collect(),
+    };
+    let matrix = self.transform.matrix();
+    for v in vertices.iter_mut() {
+      let p = matrix.transform_point(&v.position.into());
+      v.position = [p.x, p.y, p.z];
+    }
+    for (i, triangle) in vertices.chunks_mut(3).enumerate() {
+      let shade = match self.shape {
+        Shape::Cube { .. } => FACE_SHADES[(i / 2) % FACE_SHADES.len()],
+        Shape::Mesh { .. } => {
+          let [a, b, c] = [0, 1, 2].map(|k| Vector3::from(triangle.get(k).map(|v| v.position).unwrap_or_default()));
+          let normal = (b - a).cross(&(c - a)).try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y);
+          mesh_shade(&normal)
+        },
+      };
+      for v in triangle.iter_mut() {
+        v.color = [color[0] * shade, color[1] * shade, color[2] * shade, color[3]];
+      }
+    }
+    vertices
+  }
+}
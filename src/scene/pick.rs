@@ -0,0 +1,46 @@
+use nalgebra::Vector3;
+
+use super::model::Scene;
+
+/// 射线拾取的结果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+  pub id: u32, // 元素 id
+  pub distance: f32, // 从射线起点到交点的距离
+  pub point: Vector3<f32>, // 世界坐标下的交点
+}
+
+/// 射线与所有元素的三角形求交，返回最近的交点；`direction` 需要是单位向量
+pub fn pick(scene: &Scene, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<Hit> {
+  scene.elements.iter().filter_map(|el| {
+    let vertices = el.vertices([1.0, 1.0, 1.0, 1.0]);
+    let distance = vertices.chunks_exact(3)
+      .filter_map(|t| ray_triangle(origin, direction, [t[0].position, t[1].position, t[2].position].map(Vector3::from)))
+      .min_by(|a, b| a.total_cmp(b))?;
+    Some(Hit { id: el.id, distance, point: origin + direction * distance })
+  }).min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+// Möller–Trumbore 求交，正反两面都算相交，返回射线参数 t
+fn ray_triangle(origin: Vector3<f32>, direction: Vector3<f32>, [a, b, c]: [Vector3<f32>; 3]) -> Option<f32> {
+  let edge1 = b - a;
+  let edge2 = c - a;
+  let p = direction.cross(&edge2);
+  let det = edge1.dot(&p);
+  if det.abs() < f32::EPSILON {
+    return None;
+  }
+  let inv_det = 1.0 / det;
+  let s = origin - a;
+  let u = s.dot(&p) * inv_det;
+  if !(0.0..=1.0).contains(&u) {
+    return None;
+  }
+  let q = s.cross(&edge1);
+  let v = direction.dot(&q) * inv_det;
+  if v < 0.0 || u + v > 1.0 {
+    return None;
+  }
+  let t = edge2.dot(&q) * inv_det;
+  (t > 0.0).then_some(t)
+}
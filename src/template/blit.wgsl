@@ -0,0 +1,12 @@
+// 把纹理复制到另一种格式的纹理上
+// params.values[0].x: 大于 0 时把线性颜色编码为 sRGB
+#include "post_common.wgsl"
+
+@fragment
+fn fs_blit(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    var color = clamp(textureSample(t_input, s_linear, in.uv).rgb, vec3f(0.0), vec3f(1.0));
+    if params.values[0].x > 0.0 {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
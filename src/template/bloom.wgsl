@@ -0,0 +1,58 @@
+// 泛光：提取亮部并缩小到一半，逐级缩小模糊，再逐级放大叠加，最后加回原图
+// params.values[0]: x 亮度阈值，y 阈值的柔和范围，z 放大时的采样半径（纹素），w 叠加强度
+#include "post_common.wgsl"
+
+fn texel_size() -> vec2f {
+    return 1.0 / vec2f(textureDimensions(t_input));
+}
+
+// 4 次双线性采样组成的 4x4 盒式滤波
+fn box_sample(uv: vec2f) -> vec3f {
+    let d = texel_size();
+    return (textureSample(t_input, s_linear, uv + vec2f(-d.x, -d.y)).rgb
+        + textureSample(t_input, s_linear, uv + vec2f(d.x, -d.y)).rgb
+        + textureSample(t_input, s_linear, uv + vec2f(-d.x, d.y)).rgb
+        + textureSample(t_input, s_linear, uv + vec2f(d.x, d.y)).rgb) * 0.25;
+}
+
+@fragment
+fn fs_prefilter(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let settings = params.values[0];
+    // 限制亮度，避免单个极亮的像素造成闪烁
+    let color = min(box_sample(in.uv), vec3f(1000.0));
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = max(settings.y, 1e-5);
+    var soft = clamp(brightness - settings.x + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+    let contribution = max(soft, brightness - settings.x) / max(brightness, 1e-5);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(box_sample(in.uv), 1.0);
+}
+
+// 对低一级的结果做 3x3 帐篷滤波放大，加上同一级缩小时的结果
+@fragment
+fn fs_upsample(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let d = texel_size() * params.values[0].z;
+    var sum = textureSample(t_input, s_linear, in.uv).rgb * 4.0;
+    sum += (textureSample(t_input, s_linear, in.uv + vec2f(-d.x, 0.0)).rgb
+        + textureSample(t_input, s_linear, in.uv + vec2f(d.x, 0.0)).rgb
+        + textureSample(t_input, s_linear, in.uv + vec2f(0.0, -d.y)).rgb
+        + textureSample(t_input, s_linear, in.uv + vec2f(0.0, d.y)).rgb) * 2.0;
+    sum += textureSample(t_input, s_linear, in.uv + vec2f(-d.x, -d.y)).rgb
+        + textureSample(t_input, s_linear, in.uv + vec2f(d.x, -d.y)).rgb
+        + textureSample(t_input, s_linear, in.uv + vec2f(-d.x, d.y)).rgb
+        + textureSample(t_input, s_linear, in.uv + vec2f(d.x, d.y)).rgb;
+    let current = textureSample(t_extra, s_linear, in.uv).rgb;
+    return vec4<f32>(current + sum / 16.0, 1.0);
+}
+
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(t_input, s_linear, in.uv);
+    let bloom = textureSample(t_extra, s_linear, in.uv).rgb;
+    return vec4<f32>(hdr.rgb + bloom * params.values[0].w, hdr.a);
+}
@@ -0,0 +1,24 @@
+// 场景着色器共用的相机 uniform 和工具函数，通过 #include "common.wgsl" 引入
+
+struct UniformBufferObject {
+    proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    model: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> ubo: UniformBufferObject;
+
+fn is_nan(val: f32) -> bool {
+    return val != val;
+}
+
+// 世界坐标转换到裁剪空间：ubo.proj 已经是投影、视图、模型矩阵的乘积，这里手动做透视除法
+fn to_clip(world: vec3f) -> vec4<f32> {
+    let pos = ubo.proj * vec4<f32>(world, 1.0);
+    var out = vec4<f32>(pos.xyz/pos.w, 1.0);
+    if(is_nan(out.x) || is_nan(out.y) || is_nan(out.z)){
+        out = vec4<f32>(0.0);
+    }
+    return out;
+}
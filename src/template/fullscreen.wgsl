@@ -0,0 +1,27 @@
+// 全屏三角形的顶点着色器和颜色工具函数，不包含任何绑定
+
+struct FullscreenOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2f,
+}
+
+// 用一个覆盖整个屏幕的三角形绘制，不需要顶点缓冲
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> FullscreenOutput {
+    var out: FullscreenOutput;
+    let uv = vec2f(f32((index << 1u) & 2u), f32(index & 2u));
+    out.pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// 线性颜色编码为 sRGB，输出纹理不是 sRGB 格式时使用
+fn linear_to_srgb(color: vec3f) -> vec3f {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3f(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3f(0.0031308));
+}
+
+fn luminance(color: vec3f) -> f32 {
+    return dot(color, vec3f(0.2126, 0.7152, 0.0722));
+}
@@ -0,0 +1,42 @@
+// FXAA 抗锯齿，输入为色调映射后的 LDR 颜色
+// params.values[0]: x 最大搜索跨度（像素），y 方向衰减系数，z 最小衰减，w 对比度阈值（低于它不处理）
+#include "post_common.wgsl"
+
+fn luma(color: vec3f) -> f32 {
+    // 在感知亮度上比较，sRGB 纹理采样得到的是线性值
+    return sqrt(luminance(color));
+}
+
+@fragment
+fn fs_fxaa(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let settings = params.values[0];
+    let d = 1.0 / vec2f(textureDimensions(t_input));
+    let center = textureSample(t_input, s_linear, in.uv);
+    let luma_nw = luma(textureSample(t_input, s_linear, in.uv + vec2f(-d.x, -d.y)).rgb);
+    let luma_ne = luma(textureSample(t_input, s_linear, in.uv + vec2f(d.x, -d.y)).rgb);
+    let luma_sw = luma(textureSample(t_input, s_linear, in.uv + vec2f(-d.x, d.y)).rgb);
+    let luma_se = luma(textureSample(t_input, s_linear, in.uv + vec2f(d.x, d.y)).rgb);
+    let luma_m = luma(center.rgb);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // 采样和提前返回都放在分支外，保证 textureSample 在统一控制流中
+    var dir = vec2f(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * settings.y, settings.z);
+    let scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, vec2f(-settings.x), vec2f(settings.x)) * d;
+
+    let a = 0.5 * (textureSample(t_input, s_linear, in.uv + dir * (1.0 / 3.0 - 0.5)).rgb
+        + textureSample(t_input, s_linear, in.uv + dir * (2.0 / 3.0 - 0.5)).rgb);
+    let b = a * 0.5 + 0.25 * (textureSample(t_input, s_linear, in.uv - dir * 0.5).rgb
+        + textureSample(t_input, s_linear, in.uv + dir * 0.5).rgb);
+    let luma_b = luma(b);
+    var color = select(b, a, luma_b < luma_min || luma_b > luma_max);
+    if luma_max - luma_min < max(settings.w * luma_max, 0.0312) {
+        color = center.rgb;
+    }
+    return vec4<f32>(color, center.a);
+}
@@ -0,0 +1,70 @@
+#include "common.wgsl"
+
+struct VertexInput{
+    @builtin(vertex_index) index: u32,
+    @location(0) position: vec3f,
+    @location(1) color: vec4f,
+}
+
+struct VertexOutput{
+    @builtin(position) pos: vec4<f32>,
+    @location(0) color: vec4f,
+}
+
+// 与 anim::morph::MAX_MORPH_TARGETS 一致，每个 vec4 存 4 个权重
+#define MAX_MORPH_TARGETS 32
+
+struct MorphUniform {
+    model: mat4x4<f32>,
+    weights: array<vec4f, MAX_MORPH_TARGETS / 4>,
+    vertex_count: u32,
+    target_count: u32,
+    has_normals: u32,
+}
+
+@group(1) @binding(0)
+var<uniform> morph: MorphUniform;
+
+// 第 t 个变形目标第 v 个顶点的位置偏移位于 deltas[t * vertex_count + v]；
+// has_normals 为 1 时，法线偏移位于 deltas[(target_count + t) * vertex_count + v]，
+// 基础法线位于 deltas[2 * target_count * vertex_count + v]
+@group(1) @binding(1)
+var<storage, read> deltas: array<vec4f>;
+
+// 与 scene::model 中的 MESH_SHADE_DIRECTION 一致
+const SHADE_DIRECTION = vec3f(0.3, 1.0, -0.5);
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    var position = in.position;
+    let count = morph.vertex_count;
+    for (var t = 0u; t < morph.target_count; t++) {
+        let weight = morph.weights[t / 4u][t % 4u];
+        if (weight != 0.0) {
+            position += deltas[t * count + in.index].xyz * weight;
+        }
+    }
+    let world = morph.model * vec4<f32>(position, 1.0);
+    out.pos = to_clip(world.xyz);
+    out.color = in.color;
+    if (morph.has_normals != 0u) {
+        var normal = deltas[2u * morph.target_count * count + in.index].xyz;
+        for (var t = 0u; t < morph.target_count; t++) {
+            let weight = morph.weights[t / 4u][t % 4u];
+            if (weight != 0.0) {
+                normal += deltas[(morph.target_count + t) * count + in.index].xyz * weight;
+            }
+        }
+        // 与 scene::model::mesh_shade 相同的明暗
+        let n = normalize((morph.model * vec4<f32>(normal, 0.0)).xyz);
+        let shade = 0.6 + 0.4 * abs(dot(n, normalize(SHADE_DIRECTION)));
+        out.color = vec4f(in.color.rgb * shade, in.color.a);
+    }
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
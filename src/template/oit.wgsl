@@ -0,0 +1,20 @@
+// 加权混合 OIT 的合成：把累积的半透明颜色按透过比例叠加到场景颜色上，管线使用 alpha 混合
+#include "fullscreen.wgsl"
+
+@group(0) @binding(0)
+var t_accum: texture_2d<f32>;
+@group(0) @binding(1)
+var t_reveal: texture_2d<f32>;
+
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let coord = vec2<i32>(in.pos.xy);
+    let reveal = textureLoad(t_reveal, coord, 0).r;
+    // 没有半透明表面覆盖的像素保持原样
+    if reveal >= 1.0 {
+        discard;
+    }
+    let accum = textureLoad(t_accum, coord, 0);
+    let color = accum.rgb / clamp(accum.a, 1e-4, 5e4);
+    return vec4f(color, 1.0 - reveal);
+}
@@ -0,0 +1,19 @@
+// 全屏后处理共用的绑定，通过 #include "post_common.wgsl" 引入
+// - t_input: 上一步的结果
+// - t_extra: 第二个输入（例如泛光），不需要时与 t_input 相同
+// - params: 每个效果自己解释的参数
+
+#include "fullscreen.wgsl"
+
+struct PostParams {
+    values: array<vec4f, 4>,
+}
+
+@group(0) @binding(0)
+var t_input: texture_2d<f32>;
+@group(0) @binding(1)
+var s_linear: sampler;
+@group(0) @binding(2)
+var<uniform> params: PostParams;
+@group(0) @binding(3)
+var t_extra: texture_2d<f32>;
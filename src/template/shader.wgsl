@@ -1,42 +1,78 @@
+// 场景网格和调试线段的着色器
+// - SKINNED: 按关节矩阵在 GPU 上蒙皮，关节矩阵位于 group 1
+// - OIT: 加权混合的顺序无关透明，输出累积颜色和透明度，由 oit.wgsl 合成
+#include "common.wgsl"
+
 struct VertexInput{
     @location(0) position: vec3f,
-    @location(1) color: vec3f,
+    @location(1) color: vec4f,
+#ifdef SKINNED
+    @location(3) joints: vec4<u32>,
+    @location(4) weights: vec4f,
+#endif
 }
 
 struct VertexOutput{
     @builtin(position) pos: vec4<f32>,
-    @location(0) color: vec3f,
+    @location(0) color: vec4f,
 }
 
-struct UniformBufferObject {
-    proj: mat4x4<f32>,
-    view: mat4x4<f32>,
-    model: mat4x4<f32>,
+#ifdef SKINNED
+// 与 anim::skeleton::MAX_JOINTS 一致
+#define MAX_JOINTS 64
+
+struct JointMatrices {
+    matrices: array<mat4x4<f32>, MAX_JOINTS>,
 }
 
-@group(0) @binding(0) 
-var<uniform> ubo: UniformBufferObject;
+@group(1) @binding(0)
+var<uniform> joints: JointMatrices;
 
-fn is_nan(val: f32) -> bool {
-    return val != val;
+fn joint_matrix(index: u32) -> mat4x4<f32> {
+    return joints.matrices[min(index, MAX_JOINTS - 1u)];
 }
+#endif
 
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
-    // 将位置信息从裁剪空间转换到屏幕空间
-    //  * ubo.view  * ubo.view * ubo.model
-    let mvp = ubo.proj;
-    let pos = mvp * vec4<f32>(in.position, 1.0);
-    out.pos = vec4<f32>(pos.xyz/pos.w, 1.0);
-    if(is_nan(out.pos.x) || is_nan(out.pos.y) || is_nan(out.pos.z)){
-        out.pos = vec4<f32>(0.0);
-    }
+#ifdef SKINNED
+    // 按权重混合关节矩阵，把模型空间的位置变换到世界坐标
+    let skin = joint_matrix(in.joints.x) * in.weights.x
+        + joint_matrix(in.joints.y) * in.weights.y
+        + joint_matrix(in.joints.z) * in.weights.z
+        + joint_matrix(in.joints.w) * in.weights.w;
+    let world = (skin * vec4<f32>(in.position, 1.0)).xyz;
+#else
+    let world = in.position;
+#endif
+    out.pos = to_clip(world);
     out.color = in.color;
     return out;
 }
 
+#ifdef OIT
+struct OitOutput {
+    @location(0) accum: vec4f,
+    @location(1) reveal: f32,
+}
+
+// 权重随 alpha 增大、随深度减小，近处不透明的表面在合成结果中占比更大（McGuire 2013）
+fn oit_weight(alpha: f32, depth: f32) -> f32 {
+    return clamp(pow(min(1.0, alpha * 10.0) + 0.01, 3.0) * 1e8 * pow(1.0 - depth * 0.9, 3.0), 1e-2, 3e3);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> OitOutput {
+    var out: OitOutput;
+    let weight = oit_weight(in.color.a, in.pos.z);
+    out.accum = vec4f(in.color.rgb * in.color.a, in.color.a) * weight;
+    out.reveal = in.color.a;
+    return out;
+}
+#else
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 0.0);
+    return in.color;
 }
+#endif
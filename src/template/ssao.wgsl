@@ -0,0 +1,115 @@
+// 屏幕空间环境光遮蔽：由深度重建世界坐标和法线，在法线半球内采样，检查采样点是否被场景挡住
+// 输出 r 为可见度（1 表示没有遮挡），g 为到相机的距离，供双边模糊使用
+#include "fullscreen.wgsl"
+
+// 与 post::MAX_SSAO_SAMPLES 一致
+#define MAX_SAMPLES 64
+
+struct SsaoUniform {
+    pvm: mat4x4<f32>,
+    inv_pvm: mat4x4<f32>,
+    eye: vec4f, // xyz 相机位置，w 采样半径
+    settings: vec4f, // x 深度偏移，y 采样数，z 强度，w 大于 0 时使用法线纹理
+    kernel: array<vec4f, MAX_SAMPLES>, // 切线空间 z 朝上的半球内的采样偏移
+}
+
+// 深度纹理按不可过滤的浮点纹理绑定，GL 后端不支持从 texture_depth_2d 读取原始深度
+@group(0) @binding(0)
+var t_depth: texture_2d<f32>;
+@group(0) @binding(1)
+var t_normal: texture_2d<f32>;
+@group(0) @binding(2)
+var<uniform> ssao: SsaoUniform;
+
+fn screen_size() -> vec2<i32> {
+    return vec2<i32>(textureDimensions(t_depth));
+}
+
+// 像素的世界坐标，没有绘制过的像素（深度为 1）和屏幕外的像素返回 w 为 0
+fn world_at(coord: vec2<i32>) -> vec4f {
+    let size = screen_size();
+    if any(coord < vec2<i32>(0)) || any(coord >= size) {
+        return vec4f(0.0);
+    }
+    let depth = textureLoad(t_depth, coord, 0).r;
+    if depth >= 1.0 {
+        return vec4f(0.0);
+    }
+    let uv = (vec2f(coord) + 0.5) / vec2f(size);
+    let ndc = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let world = ssao.inv_pvm * ndc;
+    return vec4f(world.xyz / world.w, 1.0);
+}
+
+// 由相邻像素的位置估算法线，每个方向取深度变化较小的一侧，避免物体边缘处的法线错误
+fn normal_at(coord: vec2<i32>, center: vec3f) -> vec3f {
+    if ssao.settings.w > 0.0 {
+        return normalize(textureLoad(t_normal, coord, 0).xyz);
+    }
+    let left = world_at(coord - vec2<i32>(1, 0));
+    let right = world_at(coord + vec2<i32>(1, 0));
+    let up = world_at(coord - vec2<i32>(0, 1));
+    let down = world_at(coord + vec2<i32>(0, 1));
+    var dx = right.xyz - center;
+    if right.w == 0.0 || (left.w > 0.0 && length(center - left.xyz) < length(dx)) {
+        dx = center - left.xyz;
+    }
+    var dy = down.xyz - center;
+    if down.w == 0.0 || (up.w > 0.0 && length(center - up.xyz) < length(dy)) {
+        dy = center - up.xyz;
+    }
+    var n = normalize(cross(dx, dy));
+    if dot(n, ssao.eye.xyz - center) < 0.0 {
+        n = -n;
+    }
+    return n;
+}
+
+// 交错梯度噪声，每个像素旋转采样核的角度不同，模糊后消除条纹
+fn noise(coord: vec2f) -> f32 {
+    return fract(52.9829189 * fract(dot(coord, vec2f(0.06711056, 0.00583715))));
+}
+
+@fragment
+fn fs_ssao(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let coord = vec2<i32>(in.pos.xy);
+    let center = world_at(coord);
+    if center.w == 0.0 {
+        return vec4f(1.0, 0.0, 0.0, 1.0);
+    }
+    let p = center.xyz;
+    let distance = length(ssao.eye.xyz - p);
+    let n = normal_at(coord, p);
+
+    let angle = noise(in.pos.xy) * 6.2831853;
+    var r = vec3f(cos(angle), sin(angle), 0.0);
+    if abs(dot(r, n)) > 0.9 {
+        r = vec3f(0.0, cos(angle), sin(angle));
+    }
+    let t = normalize(r - n * dot(r, n));
+    let tbn = mat3x3<f32>(t, cross(n, t), n);
+
+    let radius = ssao.eye.w;
+    let count = u32(clamp(ssao.settings.y, 1.0, f32(MAX_SAMPLES)));
+    let size = vec2f(screen_size());
+    var occlusion = 0.0;
+    for (var i = 0u; i < count; i++) {
+        let s = p + tbn * ssao.kernel[i].xyz * radius;
+        let clip = ssao.pvm * vec4f(s, 1.0);
+        let ndc = clip.xyz / clip.w;
+        if clip.w <= 0.0 || abs(ndc.x) > 1.0 || abs(ndc.y) > 1.0 {
+            continue;
+        }
+        let sample_coord = vec2<i32>(vec2f(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5) * size);
+        let scene = world_at(sample_coord);
+        if scene.w == 0.0 {
+            continue;
+        }
+        // 场景表面比采样点离相机更近时被遮挡，离得太远的表面不算
+        let occluded = length(ssao.eye.xyz - scene.xyz) < length(ssao.eye.xyz - s) - ssao.settings.x;
+        let range = smoothstep(0.0, 1.0, radius / max(length(scene.xyz - p), 1e-4));
+        occlusion += select(0.0, range, occluded);
+    }
+    let visibility = clamp(1.0 - ssao.settings.z * occlusion / f32(count), 0.0, 1.0);
+    return vec4f(visibility, distance, 0.0, 1.0);
+}
@@ -0,0 +1,36 @@
+// 环境光遮蔽的双边模糊和合成
+// fs_blur: t_input 为 ssao.wgsl 的结果，params.values[0].xy 为模糊方向（像素），z 为边缘锐度
+// fs_composite: t_input 为 HDR 场景，t_extra 为模糊后的遮蔽
+#include "post_common.wgsl"
+
+// 可分离的高斯核，中心加两侧各 4 个
+const BLUR_WEIGHTS = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+@fragment
+fn fs_blur(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let settings = params.values[0];
+    let step = settings.xy / vec2f(textureDimensions(t_input));
+    let center = textureSampleLevel(t_input, s_linear, in.uv, 0.0).rg;
+    if center.y == 0.0 {
+        return vec4f(center, 0.0, 1.0);
+    }
+    var sum = center.x * BLUR_WEIGHTS[0];
+    var total = BLUR_WEIGHTS[0];
+    for (var i = 1; i < 5; i++) {
+        for (var side = -1.0; side <= 1.0; side += 2.0) {
+            let s = textureSampleLevel(t_input, s_linear, in.uv + step * f32(i) * side, 0.0).rg;
+            // 距离相差越大权重越小，不把遮蔽模糊到另一个物体上
+            let w = BLUR_WEIGHTS[i] * exp(-abs(s.y - center.y) / center.y * settings.z) * select(1.0, 0.0, s.y == 0.0);
+            sum += s.x * w;
+            total += w;
+        }
+    }
+    return vec4f(sum / total, center.y, 0.0, 1.0);
+}
+
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_input, s_linear, in.uv);
+    let visibility = textureSample(t_extra, s_linear, in.uv).r;
+    return vec4<f32>(color.rgb * visibility, color.a);
+}
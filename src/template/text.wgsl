@@ -0,0 +1,41 @@
+struct VertexInput{
+    @location(0) position: vec2f,
+    @location(1) uv: vec2f,
+    @location(2) color: vec4f,
+}
+
+struct VertexOutput{
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2f,
+    @location(1) color: vec4f,
+}
+
+struct ScreenUniform {
+    size: vec2f,
+    _pad: vec2f,
+}
+
+@group(0) @binding(0)
+var<uniform> screen: ScreenUniform;
+@group(0) @binding(1)
+var atlas_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var atlas_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    // 屏幕像素坐标（左上角为原点）转换到裁剪空间
+    let x = in.position.x / screen.size.x * 2.0 - 1.0;
+    let y = 1.0 - in.position.y / screen.size.y * 2.0;
+    out.pos = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas_texture, atlas_sampler, in.uv).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
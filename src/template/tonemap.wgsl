@@ -0,0 +1,59 @@
+// 曝光和色调映射，把 HDR 颜色压缩到 [0, 1]
+// params.values[0]: x 曝光倍数，y 映射方式（0 不映射只截断，1 ACES，2 Reinhard，3 AgX），z 大于 0 时编码为 sRGB
+#include "post_common.wgsl"
+
+// Narkowicz 的 ACES 拟合曲线
+fn aces(x: vec3f) -> vec3f {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3f(0.0), vec3f(1.0));
+}
+
+fn reinhard(x: vec3f) -> vec3f {
+    return x / (1.0 + x);
+}
+
+// AgX 的多项式近似，对数空间的 S 曲线
+fn agx_contrast(x: vec3f) -> vec3f {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+fn agx(color: vec3f) -> vec3f {
+    let inset = mat3x3<f32>(
+        vec3f(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+        vec3f(0.0784335999999992, 0.878468636469772, 0.0784336),
+        vec3f(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+    );
+    let outset = mat3x3<f32>(
+        vec3f(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+        vec3f(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+        vec3f(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+    var x = inset * max(color, vec3f(1e-10));
+    x = clamp((log2(x) - min_ev) / (max_ev - min_ev), vec3f(0.0), vec3f(1.0));
+    x = agx_contrast(x);
+    x = outset * x;
+    // 曲线的结果是 gamma 2.2 编码的，转换回线性
+    return pow(max(x, vec3f(0.0)), vec3f(2.2));
+}
+
+@fragment
+fn fs_tonemap(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let settings = params.values[0];
+    let hdr = textureSample(t_input, s_linear, in.uv).rgb * settings.x;
+    let mode = u32(settings.y);
+    var color = clamp(hdr, vec3f(0.0), vec3f(1.0));
+    if mode == 1u {
+        color = aces(hdr);
+    } else if mode == 2u {
+        color = reinhard(hdr);
+    } else if mode == 3u {
+        color = agx(hdr);
+    }
+    if settings.z > 0.0 {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use super::font::Font;
+
+// 字形之间的间隔，避免采样时相互渗色
+const PADDING: u32 = 1;
+// 图集左上角保留的纯白区域，用于绘制纯色矩形
+const WHITE_SIZE: u32 = 2;
+
+/// 字形在图集中的位置和度量
+#[derive(Clone, Copy, Debug)]
+pub struct AtlasGlyph {
+  pub uv: [f32; 4], // u0, v0, u1, v1
+  pub width: f32,
+  pub height: f32,
+  pub xmin: f32, // 位图左边相对字符原点的偏移
+  pub ymin: f32, // 位图底边相对基线的偏移（向上为正）
+}
+
+/// 单通道字形图集，按行（shelf）依次摆放字形
+pub struct GlyphAtlas {
+  pub size: u32, // 图集宽高
+  pub pixels: Vec<u8>,
+  pub dirty: bool, // 像素有更新，需要重新上传纹理
+  glyphs: HashMap<(char, u32), AtlasGlyph>,
+  cursor_x: u32,
+  cursor_y: u32,
+  row_height: u32,
+}
+
+impl GlyphAtlas {
+  pub fn new(size: u32) -> Self {
+    let mut atlas = Self {
+      size,
+      pixels: vec![0; (size * size) as usize],
+      dirty: true,
+      glyphs: HashMap::new(),
+      cursor_x: PADDING,
+      cursor_y: PADDING,
+      row_height: 0,
+    };
+    atlas.reserve_white();
+    atlas
+  }
+
+  // 在左上角写入纯白区域，之后的字形从它右侧开始摆放
+  fn reserve_white(&mut self) {
+    for row in PADDING..PADDING + WHITE_SIZE {
+      let start = (row * self.size + PADDING) as usize;
+      self.pixels[start..start + WHITE_SIZE as usize].fill(255);
+    }
+    self.cursor_x = PADDING * 2 + WHITE_SIZE;
+    self.row_height = WHITE_SIZE;
+  }
+
+  /// 纯白区域中心的纹理坐标，采样结果恒为 1
+  pub fn white_uv(&self) -> [f32; 2] {
+    let c = (PADDING + WHITE_SIZE / 2) as f32 / self.size as f32;
+    [c, c]
+  }
+
+  /// 获取字形，不在图集中时光栅化并放入图集；图集已满返回 None
+  pub fn glyph(&mut self, font: &Font, ch: char, size: f32) -> Option<AtlasGlyph> {
+    // 字号按 1/4 像素取整作为缓存键
+    let key = (ch, (size * 4.0).round() as u32);
+    if let Some(glyph) = self.glyphs.get(&key) {
+      return Some(*glyph);
+    }
+
+    let (metrics, bitmap) = font.rasterize(ch, size);
+    let (w, h) = (metrics.width as u32, metrics.height as u32);
+    if w + PADDING * 2 > self.size || h + PADDING * 2 > self.size {
+      return None;
+    }
+    // 当前行放不下，换到下一行
+    if self.cursor_x + w + PADDING > self.size {
+      self.cursor_x = PADDING;
+      self.cursor_y += self.row_height + PADDING;
+      self.row_height = 0;
+    }
+    if self.cursor_y + h + PADDING > self.size {
+      return None;
+    }
+
+    for row in 0..h {
+      let src = (row * w) as usize;
+      let dst = ((self.cursor_y + row) * self.size + self.cursor_x) as usize;
+      self.pixels[dst..dst + w as usize].copy_from_slice(&bitmap[src..src + w as usize]);
+    }
+
+    let s = self.size as f32;
+    let glyph = AtlasGlyph {
+      uv: [
+        self.cursor_x as f32 / s,
+        self.cursor_y as f32 / s,
+        (self.cursor_x + w) as f32 / s,
+        (self.cursor_y + h) as f32 / s,
+      ],
+      width: w as f32,
+      height: h as f32,
+      xmin: metrics.xmin as f32,
+      ymin: metrics.ymin as f32,
+    };
+    self.cursor_x += w + PADDING;
+    self.row_height = self.row_height.max(h);
+    self.glyphs.insert(key, glyph);
+    self.dirty = true;
+    Some(glyph)
+  }
+
+  /// 清空图集
+  pub fn clear(&mut self) {
+    self.pixels.fill(0);
+    self.glyphs.clear();
+    self.cursor_y = PADDING;
+    self.reserve_white();
+    self.dirty = true;
+  }
+}
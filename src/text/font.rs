@@ -0,0 +1,145 @@
+use fontdue::{FontSettings, Metrics};
+
+/// 文字排版需要的字形度量，排版只依赖这个 trait，不需要 GPU 和真实字体也可以计算
+pub trait GlyphMetrics {
+  /// 字符的水平步进宽度（像素）
+  fn advance(&self, ch: char, size: f32) -> f32;
+  /// 两个相邻字符之间的字距调整（像素）
+  fn kern(&self, left: char, right: char, size: f32) -> f32;
+  /// 行度量：(上升高度, 下降高度(负数), 行间距)
+  fn line_metrics(&self, size: f32) -> (f32, f32, f32);
+}
+
+// Unifont 位图的尺寸：高 16 像素，基线在第 14 行下方
+const UNIFONT_HEIGHT: f32 = 16.0;
+const UNIFONT_ASCENT: f32 = 14.0;
+// 缩放位图时每个像素在每个方向上的采样数
+const UNIFONT_SAMPLES: usize = 4;
+
+enum FontData {
+  Outline(fontdue::Font), // TTF/OTF 矢量字体
+  Unifont, // 内置的 GNU Unifont 位图字体
+}
+
+/// 字体：TTF/OTF 文件，或者内置的 Unifont 位图字体
+pub struct Font {
+  inner: FontData,
+}
+
+impl Font {
+  pub fn from_bytes(data: Vec<u8>) -> Result<Self, String> {
+    let inner = fontdue::Font::from_bytes(data, FontSettings::default())?;
+    Ok(Self { inner: FontData::Outline(inner) })
+  }
+
+  pub fn from_file(path: &str) -> Result<Self, String> {
+    let data = std::fs::read(path).map_err(|e| format!("读取字体文件 {} 失败: {}", path, e))?;
+    Self::from_bytes(data)
+  }
+
+  /// 内置的 GNU Unifont 位图字体，覆盖 Unicode 基本多文种平面（包括中日韩文字），没有字体文件时使用
+  ///
+  /// 原始字形为 8x16（半角）或 16x16（全角）的单色位图，按字号缩放；16 像素及其整数倍时最清晰。
+  pub fn unifont() -> Self {
+    Self { inner: FontData::Unifont }
+  }
+
+  /// 光栅化一个字符，返回度量信息和单通道灰度位图
+  pub fn rasterize(&self, ch: char, size: f32) -> (Metrics, Vec<u8>) {
+    match &self.inner {
+      FontData::Outline(font) => font.rasterize(ch, size),
+      FontData::Unifont => rasterize_unifont(ch, size),
+    }
+  }
+}
+
+// 把 Unifont 位图缩放到字号 `size`，每个像素的灰度为覆盖率
+fn rasterize_unifont(ch: char, size: f32) -> (Metrics, Vec<u8>) {
+  let scale = size / UNIFONT_HEIGHT;
+  let Some(glyph) = unifont::get_glyph(ch).filter(|_| !ch.is_whitespace()) else {
+    return (Metrics { advance_width: unifont_advance(ch, size), ..Default::default() }, vec![])
+  };
+  let width = (glyph.get_width() as f32 * scale).round().max(1.0) as usize;
+  let height = size.round().max(1.0) as usize;
+  let mut bitmap = vec![0u8; width * height];
+  for (i, pixel) in bitmap.iter_mut().enumerate() {
+    let (px, py) = (i % width, i / width);
+    let mut covered = 0;
+    for sy in 0..UNIFONT_SAMPLES {
+      for sx in 0..UNIFONT_SAMPLES {
+        let x = (px as f32 + (sx as f32 + 0.5) / UNIFONT_SAMPLES as f32) / scale;
+        let y = (py as f32 + (sy as f32 + 0.5) / UNIFONT_SAMPLES as f32) / scale;
+        if glyph.get_pixel(x as usize, y as usize) {
+          covered += 1;
+        }
+      }
+    }
+    *pixel = (covered * 255 / (UNIFONT_SAMPLES * UNIFONT_SAMPLES)) as u8;
+  }
+  let metrics = Metrics {
+    xmin: 0,
+    ymin: -((UNIFONT_HEIGHT - UNIFONT_ASCENT) * scale).round() as i32,
+    width,
+    height,
+    advance_width: glyph.get_width() as f32 * scale,
+    ..Default::default()
+  };
+  (metrics, bitmap)
+}
+
+fn unifont_advance(ch: char, size: f32) -> f32 {
+  let width = unifont::get_glyph(ch).map(|g| g.get_width()).unwrap_or(8);
+  width as f32 * size / UNIFONT_HEIGHT
+}
+
+impl GlyphMetrics for Font {
+  fn advance(&self, ch: char, size: f32) -> f32 {
+    match &self.inner {
+      FontData::Outline(font) => font.metrics(ch, size).advance_width,
+      FontData::Unifont => unifont_advance(ch, size),
+    }
+  }
+
+  fn kern(&self, left: char, right: char, size: f32) -> f32 {
+    match &self.inner {
+      FontData::Outline(font) => font.horizontal_kern(left, right, size).unwrap_or(0.0),
+      FontData::Unifont => 0.0,
+    }
+  }
+
+  fn line_metrics(&self, size: f32) -> (f32, f32, f32) {
+    match &self.inner {
+      FontData::Outline(font) => match font.horizontal_line_metrics(size) {
+        Some(m) => (m.ascent, m.descent, m.line_gap),
+        None => (size * 0.8, -size * 0.2, 0.0),
+      },
+      FontData::Unifont => {
+        let scale = size / UNIFONT_HEIGHT;
+        (UNIFONT_ASCENT * scale, -(UNIFONT_HEIGHT - UNIFONT_ASCENT) * scale, 0.0)
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn unifont_covers_cjk() {
+    let font = Font::unifont();
+    assert_eq!(font.advance('中', 16.0), 16.0);
+    assert_eq!(font.advance('a', 16.0), 8.0);
+    assert_eq!(font.advance('中', 32.0), 32.0);
+    let (metrics, bitmap) = font.rasterize('中', 16.0);
+    assert_eq!((metrics.width, metrics.height, metrics.ymin), (16, 16, -2));
+    assert!(bitmap.contains(&255));
+    // 放大两倍时位图也放大两倍，覆盖的像素数为 4 倍
+    let (_, large) = font.rasterize('中', 32.0);
+    let count = |b: &[u8]| b.iter().filter(|&&p| p == 255).count();
+    assert_eq!(count(&large), count(&bitmap) * 4);
+    let (space, empty) = font.rasterize(' ', 16.0);
+    assert!(empty.is_empty() && space.advance_width == 8.0);
+    assert_eq!(font.line_metrics(16.0), (14.0, -2.0, 0.0));
+  }
+}
@@ -0,0 +1,265 @@
+use super::font::GlyphMetrics;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+  Left,
+  Center,
+  Right,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle {
+  pub size: f32, // 字号（像素）
+  pub align: TextAlign,
+  pub max_width: Option<f32>, // 最大行宽，超过后自动换行
+  pub line_height: f32, // 行高倍数
+}
+
+impl Default for TextStyle {
+  fn default() -> Self {
+    Self {
+      size: 16.0,
+      align: TextAlign::Left,
+      max_width: None,
+      line_height: 1.0,
+    }
+  }
+}
+
+/// 排版后的字符位置，坐标原点在文本框左上角，y 轴向下
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutGlyph {
+  pub ch: char,
+  pub x: f32, // 字符原点 x
+  pub y: f32, // 基线 y
+  pub line: usize, // 所在行
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TextLayout {
+  pub glyphs: Vec<LayoutGlyph>,
+  pub width: f32,
+  pub height: f32,
+  pub line_count: usize,
+}
+
+// 一行中的字符：(字符, x, 步进宽度)
+struct Line {
+  glyphs: Vec<(char, f32, f32)>,
+  break_at: Option<usize>, // 可以换行的位置（新行从这个下标开始）
+}
+
+impl Line {
+  fn new() -> Self {
+    Self { glyphs: vec![], break_at: None }
+  }
+
+  fn end_x(&self) -> f32 {
+    self.glyphs.last().map(|(_, x, adv)| x + adv).unwrap_or(0.0)
+  }
+
+  // 行宽不计算行尾空白
+  fn width(&self) -> f32 {
+    self.glyphs.iter().rev()
+      .find(|(ch, _, _)| !ch.is_whitespace())
+      .map(|(_, x, adv)| x + adv)
+      .unwrap_or(0.0)
+  }
+}
+
+/// 中日韩文字，每个字符之间都可以换行
+pub fn is_cjk(ch: char) -> bool {
+  matches!(ch as u32,
+    0x3000..=0x303F // 中日韩标点
+    | 0x3040..=0x30FF // 日文假名
+    | 0x3400..=0x4DBF // 扩展 A
+    | 0x4E00..=0x9FFF // 基本汉字
+    | 0xAC00..=0xD7AF // 韩文
+    | 0xF900..=0xFAFF // 兼容汉字
+    | 0xFF00..=0xFFEF // 全角字符
+    | 0x20000..=0x2A6DF // 扩展 B
+  )
+}
+
+/// 不能出现在行首的标点（避头尾）
+fn is_no_break_before(ch: char) -> bool {
+  matches!(ch,
+    '，' | '。' | '、' | '；' | '：' | '？' | '！' | '）' | '》' | '」' | '』' | '】' | '”' | '’' | '…'
+    | ',' | '.' | ';' | ':' | '!' | '?' | ')' | ']' | '}'
+  )
+}
+
+/// 对 UTF-8 字符串排版，支持 `\n` 强制换行、按 `max_width` 自动换行和对齐
+///
+/// 拉丁文字在空白处换行，单词超过行宽时按字符拆开；中日韩文字可以在任意字符间换行，但行首不会出现逗号、句号等标点。
+pub fn layout_text<M: GlyphMetrics>(metrics: &M, text: &str, style: &TextStyle) -> TextLayout {
+  let size = style.size;
+  let mut lines: Vec<Line> = vec![Line::new()];
+
+  let mut prev: Option<char> = None;
+  for ch in text.chars() {
+    if ch == '\r' {
+      continue
+    }
+    if ch == '\n' {
+      lines.push(Line::new());
+      prev = None;
+      continue
+    }
+
+    let wrapped = lines.len() > 1 && prev.is_some();
+    let line = lines.last_mut().unwrap();
+    // 自动换行后，新行开头的空白直接丢弃
+    if ch.is_whitespace() && line.glyphs.is_empty() && wrapped {
+      continue
+    }
+
+    let kern = prev.map(|p| metrics.kern(p, ch, size)).unwrap_or(0.0);
+    let advance = metrics.advance(ch, size);
+    let x = line.end_x() + kern;
+
+    if let Some(max_width) = style.max_width {
+      if x + advance > max_width && !line.glyphs.is_empty() && !ch.is_whitespace() && !is_no_break_before(ch) {
+        // 超出行宽：当前字符前可以换行时直接断开，否则在记录的换行位置断开，都没有时在当前字符前断开
+        let split = match line.break_at {
+          _ if can_break_before(prev, ch) => line.glyphs.len(),
+          Some(i) if i > 0 && i < line.glyphs.len() => i,
+          _ => line.glyphs.len(),
+        };
+        let rest: Vec<(char, f32, f32)> = line.glyphs.split_off(split);
+        let mut next = Line::new();
+        let offset = rest.first().map(|(_, x, _)| *x).unwrap_or(0.0);
+        for (c, gx, adv) in rest {
+          if next.glyphs.is_empty() && c.is_whitespace() {
+            continue
+          }
+          next.glyphs.push((c, gx - offset, adv));
+        }
+        lines.push(next);
+        let line = lines.last_mut().unwrap();
+        let x = line.end_x();
+        push_glyph(line, prev, ch, x, advance);
+        prev = Some(ch);
+        continue
+      }
+    }
+
+    push_glyph(line, prev, ch, x, advance);
+    prev = Some(ch);
+  }
+
+  let (ascent, descent, line_gap) = metrics.line_metrics(size);
+  let line_advance = (ascent - descent + line_gap) * style.line_height;
+  let width = lines.iter().map(|l| l.width()).fold(0.0, f32::max);
+  let box_width = style.max_width.unwrap_or(width);
+
+  let mut glyphs = vec![];
+  for (i, line) in lines.iter().enumerate() {
+    let offset = match style.align {
+      TextAlign::Left => 0.0,
+      TextAlign::Center => (box_width - line.width()) / 2.0,
+      TextAlign::Right => box_width - line.width(),
+    };
+    let baseline = ascent + i as f32 * line_advance;
+    for (ch, x, _) in line.glyphs.iter() {
+      glyphs.push(LayoutGlyph { ch: *ch, x: x + offset, y: baseline, line: i });
+    }
+  }
+
+  TextLayout {
+    glyphs,
+    width,
+    height: lines.len() as f32 * line_advance,
+    line_count: lines.len(),
+  }
+}
+
+// 中日韩文字前后都可以换行（避头标点除外）
+fn can_break_before(prev: Option<char>, ch: char) -> bool {
+  !is_no_break_before(ch) && (is_cjk(ch) || prev.is_some_and(is_cjk))
+}
+
+// 把字符加入行，并记录之后的换行位置
+fn push_glyph(line: &mut Line, prev: Option<char>, ch: char, x: f32, advance: f32) {
+  let index = line.glyphs.len();
+  if index > 0 && can_break_before(prev, ch) {
+    line.break_at = Some(index);
+  }
+  line.glyphs.push((ch, x, advance));
+  if ch.is_whitespace() {
+    line.break_at = Some(index + 1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 等宽度量：每个字符 10 像素，中日韩文字 20 像素，没有字距调整
+  struct Fixed;
+
+  impl GlyphMetrics for Fixed {
+    fn advance(&self, ch: char, _size: f32) -> f32 {
+      if is_cjk(ch) { 20.0 } else { 10.0 }
+    }
+
+    fn kern(&self, _left: char, _right: char, _size: f32) -> f32 {
+      0.0
+    }
+
+    fn line_metrics(&self, _size: f32) -> (f32, f32, f32) {
+      (12.0, -4.0, 0.0)
+    }
+  }
+
+  fn lines(text: &str, max_width: f32) -> Vec<String> {
+    let layout = layout_text(&Fixed, text, &TextStyle { max_width: Some(max_width), ..Default::default() });
+    let mut lines = vec![String::new(); layout.line_count];
+    for glyph in layout.glyphs {
+      lines[glyph.line].push(glyph.ch);
+    }
+    lines
+  }
+
+  #[test]
+  fn cjk_fills_each_line() {
+    // 每行正好放下 3 个字
+    assert_eq!(lines("一二三四五六七", 60.0), ["一二三", "四五六", "七"]);
+    assert_eq!(lines("一二三四", 65.0), ["一二三", "四"]);
+  }
+
+  #[test]
+  fn latin_wraps_at_spaces() {
+    assert_eq!(lines("hello world foo", 80.0), ["hello ", "world ", "foo"]);
+    // 超过行宽的单词按字符拆开
+    assert_eq!(lines("abcdefghij", 40.0), ["abcd", "efgh", "ij"]);
+  }
+
+  #[test]
+  fn mixed_cjk_and_latin() {
+    assert_eq!(lines("中文abc", 50.0), ["中文", "abc"]);
+    assert_eq!(lines("ab中文", 50.0), ["ab中", "文"]);
+  }
+
+  #[test]
+  fn punctuation_does_not_start_a_line() {
+    // 句号放不下时留在行尾，不移到下一行行首
+    assert_eq!(lines("一二三。四五", 60.0), ["一二三。", "四五"]);
+    assert_eq!(lines("一二，三四", 40.0), ["一二，", "三四"]);
+    assert_eq!(lines("word, next", 40.0), ["word, ", "next"]);
+    for line in lines("一，二。三、四！五？六", 40.0) {
+      assert!(!line.starts_with(is_no_break_before), "{}", line);
+    }
+  }
+
+  #[test]
+  fn explicit_newlines_and_alignment() {
+    let style = TextStyle { align: TextAlign::Right, max_width: Some(100.0), ..Default::default() };
+    let layout = layout_text(&Fixed, "ab\n一", &style);
+    assert_eq!(layout.line_count, 2);
+    assert_eq!(layout.glyphs[0].x, 80.0);
+    assert_eq!(layout.glyphs[2].x, 80.0);
+    assert_eq!(layout.glyphs[2].y, 12.0 + 16.0);
+    assert_eq!(layout.height, 32.0);
+  }
+}
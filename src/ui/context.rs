@@ -0,0 +1,409 @@
+use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::render::text::TextRenderer;
+use crate::text::layout::TextStyle;
+
+use super::panel::Panel;
+use super::widget::*;
+
+/// 控件位置：第几个面板的第几个控件
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WidgetRef {
+  pub panel: usize,
+  pub widget: usize,
+}
+
+/// 界面输入，由 `App::window_event` 从窗口事件转换而来
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UiInput {
+  MouseMove(f32, f32), // 鼠标位置（像素）
+  MouseDown,
+  MouseUp,
+  Char(char), // 输入的字符
+  Key(UiKey),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UiKey {
+  Backspace,
+  Enter,
+  Tab,
+  Escape,
+  Left,
+  Right,
+}
+
+/// 控件产生的事件，参数第一个是控件 id
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiEvent {
+  Clicked(String),
+  ValueChanged(String, f32),
+  Toggled(String, bool),
+  TextChanged(String, String),
+  TextSubmitted(String, String),
+  Selected(String, usize),
+  ColorChanged(String, [f32; 3]),
+}
+
+// 正在拖动的对象
+#[derive(Clone, Copy, Debug)]
+enum Drag {
+  Slider(WidgetRef),
+  Color(WidgetRef, usize), // 颜色选择器的第几个通道
+  Panel(usize),
+}
+
+/// 界面层，管理面板、焦点和输入分发
+///
+/// `handle_input` 返回 true 表示输入被界面消费，调用方不应再把它交给相机等场景逻辑。
+/// 控件产生的事件通过 `drain_events` 取出。
+pub struct Ui {
+  pub panels: Vec<Panel>,
+  pub visible: bool,
+  mouse: (f32, f32),
+  hover: Option<WidgetRef>,
+  focus: Option<WidgetRef>,
+  drag: Option<Drag>,
+  events: Vec<UiEvent>,
+}
+
+impl Default for Ui {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Ui {
+  pub fn new() -> Self {
+    Self {
+      panels: vec![],
+      visible: true,
+      mouse: (0.0, 0.0),
+      hover: None,
+      focus: None,
+      drag: None,
+      events: vec![],
+    }
+  }
+
+  pub fn add_panel(&mut self, panel: Panel) -> usize {
+    self.panels.push(panel);
+    self.panels.len() - 1
+  }
+
+  /// 有控件获得焦点时，键盘输入交给界面
+  pub fn wants_keyboard(&self) -> bool {
+    self.visible && self.focus.is_some()
+  }
+
+  /// 鼠标在界面上或正在拖动控件
+  pub fn wants_mouse(&self) -> bool {
+    self.visible && (self.drag.is_some() || self.is_over_ui(self.mouse.0, self.mouse.1))
+  }
+
+  /// 取出本帧产生的事件
+  pub fn drain_events(&mut self) -> Vec<UiEvent> {
+    std::mem::take(&mut self.events)
+  }
+
+  pub fn widget(&self, id: &str) -> Option<&Widget> {
+    self.panels.iter().flat_map(|p| p.widgets.iter()).find(|w| w.id == id)
+  }
+
+  pub fn widget_mut(&mut self, id: &str) -> Option<&mut Widget> {
+    self.panels.iter_mut().flat_map(|p| p.widgets.iter_mut()).find(|w| w.id == id)
+  }
+
+  fn get(&self, r: WidgetRef) -> &Widget {
+    &self.panels[r.panel].widgets[r.widget]
+  }
+
+  fn is_over_ui(&self, x: f32, y: f32) -> bool {
+    self.panels.iter().any(|p| {
+      p.visible && (p.rect.contains(x, y) || p.widgets.iter().any(|w| {
+        w.dropdown_options().map(|rects| rects.iter().any(|r| r.contains(x, y))).unwrap_or(false)
+      }))
+    })
+  }
+
+  // 鼠标下的控件，后添加的面板在上层
+  fn widget_at(&self, x: f32, y: f32) -> Option<WidgetRef> {
+    for (pi, panel) in self.panels.iter().enumerate().rev() {
+      if !panel.visible || !panel.rect.contains(x, y) {
+        continue
+      }
+      return panel.widgets.iter().position(|w| w.rect.contains(x, y)).map(|wi| WidgetRef { panel: pi, widget: wi });
+    }
+    None
+  }
+
+  pub fn handle_input(&mut self, input: UiInput) -> bool {
+    if !self.visible {
+      return false;
+    }
+    match input {
+      UiInput::MouseMove(x, y) => {
+        let (dx, dy) = (x - self.mouse.0, y - self.mouse.1);
+        self.mouse = (x, y);
+        self.hover = self.widget_at(x, y);
+        match self.drag {
+          Some(Drag::Slider(r)) => self.set_slider_from_mouse(r),
+          Some(Drag::Color(r, channel)) => self.set_color_from_mouse(r, channel),
+          Some(Drag::Panel(pi)) => self.panels[pi].move_by(dx, dy),
+          None => {},
+        }
+        self.drag.is_some() || self.is_over_ui(x, y)
+      },
+      UiInput::MouseDown => self.mouse_down(),
+      UiInput::MouseUp => {
+        let dragging = self.drag.take().is_some();
+        dragging || self.is_over_ui(self.mouse.0, self.mouse.1)
+      },
+      UiInput::Char(c) => {
+        let Some(r) = self.focus else {
+          return false
+        };
+        if c.is_control() {
+          return true;
+        }
+        let id = self.get(r).id.clone();
+        if let WidgetKind::TextInput { text, .. } = &mut self.panels[r.panel].widgets[r.widget].kind {
+          text.push(c);
+          let text = text.clone();
+          self.events.push(UiEvent::TextChanged(id, text));
+        }
+        true
+      },
+      UiInput::Key(key) => self.key(key),
+    }
+  }
+
+  /// 把窗口事件转换为界面输入，返回 true 表示被界面消费
+  pub fn handle_window_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::CursorMoved { position, .. } => {
+        self.handle_input(UiInput::MouseMove(position.x as f32, position.y as f32))
+      },
+      WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+        let input = if *state == ElementState::Pressed { UiInput::MouseDown } else { UiInput::MouseUp };
+        self.handle_input(input)
+      },
+      WindowEvent::KeyboardInput { event, .. } => {
+        // 控件获得焦点时，键盘输入只交给界面
+        if !self.wants_keyboard() {
+          return false;
+        }
+        if event.state == ElementState::Pressed {
+          let key = match event.physical_key {
+            PhysicalKey::Code(KeyCode::Backspace) => Some(UiKey::Backspace),
+            PhysicalKey::Code(KeyCode::Enter) => Some(UiKey::Enter),
+            PhysicalKey::Code(KeyCode::Tab) => Some(UiKey::Tab),
+            PhysicalKey::Code(KeyCode::Escape) => Some(UiKey::Escape),
+            PhysicalKey::Code(KeyCode::ArrowLeft) => Some(UiKey::Left),
+            PhysicalKey::Code(KeyCode::ArrowRight) => Some(UiKey::Right),
+            _ => None,
+          };
+          if let Some(key) = key {
+            self.handle_input(UiInput::Key(key));
+          } else if let Some(text) = event.text.as_ref() {
+            for c in text.chars() {
+              self.handle_input(UiInput::Char(c));
+            }
+          }
+        }
+        true
+      },
+      _ => false,
+    }
+  }
+
+  fn mouse_down(&mut self) -> bool {
+    let (x, y) = self.mouse;
+    // 先处理展开的下拉框选项，它们显示在最上层
+    for pi in 0..self.panels.len() {
+      for wi in 0..self.panels[pi].widgets.len() {
+        let r = WidgetRef { panel: pi, widget: wi };
+        let Some(rects) = self.get(r).dropdown_options() else {
+          continue
+        };
+        let id = self.get(r).id.clone();
+        let hit = rects.iter().position(|rect| rect.contains(x, y));
+        if let WidgetKind::Dropdown { selected, open, .. } = &mut self.panels[r.panel].widgets[r.widget].kind {
+          *open = false;
+          if let Some(i) = hit {
+            *selected = i;
+            self.events.push(UiEvent::Selected(id, i));
+            self.focus = Some(r);
+            return true;
+          }
+        }
+      }
+    }
+
+    let Some(pi) = self.panels.iter().rposition(|p| p.visible && p.rect.contains(x, y)) else {
+      self.focus = None;
+      return false
+    };
+    if self.panels[pi].title_rect().contains(x, y) {
+      self.drag = Some(Drag::Panel(pi));
+      return true;
+    }
+    let Some(r) = self.widget_at(x, y) else {
+      self.focus = None;
+      return true
+    };
+    self.focus = if self.get(r).focusable() { Some(r) } else { None };
+
+    let id = self.get(r).id.clone();
+    let rect = self.get(r).rect;
+    match &mut self.panels[r.panel].widgets[r.widget].kind {
+      WidgetKind::Button { .. } => self.events.push(UiEvent::Clicked(id)),
+      WidgetKind::Slider { .. } => {
+        self.drag = Some(Drag::Slider(r));
+        self.set_slider_from_mouse(r);
+      },
+      WidgetKind::Checkbox { checked, .. } => {
+        *checked = !*checked;
+        let checked = *checked;
+        self.events.push(UiEvent::Toggled(id, checked));
+      },
+      WidgetKind::Dropdown { open, .. } => *open = !*open,
+      WidgetKind::ColorPicker { .. } => {
+        let row = ((y - rect.y) / ROW_HEIGHT) as usize;
+        if row >= 1 {
+          self.drag = Some(Drag::Color(r, row - 1));
+          self.set_color_from_mouse(r, row - 1);
+        }
+      },
+      WidgetKind::Label { .. } | WidgetKind::TextInput { .. } => {},
+    }
+    true
+  }
+
+  fn key(&mut self, key: UiKey) -> bool {
+    let Some(r) = self.focus else {
+      return false
+    };
+    let id = self.get(r).id.clone();
+    match key {
+      UiKey::Tab => self.focus_next(),
+      UiKey::Escape => {
+        if let WidgetKind::Dropdown { open, .. } = &mut self.panels[r.panel].widgets[r.widget].kind {
+          *open = false;
+        }
+        self.focus = None;
+      },
+      UiKey::Enter => match &mut self.panels[r.panel].widgets[r.widget].kind {
+        WidgetKind::Button { .. } => self.events.push(UiEvent::Clicked(id)),
+        WidgetKind::Checkbox { checked, .. } => {
+          *checked = !*checked;
+          let checked = *checked;
+          self.events.push(UiEvent::Toggled(id, checked));
+        },
+        WidgetKind::TextInput { text, .. } => {
+          let text = text.clone();
+          self.events.push(UiEvent::TextSubmitted(id, text));
+          self.focus = None;
+        },
+        WidgetKind::Dropdown { open, .. } => *open = !*open,
+        _ => {},
+      },
+      UiKey::Backspace => {
+        if let WidgetKind::TextInput { text, .. } = &mut self.panels[r.panel].widgets[r.widget].kind {
+          if text.pop().is_some() {
+            let text = text.clone();
+            self.events.push(UiEvent::TextChanged(id, text));
+          }
+        }
+      },
+      UiKey::Left | UiKey::Right => {
+        let dir = if key == UiKey::Left { -1.0 } else { 1.0 };
+        match &mut self.panels[r.panel].widgets[r.widget].kind {
+          WidgetKind::Slider { value, min, max, .. } => {
+            *value = (*value + dir * (*max - *min) / 100.0).clamp(*min, *max);
+            let value = *value;
+            self.events.push(UiEvent::ValueChanged(id, value));
+          },
+          WidgetKind::Dropdown { options, selected, .. } => {
+            let next = (*selected as i32 + dir as i32).clamp(0, options.len() as i32 - 1) as usize;
+            if next != *selected {
+              *selected = next;
+              self.events.push(UiEvent::Selected(id, next));
+            }
+          },
+          _ => {},
+        }
+      },
+    }
+    true
+  }
+
+  // 焦点移动到下一个可获得焦点的控件
+  fn focus_next(&mut self) {
+    let all: Vec<WidgetRef> = self.panels.iter().enumerate()
+      .filter(|(_, p)| p.visible)
+      .flat_map(|(pi, p)| p.widgets.iter().enumerate().filter(|(_, w)| w.focusable()).map(move |(wi, _)| WidgetRef { panel: pi, widget: wi }))
+      .collect();
+    if all.is_empty() {
+      self.focus = None;
+      return
+    }
+    let next = self.focus.and_then(|f| all.iter().position(|r| *r == f)).map(|i| (i + 1) % all.len()).unwrap_or(0);
+    self.focus = Some(all[next]);
+  }
+
+  fn set_slider_from_mouse(&mut self, r: WidgetRef) {
+    let field = self.get(r).rect.field_part();
+    let t = ((self.mouse.0 - field.x) / field.w).clamp(0.0, 1.0);
+    let id = self.get(r).id.clone();
+    if let WidgetKind::Slider { value, min, max, .. } = &mut self.panels[r.panel].widgets[r.widget].kind {
+      let v = *min + (*max - *min) * t;
+      if v != *value {
+        *value = v;
+        self.events.push(UiEvent::ValueChanged(id, v));
+      }
+    }
+  }
+
+  fn set_color_from_mouse(&mut self, r: WidgetRef, channel: usize) {
+    let field = self.get(r).rect.row(channel + 1).field_part();
+    let t = ((self.mouse.0 - field.x) / field.w).clamp(0.0, 1.0);
+    let id = self.get(r).id.clone();
+    if let WidgetKind::ColorPicker { color, .. } = &mut self.panels[r.panel].widgets[r.widget].kind {
+      if color[channel] != t {
+        color[channel] = t;
+        let color = *color;
+        self.events.push(UiEvent::ColorChanged(id, color));
+      }
+    }
+  }
+
+  /// 把界面绘制到文字渲染器（矩形和文字）
+  pub fn draw(&self, renderer: &mut TextRenderer) {
+    if !self.visible {
+      return
+    }
+    let title_style = TextStyle { size: FONT_SIZE, ..Default::default() };
+    for (pi, panel) in self.panels.iter().enumerate() {
+      if !panel.visible {
+        continue
+      }
+      let r = panel.rect;
+      renderer.queue_rect(r.x, r.y, r.w, r.h, COLOR_PANEL);
+      let t = panel.title_rect();
+      renderer.queue_rect(t.x, t.y, t.w, t.h, COLOR_TITLE);
+      renderer.queue_text(&panel.title, t.x + PADDING, t.y + (ROW_HEIGHT - FONT_SIZE * 1.2) / 2.0, &title_style, COLOR_TEXT);
+      for (wi, widget) in panel.widgets.iter().enumerate() {
+        let wr = Some(WidgetRef { panel: pi, widget: wi });
+        widget.draw(renderer, self.hover == wr, self.focus == wr);
+      }
+    }
+    // 下拉选项最后绘制，覆盖在其他控件上
+    for panel in self.panels.iter().filter(|p| p.visible) {
+      for widget in panel.widgets.iter() {
+        widget.draw_popup(renderer, self.mouse);
+      }
+    }
+  }
+}
@@ -0,0 +1,3 @@
+pub mod widget;
+pub mod panel;
+pub mod context;
@@ -0,0 +1,87 @@
+use super::widget::*;
+
+/// 面板，控件从上到下依次排列
+///
+/// 通过链式调用添加控件：
+/// `Panel::new("操作", 10.0, 40.0, 260.0).slider("x", "X", 0.0, -100.0, 100.0).button("reset", "重置")`
+pub struct Panel {
+  pub title: String,
+  pub rect: Rect, // 高度在布局时根据控件数量计算
+  pub widgets: Vec<Widget>,
+  pub visible: bool,
+}
+
+impl Panel {
+  pub fn new(title: &str, x: f32, y: f32, width: f32) -> Self {
+    let mut panel = Self {
+      title: title.to_string(),
+      rect: Rect::new(x, y, width, 0.0),
+      widgets: vec![],
+      visible: true,
+    };
+    panel.layout();
+    panel
+  }
+
+  /// 添加任意控件，其它方法都通过它添加
+  pub fn with_widget(mut self, widget: Widget) -> Self {
+    self.widgets.push(widget);
+    self.layout();
+    self
+  }
+
+  pub fn label(self, id: &str, text: &str) -> Self {
+    self.with_widget(Widget::new(id, WidgetKind::Label { text: text.to_string() }))
+  }
+
+  pub fn button(self, id: &str, label: &str) -> Self {
+    self.with_widget(Widget::new(id, WidgetKind::Button { label: label.to_string() }))
+  }
+
+  pub fn slider(self, id: &str, label: &str, value: f32, min: f32, max: f32) -> Self {
+    self.with_widget(Widget::new(id, WidgetKind::Slider { label: label.to_string(), value: value.clamp(min, max), min, max }))
+  }
+
+  pub fn checkbox(self, id: &str, label: &str, checked: bool) -> Self {
+    self.with_widget(Widget::new(id, WidgetKind::Checkbox { label: label.to_string(), checked }))
+  }
+
+  pub fn text_input(self, id: &str, label: &str, text: &str) -> Self {
+    self.with_widget(Widget::new(id, WidgetKind::TextInput { label: label.to_string(), text: text.to_string() }))
+  }
+
+  pub fn dropdown(self, id: &str, label: &str, options: &[&str], selected: usize) -> Self {
+    let options: Vec<String> = options.iter().map(|s| s.to_string()).collect();
+    let selected = selected.min(options.len().saturating_sub(1));
+    self.with_widget(Widget::new(id, WidgetKind::Dropdown { label: label.to_string(), options, selected, open: false }))
+  }
+
+  pub fn color_picker(self, id: &str, label: &str, color: [f32; 3]) -> Self {
+    self.with_widget(Widget::new(id, WidgetKind::ColorPicker { label: label.to_string(), color }))
+  }
+
+  /// 标题栏区域
+  pub fn title_rect(&self) -> Rect {
+    Rect::new(self.rect.x, self.rect.y, self.rect.w, ROW_HEIGHT)
+  }
+
+  /// 重新计算控件位置和面板高度
+  pub fn layout(&mut self) {
+    let x = self.rect.x + PADDING;
+    let w = self.rect.w - PADDING * 2.0;
+    let mut y = self.rect.y + ROW_HEIGHT + PADDING;
+    for widget in self.widgets.iter_mut() {
+      let h = widget.rows() as f32 * ROW_HEIGHT;
+      widget.rect = Rect::new(x, y, w, h);
+      y += h;
+    }
+    self.rect.h = y + PADDING - self.rect.y;
+  }
+
+  /// 移动面板（拖动标题栏）
+  pub fn move_by(&mut self, dx: f32, dy: f32) {
+    self.rect.x += dx;
+    self.rect.y += dy;
+    self.layout();
+  }
+}
@@ -0,0 +1,175 @@
+use crate::render::text::TextRenderer;
+use crate::text::layout::TextStyle;
+
+// 布局参数（像素）
+pub const ROW_HEIGHT: f32 = 28.0;
+pub const PADDING: f32 = 8.0;
+pub const FONT_SIZE: f32 = 14.0;
+// 标签占一行宽度的比例，剩余部分是输入区域
+const LABEL_RATIO: f32 = 0.4;
+
+// 配色
+pub const COLOR_PANEL: [f32; 4] = [0.12, 0.12, 0.15, 0.9];
+pub const COLOR_TITLE: [f32; 4] = [0.22, 0.28, 0.38, 1.0];
+pub const COLOR_FIELD: [f32; 4] = [0.25, 0.25, 0.3, 1.0];
+pub const COLOR_HOVER: [f32; 4] = [0.32, 0.32, 0.4, 1.0];
+pub const COLOR_ACCENT: [f32; 4] = [0.3, 0.6, 0.9, 1.0];
+pub const COLOR_TEXT: [f32; 4] = [0.95, 0.95, 0.95, 1.0];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+  pub x: f32,
+  pub y: f32,
+  pub w: f32,
+  pub h: f32,
+}
+
+impl Rect {
+  pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+    Self { x, y, w, h }
+  }
+
+  pub fn contains(&self, px: f32, py: f32) -> bool {
+    px >= self.x && px < self.x + self.w && py >= self.y && py < self.y + self.h
+  }
+
+  // 右侧输入区域
+  pub fn field_part(&self) -> Rect {
+    Rect::new(self.x + self.w * LABEL_RATIO, self.y + 2.0, self.w * (1.0 - LABEL_RATIO), self.h - 4.0)
+  }
+
+  // 第 i 行（从 0 开始，每行 ROW_HEIGHT）
+  pub fn row(&self, i: usize) -> Rect {
+    Rect::new(self.x, self.y + i as f32 * ROW_HEIGHT, self.w, ROW_HEIGHT)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WidgetKind {
+  Label { text: String },
+  Button { label: String },
+  Slider { label: String, value: f32, min: f32, max: f32 },
+  Checkbox { label: String, checked: bool },
+  TextInput { label: String, text: String },
+  Dropdown { label: String, options: Vec<String>, selected: usize, open: bool },
+  ColorPicker { label: String, color: [f32; 3] },
+}
+
+/// 控件，`rect` 在面板布局时计算
+#[derive(Clone, Debug)]
+pub struct Widget {
+  pub id: String,
+  pub kind: WidgetKind,
+  pub rect: Rect,
+}
+
+impl Widget {
+  pub fn new(id: &str, kind: WidgetKind) -> Self {
+    Self { id: id.to_string(), kind, rect: Rect::default() }
+  }
+
+  /// 占用的行数
+  pub fn rows(&self) -> usize {
+    match self.kind {
+      WidgetKind::ColorPicker { .. } => 4, // 标题行 + R、G、B 三个滑块
+      _ => 1,
+    }
+  }
+
+  /// 是否可以获得焦点（Tab 切换）
+  pub fn focusable(&self) -> bool {
+    !matches!(self.kind, WidgetKind::Label { .. })
+  }
+
+  /// 下拉框展开后选项所在区域，未展开时为 None
+  pub fn dropdown_options(&self) -> Option<Vec<Rect>> {
+    match &self.kind {
+      WidgetKind::Dropdown { options, open: true, .. } => {
+        let field = self.rect.field_part();
+        Some((0..options.len()).map(|i| Rect::new(field.x, self.rect.y + (i + 1) as f32 * ROW_HEIGHT, field.w, ROW_HEIGHT)).collect())
+      },
+      _ => None,
+    }
+  }
+
+  /// 绘制控件
+  /// - `hover`: 鼠标是否悬停
+  /// - `focused`: 是否获得焦点
+  pub fn draw(&self, renderer: &mut TextRenderer, hover: bool, focused: bool) {
+    let style = TextStyle { size: FONT_SIZE, ..Default::default() };
+    let r = self.rect;
+    let text_y = r.y + (ROW_HEIGHT - FONT_SIZE * 1.2) / 2.0;
+    let field_color = if hover { COLOR_HOVER } else { COLOR_FIELD };
+    match &self.kind {
+      WidgetKind::Label { text } => {
+        renderer.queue_text(text, r.x, text_y, &style, COLOR_TEXT);
+      },
+      WidgetKind::Button { label } => {
+        let b = Rect::new(r.x, r.y + 2.0, r.w, r.h - 4.0);
+        renderer.queue_rect(b.x, b.y, b.w, b.h, if focused { COLOR_ACCENT } else { field_color });
+        let centered = TextStyle { max_width: Some(r.w), align: crate::text::layout::TextAlign::Center, ..style };
+        renderer.queue_text(label, r.x, text_y, &centered, COLOR_TEXT);
+      },
+      WidgetKind::Slider { label, value, min, max } => {
+        renderer.queue_text(&format!("{} {:.1}", label, value), r.x, text_y, &style, COLOR_TEXT);
+        let f = r.field_part();
+        let t = if max > min { (value - min) / (max - min) } else { 0.0 };
+        renderer.queue_rect(f.x, f.y + f.h * 0.4, f.w, f.h * 0.2, field_color);
+        renderer.queue_rect(f.x, f.y + f.h * 0.4, f.w * t, f.h * 0.2, COLOR_ACCENT);
+        renderer.queue_rect(f.x + f.w * t - 3.0, f.y, 6.0, f.h, if focused { COLOR_ACCENT } else { COLOR_TEXT });
+      },
+      WidgetKind::Checkbox { label, checked } => {
+        let size = ROW_HEIGHT - 10.0;
+        renderer.queue_rect(r.x, r.y + 5.0, size, size, if focused { COLOR_ACCENT } else { field_color });
+        if *checked {
+          renderer.queue_rect(r.x + 4.0, r.y + 9.0, size - 8.0, size - 8.0, COLOR_TEXT);
+        }
+        renderer.queue_text(label, r.x + size + PADDING, text_y, &style, COLOR_TEXT);
+      },
+      WidgetKind::TextInput { label, text } => {
+        renderer.queue_text(label, r.x, text_y, &style, COLOR_TEXT);
+        let f = r.field_part();
+        renderer.queue_rect(f.x, f.y, f.w, f.h, if focused { COLOR_HOVER } else { field_color });
+        let shown = if focused { format!("{}|", text) } else { text.clone() };
+        renderer.queue_text(&shown, f.x + 4.0, text_y, &style, COLOR_TEXT);
+      },
+      WidgetKind::Dropdown { label, options, selected, .. } => {
+        renderer.queue_text(label, r.x, text_y, &style, COLOR_TEXT);
+        let f = r.field_part();
+        renderer.queue_rect(f.x, f.y, f.w, f.h, if focused { COLOR_HOVER } else { field_color });
+        let current = options.get(*selected).map(|s| s.as_str()).unwrap_or("");
+        renderer.queue_text(&format!("{} ▼", current), f.x + 4.0, text_y, &style, COLOR_TEXT);
+      },
+      WidgetKind::ColorPicker { label, color } => {
+        renderer.queue_text(label, r.x, text_y, &style, COLOR_TEXT);
+        let f = r.row(0).field_part();
+        renderer.queue_rect(f.x, f.y, f.w, f.h, [color[0], color[1], color[2], 1.0]);
+        let names = ["R", "G", "B"];
+        for (i, name) in names.iter().enumerate() {
+          let row = r.row(i + 1);
+          let row_text_y = row.y + (ROW_HEIGHT - FONT_SIZE * 1.2) / 2.0;
+          renderer.queue_text(&format!("{} {:.2}", name, color[i]), row.x + PADDING, row_text_y, &style, COLOR_TEXT);
+          let f = row.field_part();
+          let mut channel = [0.0, 0.0, 0.0, 1.0];
+          channel[i] = 1.0;
+          renderer.queue_rect(f.x, f.y + f.h * 0.4, f.w, f.h * 0.2, field_color);
+          renderer.queue_rect(f.x, f.y + f.h * 0.4, f.w * color[i], f.h * 0.2, channel);
+          renderer.queue_rect(f.x + f.w * color[i] - 3.0, f.y, 6.0, f.h, COLOR_TEXT);
+        }
+      },
+    }
+  }
+
+  /// 绘制下拉框展开的选项，需要在所有控件之后绘制以显示在最上层
+  pub fn draw_popup(&self, renderer: &mut TextRenderer, mouse: (f32, f32)) {
+    let (WidgetKind::Dropdown { options, selected, .. }, Some(rects)) = (&self.kind, self.dropdown_options()) else {
+      return
+    };
+    let style = TextStyle { size: FONT_SIZE, ..Default::default() };
+    for (i, rect) in rects.iter().enumerate() {
+      let color = if rect.contains(mouse.0, mouse.1) || i == *selected { COLOR_HOVER } else { COLOR_FIELD };
+      renderer.queue_rect(rect.x, rect.y, rect.w, rect.h, color);
+      renderer.queue_text(&options[i], rect.x + 4.0, rect.y + (ROW_HEIGHT - FONT_SIZE * 1.2) / 2.0, &style, COLOR_TEXT);
+    }
+  }
+}
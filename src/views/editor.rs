@@ -0,0 +1,234 @@
+use winit::event::{ElementState, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::constants::{HOME_SCENE_PATH, USER_HOME_SCENE_PATH};
+use crate::render::{vertex::Vertex, wgpu_ctx::WgpuCtx};
+use crate::scene::{file::save_scene, history::{Command, History, Property}, model::Scene};
+use crate::ui::{context::{Ui, UiEvent}, panel::Panel};
+
+use super::home::home_scene;
+use super::view::{enter_scene, View};
+
+/// 编辑器：选择场景中的元素并修改位置、缩放、旋转和材质颜色，Tab 切换选中元素
+///
+/// 所有修改都通过 `History` 执行，Ctrl+Z 撤销，Ctrl+Y 或 Ctrl+Shift+Z 重做。
+#[derive(Default)]
+pub struct EditorView {
+  scene: Option<Scene>,
+  selected: usize, // 选中元素的下标
+  ui: Ui,
+  history: History,
+  ctrl: bool, // Ctrl 是否按下
+  shift: bool, // Shift 是否按下
+}
+
+impl EditorView {
+  // 按当前选中的元素重建面板，保持面板位置不变
+  fn rebuild_panel(&mut self) {
+    let Some(scene) = self.scene.as_ref() else {
+      return
+    };
+    let mut panel = Panel::new("编辑器", 10.0, 40.0, 300.0);
+    if let Some(el) = scene.elements.get(self.selected) {
+      let [x, y, z] = el.transform.position;
+      let color = scene.material(&el.material).map(|m| m.color).unwrap_or([1.0, 1.0, 1.0]);
+      panel = panel
+        .label("id", &format!("元素 #{}", el.id))
+        .text_input("name", "名称", &el.name)
+        .slider("pos_x", "X", x, x - 500.0, x + 500.0)
+        .slider("pos_y", "Y", y, y - 500.0, y + 500.0)
+        .slider("pos_z", "Z", z, z - 500.0, z + 500.0)
+        .slider("scale", "缩放", el.transform.scale[0], 0.1, 5.0)
+        .slider("rot_y", "旋转 Y", el.transform.rotation[1], -180.0, 180.0)
+        .color_picker("color", &format!("材质 {}", el.material), color);
+    } else {
+      panel = panel.label("name", "场景中没有元素");
+    }
+    panel = panel
+      .button("prev", "上一个")
+      .button("next", "下一个")
+      .button("duplicate", "复制元素")
+      .button("remove", "删除元素")
+      .button("undo", "撤销")
+      .button("redo", "重做")
+      .button("save", "保存");
+    if let Some(old) = self.ui.panels.first() {
+      panel.move_by(old.rect.x - panel.rect.x, old.rect.y - panel.rect.y);
+    }
+    self.ui = Ui::new();
+    self.ui.add_panel(panel);
+  }
+
+  fn select(&mut self, offset: isize) {
+    let count = self.scene.as_ref().map(|s| s.elements.len()).unwrap_or(0);
+    if count == 0 {
+      return
+    }
+    self.selected = (self.selected as isize + offset).rem_euclid(count as isize) as usize;
+    self.rebuild_panel();
+  }
+
+  // 通过历史记录执行修改
+  fn execute(&mut self, command: Option<Command>) {
+    if let (Some(scene), Some(command)) = (self.scene.as_mut(), command) {
+      self.history.execute(scene, command);
+    }
+  }
+
+  // 撤销或重做后选中元素可能已不存在，需要修正下标并刷新面板
+  fn undo(&mut self, redo: bool) {
+    let Some(scene) = self.scene.as_mut() else {
+      return
+    };
+    let changed = if redo { self.history.redo(scene) } else { self.history.undo(scene) };
+    if changed {
+      self.selected = self.selected.min(scene.elements.len().saturating_sub(1));
+      self.rebuild_panel();
+    }
+  }
+
+  // 复制选中的元素，放在它的右侧
+  fn duplicate(&mut self) {
+    let Some(scene) = self.scene.as_ref() else {
+      return
+    };
+    let Some(el) = scene.elements.get(self.selected) else {
+      return
+    };
+    let mut element = el.clone();
+    element.id = scene.next_id();
+    element.transform.position[0] += el.bounds().1.x - el.bounds().0.x;
+    let index = self.selected + 1;
+    self.history.seal();
+    self.execute(Some(Command::AddElement { index, element }));
+    self.history.seal();
+    self.selected = index;
+    self.rebuild_panel();
+  }
+
+  fn remove(&mut self) {
+    let command = self.scene.as_ref().and_then(|s| Command::remove_element(s, self.selected));
+    self.history.seal();
+    self.execute(command);
+    self.history.seal();
+    let count = self.scene.as_ref().map(|s| s.elements.len()).unwrap_or(0);
+    self.selected = self.selected.min(count.saturating_sub(1));
+    self.rebuild_panel();
+  }
+}
+
+impl View for EditorView {
+  fn name(&self) -> &str {
+    "editor"
+  }
+
+  fn on_enter(&mut self, ctx: &mut WgpuCtx) {
+    self.scene = Some(enter_scene(ctx, &[USER_HOME_SCENE_PATH, HOME_SCENE_PATH], home_scene));
+    self.selected = 0;
+    self.history.clear();
+    self.rebuild_panel();
+  }
+
+  fn update(&mut self, _ctx: &mut WgpuCtx, _dt: f32) {
+    for event in self.ui.drain_events() {
+      match event {
+        UiEvent::Clicked(id) => match id.as_str() {
+          "prev" => self.select(-1),
+          "next" => self.select(1),
+          "duplicate" => self.duplicate(),
+          "remove" => self.remove(),
+          "undo" => self.undo(false),
+          "redo" => self.undo(true),
+          "save" => {
+            if let Some(scene) = self.scene.as_ref() {
+              match save_scene(scene, USER_HOME_SCENE_PATH) {
+                Ok(()) => println!("场景已保存到 {}", USER_HOME_SCENE_PATH),
+                Err(e) => println!("{}", e),
+              }
+            }
+          },
+          _ => {},
+        },
+        UiEvent::ValueChanged(id, v) => {
+          let Some(el) = self.scene.as_ref().and_then(|s| s.elements.get(self.selected)) else {
+            continue
+          };
+          let mut transform = el.transform;
+          match id.as_str() {
+            "pos_x" => transform.position[0] = v,
+            "pos_y" => transform.position[1] = v,
+            "pos_z" => transform.position[2] = v,
+            "scale" => transform.scale = [v, v, v],
+            "rot_y" => transform.rotation[1] = v,
+            _ => continue,
+          }
+          let command = self.scene.as_ref().and_then(|s| Command::set_transform(s, el.id, transform));
+          self.execute(command);
+        },
+        UiEvent::TextSubmitted(id, text) if id == "name" => {
+          let command = self.scene.as_ref().and_then(|s| {
+            s.elements.get(self.selected).and_then(|el| Command::set_property(s, el.id, Property::Name(text)))
+          });
+          self.history.seal();
+          self.execute(command);
+          self.history.seal();
+        },
+        UiEvent::ColorChanged(id, color) if id == "color" => {
+          // 修改的是材质，使用同一材质的元素都会变化
+          let Some(scene) = self.scene.as_ref() else {
+            continue
+          };
+          let command = scene.elements.get(self.selected).and_then(|el| Command::set_material_color(scene, &el.material, color));
+          self.execute(command);
+        },
+        _ => {},
+      }
+    }
+  }
+
+  fn handle_input(&mut self, _ctx: &mut WgpuCtx, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::ModifiersChanged(modifiers) => {
+        self.ctrl = modifiers.state().control_key();
+        self.shift = modifiers.state().shift_key();
+        return false;
+      },
+      // 松开鼠标结束拖动，之后的修改单独记录
+      WindowEvent::MouseInput { state: ElementState::Released, .. } => self.history.seal(),
+      _ => {},
+    }
+    if self.ui.handle_window_event(event) {
+      return true;
+    }
+    let WindowEvent::KeyboardInput { event, .. } = event else {
+      return false
+    };
+    if event.state != ElementState::Pressed {
+      return false;
+    }
+    match event.physical_key {
+      PhysicalKey::Code(KeyCode::Tab) => self.select(1),
+      PhysicalKey::Code(KeyCode::KeyZ) if self.ctrl => self.undo(self.shift),
+      PhysicalKey::Code(KeyCode::KeyY) if self.ctrl => self.undo(true),
+      _ => return false,
+    }
+    true
+  }
+
+  fn build_render_list(&mut self, ctx: &mut WgpuCtx) -> Vec<Vertex> {
+    let Some(scene) = self.scene.as_ref() else {
+      return vec![]
+    };
+    if let Some(el) = scene.elements.get(self.selected) {
+      let (min, max) = el.bounds();
+      ctx.debug_draw.aabb(min, max, [1.0, 1.0, 0.0]);
+    }
+    self.ui.visible = ctx.text_renderer.is_some();
+    if let Some(text_renderer) = ctx.text_renderer.as_mut() {
+      self.ui.draw(text_renderer);
+    }
+    let (vertex_list, batches) = scene.render_list();
+    ctx.batches.extend(batches);
+    vertex_list
+  }
+}
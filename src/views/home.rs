@@ -1,37 +1,150 @@
-use crate::{element::{action::move_el, cube::Cube}, render::vertex::Vertex};
+use winit::event::{ElementState, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
 
+use crate::{render::{vertex::Vertex, wgpu_ctx::WgpuCtx}, scene::model::{CameraDesc, Element, Light, LightKind, Material, Scene, Shape, Transform}};
+use crate::constants::{HOME_SCENE_PATH, USER_HOME_SCENE_PATH};
+use crate::render::draw::RenderBatch;
+use crate::physics::body::BodyType;
+use crate::physics::world::{static_colliders, update_colliders, PhysicsWorld};
+use crate::scene::file::save_scene;
 
-// struct Home
+use super::operation::{draw_operation_panel, OperationPanel};
+use super::view::{enter_scene, View};
 
-pub fn draw_home() -> Vec<Vertex>{
-  // 绘制多个立方体
+/// 首页：展示场景并提供操作面板，F5 重新加载场景文件，F6 保存场景，P 开关物理模拟，L 开关线框模式
+#[derive(Default)]
+pub struct HomeView {
+  scene: Option<Scene>,
+  operation_panel: OperationPanel,
+  physics: Option<PhysicsWorld>, // 开启物理模拟时元素受重力下落并相互碰撞
+  dynamic: Vec<u32>, // 物理模拟移动的元素，每帧只更新它们的碰撞体
+  wireframe: bool, // 场景元素按线框绘制
+}
+
+impl HomeView {
+  // 加载场景文件，物理模拟回到关闭状态
+  fn load(&mut self, ctx: &mut WgpuCtx) {
+    self.scene = Some(enter_scene(ctx, &[USER_HOME_SCENE_PATH, HOME_SCENE_PATH], home_scene));
+    self.physics = None;
+  }
+}
+
+impl View for HomeView {
+  fn name(&self) -> &str {
+    "home"
+  }
+
+  fn on_enter(&mut self, ctx: &mut WgpuCtx) {
+    self.load(ctx);
+  }
+
+  fn update(&mut self, ctx: &mut WgpuCtx, dt: f32) {
+    self.operation_panel.update(ctx);
+    if let (Some(world), Some(scene)) = (self.physics.as_mut(), self.scene.as_mut()) {
+      world.update(dt);
+      world.sync_to_scene(scene);
+      update_colliders(scene, ctx.camera.colliders_mut(), &self.dynamic);
+      for contact in world.contacts() {
+        ctx.debug_draw.cross(contact.point, 10.0, [1.0, 0.2, 0.2]);
+      }
+    }
+  }
+
+  fn handle_input(&mut self, ctx: &mut WgpuCtx, event: &WindowEvent) -> bool {
+    if self.operation_panel.ui.handle_window_event(event) {
+      return true;
+    }
+    let WindowEvent::KeyboardInput { event, .. } = event else {
+      return false
+    };
+    if event.state != ElementState::Pressed {
+      return false;
+    }
+    match event.physical_key {
+      PhysicalKey::Code(KeyCode::F5) => {
+        // 重新加载场景文件
+        self.load(ctx);
+        true
+      },
+      PhysicalKey::Code(KeyCode::F6) => {
+        // 保存场景（包括当前相机位置）
+        if let Some(scene) = self.scene.as_mut() {
+          scene.camera = CameraDesc::from_camera(&ctx.camera);
+          match save_scene(scene, USER_HOME_SCENE_PATH) {
+            Ok(()) => println!("场景已保存到 {}", USER_HOME_SCENE_PATH),
+            Err(e) => println!("{}", e),
+          }
+        }
+        true
+      },
+      PhysicalKey::Code(KeyCode::KeyP) => {
+        // 开启时由当前场景生成物理世界和相机的碰撞体，关闭后元素停在当前位置
+        self.physics = match (self.physics.as_ref(), self.scene.as_ref()) {
+          (None, Some(scene)) => {
+            let world = PhysicsWorld::from_scene(scene);
+            self.dynamic = world.bodies.iter().filter(|b| b.body_type == BodyType::Dynamic).filter_map(|b| b.element).collect();
+            ctx.camera.set_colliders(static_colliders(scene));
+            Some(world)
+          },
+          _ => None,
+        };
+        true
+      },
+      PhysicalKey::Code(KeyCode::KeyL) => {
+        self.wireframe = !self.wireframe;
+        true
+      },
+      _ => false,
+    }
+  }
+
+  fn build_render_list(&mut self, ctx: &mut WgpuCtx) -> Vec<Vertex> {
+    let (mut vertex_list, batches) = self.scene.as_ref().map(Scene::render_list).unwrap_or_default();
+    for batch in batches {
+      let state = if self.wireframe { batch.state.wireframe() } else { batch.state };
+      ctx.batches.push(RenderBatch { state, ..batch });
+    }
+    self.operation_panel.apply(&mut vertex_list);
+    draw_operation_panel(ctx, &mut self.operation_panel, &vertex_list);
+    vertex_list
+  }
+}
+
+/// 内置的首页场景，场景文件不存在时使用
+pub fn home_scene() -> Scene {
+  let mut scene = Scene::new("home");
+  scene.camera = CameraDesc {
+    position: [5100.0, 2200.0, 0.0],
+    ..Default::default()
+  };
+  scene.lights = vec![
+    Light { kind: LightKind::Ambient, color: [1.0, 1.0, 1.0], intensity: 0.3 },
+    Light { kind: LightKind::Directional { direction: [-0.3, -1.0, 0.5] }, color: [1.0, 1.0, 1.0], intensity: 0.8 },
+  ];
+  scene.materials = vec![
+    Material::new("base", [0.8, 0.35, 0.25]),
+    Material::new("accent", [0.25, 0.55, 0.85]),
+    Material::new("small", [0.9, 0.8, 0.3]),
+  ];
+  // 绘制多个立方体：(中心点, 边长, 材质)
   let init_objs = [
-    Cube::new(5000.0, 2000.0, 2500.0, 200.0, 200.0,200.0,0.5),
-    Cube::new(5200.0, 2200.0, 2200.0, 100.0, 100.0, 100.0, 0.2),
-    Cube::new(5100.0, 2200.0, 2325.0, 50.0, 50.0, 50.0, 0.9),
-    Cube::new(5200.0, 2200.0, 2325.0, 50.0, 50.0, 50.0, 0.9),
-    Cube::new(5300.0, 2200.0, 2325.0, 50.0, 50.0, 50.0, 0.9),
-    Cube::new(5400.0, 2200.0, 2325.0, 50.0, 50.0, 50.0, 0.9),
-    Cube::new(5500.0, 2200.0, 2325.0, 50.0, 50.0, 50.0, 0.9),
-    Cube::new(4800.0, 2200.0, 2325.0, 50.0, 50.0, 50.0, 0.9),
+    ([5000.0, 2000.0, 2500.0], 200.0, "base"),
+    ([5200.0, 2200.0, 2200.0], 100.0, "accent"),
+    ([5100.0, 2200.0, 2325.0], 50.0, "small"),
+    ([5200.0, 2200.0, 2325.0], 50.0, "small"),
+    ([5300.0, 2200.0, 2325.0], 50.0, "small"),
+    ([5400.0, 2200.0, 2325.0], 50.0, "small"),
+    ([5500.0, 2200.0, 2325.0], 50.0, "small"),
+    ([4800.0, 2200.0, 2325.0], 50.0, "small"),
   ];
-  
-  // 将两个立方体的顶点合并
-  let mut vertex_list = vec![];
-  for obj in init_objs.iter() {
-    vertex_list.extend_from_slice(&obj.pos);
+  for (i, (center, size, material)) in init_objs.iter().enumerate() {
+    scene.elements.push(Element {
+      id: i as u32 + 1,
+      name: format!("cube_{}", i + 1),
+      shape: Shape::Cube { size: [*size, *size, *size] },
+      transform: Transform::at(center[0], center[1], center[2]),
+      material: material.to_string(),
+    });
   }
-  // let all_pos = cube_a.pos.iter().chain(&cube_b.pos).chain(&cube_c.pos).cloned().collect();
-  
-  // vertex_list.extend(&(&cube_a).pos.to_vec());
-  // vertex_list.extend(&cube_b.pos);
-  // cube_a.pos.
-  // for i in 0..cube_a.pos.len() {
-  //   vertex_list.push(cube_a.pos[i]);
-  // }
-  // for i in 0..cube_b.pos.len() {
-  //   vertex_list.push(cube_b.pos[i]);
-  // }
-  // vertex_list
-  vertex_list
-}
\ No newline at end of file
+  scene
+}
@@ -1 +1,7 @@
-pub mod home;
\ No newline at end of file
+pub mod home;
+pub mod operation;
+pub mod view;
+pub mod registry;
+pub mod editor;
+pub mod viewer;
+pub mod script;
@@ -1,4 +1,125 @@
+use nalgebra::Vector3;
+
+use crate::element::action::move_el;
+use crate::render::{vertex::Vertex, wgpu_ctx::WgpuCtx};
+use crate::text::layout::TextStyle;
+use crate::ui::{context::{Ui, UiEvent}, panel::Panel, widget::WidgetKind};
+
+// 相机速度档位
+const CAMERA_SPEEDS: [f32; 3] = [2.0, 5.0, 20.0];
+const SPEED_NAMES: [&str; 3] = ["慢", "中", "快"];
+
+/// 操作面板，调整场景的偏移、颜色和显示选项
+pub struct OperationPanel {
+  pub ui: Ui,
+  pub offset: Vector3<f32>, // 场景整体偏移
+  pub tint: [f32; 3], // 顶点颜色乘以该颜色
+  pub show_bounds: bool, // 显示包围盒
+  pub label: String, // 显示在场景上方的标签
+}
+
+impl Default for OperationPanel {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl OperationPanel {
+  pub fn new() -> Self {
+    let mut ui = Ui::new();
+    ui.add_panel(Self::build_panel());
+    Self {
+      ui,
+      offset: Vector3::zeros(),
+      tint: [1.0, 1.0, 1.0],
+      show_bounds: false,
+      label: String::new(),
+    }
+  }
+
+  fn build_panel() -> Panel {
+    Panel::new("操作面板", 10.0, 40.0, 280.0)
+      .slider("offset_x", "X", 0.0, -500.0, 500.0)
+      .slider("offset_y", "Y", 0.0, -500.0, 500.0)
+      .slider("offset_z", "Z", 0.0, -500.0, 500.0)
+      .color_picker("tint", "颜色", [1.0, 1.0, 1.0])
+      .checkbox("show_bounds", "显示包围盒", false)
+      .text_input("label", "标签", "")
+      .dropdown("speed", "相机速度", &SPEED_NAMES, 1)
+      .button("reset", "重置")
+  }
+
+  /// 处理界面事件，更新面板状态
+  pub fn update(&mut self, ctx: &mut WgpuCtx) {
+    for event in self.ui.drain_events() {
+      match event {
+        UiEvent::ValueChanged(id, v) => match id.as_str() {
+          "offset_x" => self.offset.x = v,
+          "offset_y" => self.offset.y = v,
+          "offset_z" => self.offset.z = v,
+          _ => {},
+        },
+        UiEvent::ColorChanged(id, color) if id == "tint" => self.tint = color,
+        UiEvent::Toggled(id, checked) if id == "show_bounds" => self.show_bounds = checked,
+        UiEvent::TextChanged(id, text) if id == "label" => self.label = text,
+        UiEvent::Selected(id, i) if id == "speed" => ctx.camera.set_speed(CAMERA_SPEEDS[i]),
+        UiEvent::Clicked(id) if id == "reset" => self.reset(ctx),
+        _ => {},
+      }
+    }
+  }
+
+  // 恢复默认值，同时重建面板上的控件
+  fn reset(&mut self, ctx: &mut WgpuCtx) {
+    let rect = self.ui.panels[0].rect;
+    let mut panel = Self::build_panel();
+    panel.move_by(rect.x - panel.rect.x, rect.y - panel.rect.y);
+    self.ui.panels[0] = panel;
+    self.offset = Vector3::zeros();
+    self.tint = [1.0, 1.0, 1.0];
+    self.show_bounds = false;
+    self.label.clear();
+    if let Some(WidgetKind::Dropdown { selected, .. }) = self.ui.widget("speed").map(|w| &w.kind) {
+      ctx.camera.set_speed(CAMERA_SPEEDS[*selected]);
+    }
+  }
+
+  /// 把面板的设置应用到场景顶点
+  pub fn apply(&self, vertex_list: &mut Vec<Vertex>) {
+    move_el(vertex_list, self.offset.x, self.offset.y, self.offset.z);
+    for v in vertex_list.iter_mut() {
+      for i in 0..3 {
+        v.color[i] *= self.tint[i];
+      }
+    }
+  }
+}
+
 // 绘制一个操作面板
-pub fn draw_operation_panel(ctx: &mut WgpuCtx) {
-  
-}
\ No newline at end of file
+pub fn draw_operation_panel(ctx: &mut WgpuCtx, panel: &mut OperationPanel, vertex_list: &[Vertex]) {
+  // 没有字体时面板不可见，也不拦截输入
+  panel.ui.visible = ctx.text_renderer.is_some();
+
+  if (panel.show_bounds || !panel.label.is_empty()) && !vertex_list.is_empty() {
+    let mut min = Vector3::from(vertex_list[0].position);
+    let mut max = min;
+    for v in vertex_list.iter() {
+      let p = Vector3::from(v.position);
+      min = min.inf(&p);
+      max = max.sup(&p);
+    }
+    if panel.show_bounds {
+      ctx.debug_draw.aabb(min, max, [1.0, 1.0, 0.0]);
+    }
+    if let Some(text_renderer) = ctx.text_renderer.as_mut() {
+      if !panel.label.is_empty() {
+        let anchor = Vector3::new((min.x + max.x) / 2.0, max.y, (min.z + max.z) / 2.0);
+        text_renderer.queue_label(&panel.label, anchor, &ctx.camera, &TextStyle { size: 18.0, ..Default::default() }, [1.0, 1.0, 1.0, 1.0]);
+      }
+    }
+  }
+
+  if let Some(text_renderer) = ctx.text_renderer.as_mut() {
+    panel.ui.draw(text_renderer);
+  }
+}
@@ -0,0 +1,287 @@
+use winit::event::WindowEvent;
+
+use crate::render::{vertex::Vertex, wgpu_ctx::WgpuCtx};
+
+use super::view::View;
+
+/// 视图切换方式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+  Cut, // 立即切换
+  Fade(f32), // 淡出再淡入，参数为总时长（秒）
+}
+
+// 正在进行的切换
+struct Switching {
+  to: usize,
+  elapsed: f32,
+  duration: f32,
+  switched: bool, // 是否已经过了中点（已调用 on_exit/on_enter）
+}
+
+/// 已注册的视图，按名称切换，`C` 是传给视图回调的上下文
+pub struct ViewRegistry<C = WgpuCtx<'static>> {
+  views: Vec<Box<dyn View<C>>>,
+  current: Option<usize>,
+  switching: Option<Switching>,
+  pub transition: Transition, // 切换视图时使用的过渡方式
+}
+
+impl<C> Default for ViewRegistry<C> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<C> ViewRegistry<C> {
+  pub fn new() -> Self {
+    Self {
+      views: vec![],
+      current: None,
+      switching: None,
+      transition: Transition::Fade(0.4),
+    }
+  }
+
+  /// 注册视图，同名视图会被替换
+  pub fn register(&mut self, view: Box<dyn View<C>>) {
+    match self.index_of(view.name()) {
+      Some(i) => self.views[i] = view,
+      None => self.views.push(view),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.views.is_empty()
+  }
+
+  fn index_of(&self, name: &str) -> Option<usize> {
+    self.views.iter().position(|v| v.name() == name)
+  }
+
+  pub fn current_name(&self) -> Option<&str> {
+    self.current.map(|i| self.views[i].name())
+  }
+
+  /// 切换的目标视图名称，没有切换时为当前视图
+  pub fn target_name(&self) -> Option<&str> {
+    match self.switching.as_ref() {
+      Some(s) => Some(self.views[s.to].name()),
+      None => self.current_name(),
+    }
+  }
+
+  /// 切换到指定名称的视图，找不到时返回 false
+  pub fn switch_to(&mut self, name: &str, ctx: &mut C) -> bool {
+    let Some(to) = self.index_of(name) else {
+      println!("视图 {} 不存在", name);
+      return false
+    };
+    if self.target_name() == Some(name) {
+      return true;
+    }
+    match (self.current, self.transition) {
+      (Some(_), Transition::Fade(duration)) if duration > 0.0 => {
+        // 切换还没过中点时直接改变目标，已过中点则从当前视图重新开始淡出
+        self.switching = match self.switching.take() {
+          Some(s) if !s.switched => Some(Switching { to, ..s }),
+          _ => Some(Switching { to, elapsed: 0.0, duration, switched: false }),
+        };
+      },
+      _ => {
+        self.switching = None;
+        self.enter(to, ctx);
+      },
+    }
+    true
+  }
+
+  // 离开当前视图并进入新视图
+  fn enter(&mut self, to: usize, ctx: &mut C) {
+    if let Some(current) = self.current {
+      self.views[current].on_exit(ctx);
+    }
+    self.current = Some(to);
+    self.views[to].on_enter(ctx);
+  }
+
+  /// 推进切换过程并更新当前视图
+  pub fn update(&mut self, ctx: &mut C, dt: f32) {
+    if let Some(mut s) = self.switching.take() {
+      s.elapsed += dt;
+      if !s.switched && s.elapsed >= s.duration / 2.0 {
+        s.switched = true;
+        self.enter(s.to, ctx);
+      }
+      if s.elapsed < s.duration {
+        self.switching = Some(s);
+      }
+    }
+    if let Some(current) = self.current {
+      self.views[current].update(ctx, dt);
+    }
+  }
+
+  /// 切换过程中遮罩的不透明度，中点时为 1
+  pub fn fade_alpha(&self) -> f32 {
+    match self.switching.as_ref() {
+      Some(s) => 1.0 - (s.elapsed / s.duration * 2.0 - 1.0).abs().min(1.0),
+      None => 0.0,
+    }
+  }
+
+  pub fn handle_input(&mut self, ctx: &mut C, event: &WindowEvent) -> bool {
+    match self.current {
+      Some(current) => self.views[current].handle_input(ctx, event),
+      None => false,
+    }
+  }
+}
+
+impl ViewRegistry {
+  /// 当前视图的顶点，切换过程中叠加黑色遮罩（没有文字渲染器时改为压暗顶点颜色）
+  pub fn build_render_list(&mut self, ctx: &mut WgpuCtx<'static>) -> Vec<Vertex> {
+    let Some(current) = self.current else {
+      return vec![]
+    };
+    let mut vertex_list = self.views[current].build_render_list(ctx);
+    let alpha = self.fade_alpha();
+    if alpha > 0.0 {
+      match ctx.text_renderer.as_mut() {
+        Some(text_renderer) => text_renderer.queue_rect(0.0, 0.0, ctx.vw as f32, ctx.vh as f32, [0.0, 0.0, 0.0, alpha]),
+        None => {
+          for v in vertex_list.iter_mut() {
+            for c in v.color[..3].iter_mut() {
+              *c *= 1.0 - alpha;
+            }
+          }
+        },
+      }
+    }
+    vertex_list
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 上下文换成调用记录，视图的回调按顺序写入 "视图名.回调名"
+  type Log = Vec<String>;
+
+  struct Stub(&'static str);
+
+  impl View<Log> for Stub {
+    fn name(&self) -> &str {
+      self.0
+    }
+
+    fn on_enter(&mut self, log: &mut Log) {
+      log.push(format!("{}.enter", self.0));
+    }
+
+    fn on_exit(&mut self, log: &mut Log) {
+      log.push(format!("{}.exit", self.0));
+    }
+
+    fn update(&mut self, log: &mut Log, _dt: f32) {
+      log.push(format!("{}.update", self.0));
+    }
+
+    fn build_render_list(&mut self, _log: &mut Log) -> Vec<Vertex> {
+      vec![]
+    }
+  }
+
+  fn registry(transition: Transition) -> ViewRegistry<Log> {
+    let mut registry = ViewRegistry::new();
+    registry.transition = transition;
+    for name in ["a", "b", "c"] {
+      registry.register(Box::new(Stub(name)));
+    }
+    registry
+  }
+
+  // 取出并清空调用记录
+  fn take(log: &mut Log) -> Vec<String> {
+    std::mem::take(log)
+  }
+
+  #[test]
+  fn cut_switches_immediately() {
+    let mut log = Log::new();
+    let mut views = registry(Transition::Cut);
+    assert_eq!(views.current_name(), None);
+    assert!(views.switch_to("a", &mut log));
+    assert_eq!(take(&mut log), ["a.enter"]);
+    assert!(views.switch_to("b", &mut log));
+    assert_eq!(take(&mut log), ["a.exit", "b.enter"]);
+    assert_eq!(views.current_name(), Some("b"));
+
+    // 切换到当前视图和不存在的视图都不调用回调
+    assert!(views.switch_to("b", &mut log));
+    assert!(!views.switch_to("missing", &mut log));
+    views.update(&mut log, 0.1);
+    assert_eq!(take(&mut log), ["b.update"]);
+    assert_eq!(views.fade_alpha(), 0.0);
+  }
+
+  #[test]
+  fn fade_switches_at_midpoint() {
+    let mut log = Log::new();
+    let mut views = registry(Transition::Fade(1.0));
+    // 还没有当前视图时直接进入
+    views.switch_to("a", &mut log);
+    assert_eq!(take(&mut log), ["a.enter"]);
+
+    views.switch_to("b", &mut log);
+    assert!(log.is_empty());
+    assert_eq!((views.current_name(), views.target_name()), (Some("a"), Some("b")));
+    views.update(&mut log, 0.25);
+    assert_eq!(take(&mut log), ["a.update"]);
+    assert_eq!(views.fade_alpha(), 0.5);
+
+    views.update(&mut log, 0.25);
+    assert_eq!(take(&mut log), ["a.exit", "b.enter", "b.update"]);
+    assert_eq!(views.current_name(), Some("b"));
+    assert_eq!(views.fade_alpha(), 1.0);
+
+    views.update(&mut log, 0.25);
+    assert_eq!(take(&mut log), ["b.update"]);
+    assert_eq!(views.fade_alpha(), 0.5);
+    views.update(&mut log, 0.25);
+    assert_eq!(take(&mut log), ["b.update"]);
+    assert_eq!(views.fade_alpha(), 0.0);
+    assert_eq!(views.target_name(), Some("b"));
+  }
+
+  #[test]
+  fn retarget_mid_fade() {
+    let mut log = Log::new();
+    let mut views = registry(Transition::Fade(1.0));
+    views.switch_to("a", &mut log);
+
+    // 中点之前改变目标：继续同一次淡出，中点时直接进入新目标，b 不会被进入
+    views.switch_to("b", &mut log);
+    views.update(&mut log, 0.25);
+    views.switch_to("c", &mut log);
+    assert_eq!(views.target_name(), Some("c"));
+    assert_eq!(views.fade_alpha(), 0.5);
+    views.update(&mut log, 0.25);
+    assert_eq!(take(&mut log), ["a.enter", "a.update", "a.exit", "c.enter", "c.update"]);
+
+    // 中点之后改变目标：从 c 重新开始淡出
+    views.update(&mut log, 0.25);
+    views.switch_to("b", &mut log);
+    assert_eq!(views.fade_alpha(), 0.0);
+    views.update(&mut log, 0.25);
+    assert_eq!(views.fade_alpha(), 0.5);
+    views.update(&mut log, 0.25);
+    assert_eq!(take(&mut log), ["c.update", "c.update", "c.exit", "b.enter", "b.update"]);
+    assert_eq!(views.current_name(), Some("b"));
+
+    // 淡入过程中切换回当前目标不改变进度
+    views.switch_to("b", &mut log);
+    assert_eq!(views.fade_alpha(), 1.0);
+  }
+}
@@ -0,0 +1,263 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use nalgebra::Vector3;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+
+use crate::physics::world::static_colliders;
+use crate::render::{camera::Camera, vertex::Vertex, wgpu_ctx::WgpuCtx};
+use crate::scene::file::scene_from_json;
+use crate::scene::import::{centered, import_model};
+use crate::scene::model::{CameraDesc, Element, Material, Scene, Shape, Transform};
+use crate::scene::pick::{pick, Hit};
+
+use super::view::View;
+
+/// 拾取事件的监听函数
+pub type PickListener = Rc<dyn Fn(&Hit)>;
+
+/// 脚本视图和外部（浏览器中的 JS）共享的状态
+pub type SharedScript = Rc<RefCell<ScriptState>>;
+
+// 新元素的默认颜色
+const DEFAULT_COLOR: [f32; 3] = [0.8, 0.8, 0.8];
+
+/// 由外部脚本修改的场景，每个元素使用自己的材质 `element_<id>`，修改颜色不影响其他元素
+///
+/// 这里只修改数据，不依赖 GPU，`ScriptView` 每帧读取后绘制；相机的修改在下一帧应用到窗口的相机上。
+pub struct ScriptState {
+  pub scene: Scene,
+  camera: CameraDesc, // 最近一帧的相机，拾取时使用
+  screen: (f32, f32), // 最近一帧的画面尺寸（像素）
+  camera_changed: bool, // 脚本修改了相机，下一帧应用
+  revision: u64, // 每次修改场景加 1，视图据此更新碰撞体和背景
+  listeners: Vec<(u32, PickListener)>,
+  next_listener: u32,
+}
+
+impl Default for ScriptState {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ScriptState {
+  pub fn new() -> Self {
+    let scene = Scene::new("script");
+    Self {
+      camera: scene.camera,
+      scene,
+      screen: (1.0, 1.0),
+      camera_changed: true,
+      revision: 0,
+      listeners: vec![],
+      next_listener: 1,
+    }
+  }
+
+  pub fn shared() -> SharedScript {
+    Rc::new(RefCell::new(Self::new()))
+  }
+
+  pub fn revision(&self) -> u64 {
+    self.revision
+  }
+
+  fn material_name(id: u32) -> String {
+    format!("element_{}", id)
+  }
+
+  /// 添加元素并创建它的材质，返回元素 id
+  pub fn add_element(&mut self, name: &str, shape: Shape, transform: Transform) -> u32 {
+    let id = self.scene.next_id();
+    let material = Self::material_name(id);
+    self.scene.materials.retain(|m| m.name != material);
+    self.scene.materials.push(Material::new(&material, DEFAULT_COLOR));
+    self.scene.elements.push(Element { id, name: name.to_string(), shape, transform, material });
+    self.revision += 1;
+    id
+  }
+
+  /// 添加以 `position` 为中心的立方体
+  pub fn add_cube(&mut self, position: [f32; 3], size: [f32; 3]) -> u32 {
+    let id = self.scene.next_id();
+    self.add_element(&format!("cube_{}", id), Shape::Cube { size }, Transform { position, ..Default::default() })
+  }
+
+  /// 添加三角形列表组成的网格，顶点为世界坐标，元素位置为网格包围盒的中心
+  pub fn add_mesh(&mut self, name: &str, positions: Vec<[f32; 3]>) -> Result<u32, String> {
+    let mesh = centered(positions)?;
+    Ok(self.add_element(name, Shape::Mesh { positions: mesh.positions }, Transform { position: mesh.center, ..Default::default() }))
+  }
+
+  /// 从 OBJ 或 STL 文件内容导入网格
+  pub fn load_model(&mut self, name: &str, data: &[u8]) -> Result<u32, String> {
+    let mesh = import_model(data).map_err(|e| format!("导入模型 {} 失败：{}", name, e))?;
+    Ok(self.add_element(name, Shape::Mesh { positions: mesh.positions }, Transform { position: mesh.center, ..Default::default() }))
+  }
+
+  /// 用场景 JSON 替换当前场景，相机也使用场景中的设置
+  pub fn load_scene(&mut self, text: &str) -> Result<(), String> {
+    self.scene = scene_from_json(text)?;
+    self.set_camera(self.scene.camera);
+    self.revision += 1;
+    Ok(())
+  }
+
+  /// 删除元素和它的材质，找不到元素时返回 false
+  pub fn remove(&mut self, id: u32) -> bool {
+    let Some(index) = self.scene.elements.iter().position(|e| e.id == id) else {
+      return false
+    };
+    let element = self.scene.elements.remove(index);
+    if element.material == Self::material_name(id) {
+      self.scene.materials.retain(|m| m.name != element.material);
+    }
+    self.revision += 1;
+    true
+  }
+
+  /// 修改元素的变换，找不到元素时返回错误
+  pub fn update_transform(&mut self, id: u32, update: impl FnOnce(&mut Transform)) -> Result<(), String> {
+    let element = self.scene.element_mut(id).ok_or_else(|| format!("元素 {} 不存在", id))?;
+    update(&mut element.transform);
+    self.revision += 1;
+    Ok(())
+  }
+
+  /// 修改元素的颜色和不透明度；元素使用共享材质（来自场景文件）时先复制一份自己的材质
+  pub fn set_color(&mut self, id: u32, color: [f32; 3], opacity: f32) -> Result<(), String> {
+    let element = self.scene.element(id).ok_or_else(|| format!("元素 {} 不存在", id))?;
+    let name = Self::material_name(id);
+    if element.material != name {
+      self.scene.materials.retain(|m| m.name != name);
+      self.scene.materials.push(Material::new(&name, color));
+      if let Some(element) = self.scene.element_mut(id) {
+        element.material = name.clone();
+      }
+    }
+    if let Some(material) = self.scene.materials.iter_mut().find(|m| m.name == name) {
+      material.color = color;
+      material.opacity = opacity.clamp(0.0, 1.0);
+    }
+    self.revision += 1;
+    Ok(())
+  }
+
+  /// 最近一帧的相机，脚本修改后还没有应用时为修改后的值
+  pub fn camera(&self) -> CameraDesc {
+    self.camera
+  }
+
+  /// 修改相机，下一帧应用
+  pub fn set_camera(&mut self, camera: CameraDesc) {
+    self.camera = camera;
+    self.camera_changed = true;
+  }
+
+  /// 取出脚本对相机的修改
+  pub fn take_camera_change(&mut self) -> Option<CameraDesc> {
+    std::mem::take(&mut self.camera_changed).then_some(self.camera)
+  }
+
+  /// 记录窗口当前的相机和画面尺寸，拾取时使用
+  pub fn set_viewport(&mut self, camera: CameraDesc, width: f32, height: f32) {
+    self.camera = camera;
+    self.screen = (width.max(1.0), height.max(1.0));
+  }
+
+  /// 拾取画面上像素坐标（左上角为原点）处最近的元素
+  pub fn pick(&self, x: f32, y: f32) -> Option<Hit> {
+    let mut camera = Camera::new(Vector3::zeros(), Vector3::z(), Vector3::y(), 1.0, self.screen.0, self.screen.1, 0.1, 100.0, 0.0);
+    self.camera.apply(&mut camera);
+    let (origin, direction) = camera.screen_ray(x, y)?;
+    pick(&self.scene, origin, direction)
+  }
+
+  /// 添加拾取事件的监听函数，返回用于 `remove_listener` 的 id
+  pub fn add_listener(&mut self, listener: PickListener) -> u32 {
+    let id = self.next_listener;
+    self.next_listener += 1;
+    self.listeners.push((id, listener));
+    id
+  }
+
+  pub fn remove_listener(&mut self, id: u32) -> bool {
+    let count = self.listeners.len();
+    self.listeners.retain(|(i, _)| *i != id);
+    self.listeners.len() != count
+  }
+}
+
+/// 拾取点击位置的元素并通知监听函数，监听函数中可以继续修改场景
+pub fn click(state: &SharedScript, x: f32, y: f32) -> Option<Hit> {
+  let (hit, listeners) = {
+    let state = state.borrow();
+    let hit = state.pick(x, y)?;
+    (hit, state.listeners.iter().map(|(_, l)| l.clone()).collect::<Vec<_>>())
+  };
+  for listener in listeners {
+    listener(&hit);
+  }
+  Some(hit)
+}
+
+/// 脚本视图：绘制 `ScriptState` 中的场景，点击元素时发出拾取事件，没有点中元素时交给相机
+pub struct ScriptView {
+  state: SharedScript,
+  revision: Option<u64>, // 已应用碰撞体和背景的场景版本
+  cursor: (f32, f32),
+}
+
+impl ScriptView {
+  pub fn new(state: SharedScript) -> Self {
+    Self { state, revision: None, cursor: (0.0, 0.0) }
+  }
+}
+
+impl View for ScriptView {
+  fn name(&self) -> &str {
+    "script"
+  }
+
+  fn on_enter(&mut self, _ctx: &mut WgpuCtx) {
+    // 进入时重新应用相机、背景和碰撞体
+    self.revision = None;
+    let mut state = self.state.borrow_mut();
+    let camera = state.camera();
+    state.set_camera(camera);
+  }
+
+  fn update(&mut self, ctx: &mut WgpuCtx, _dt: f32) {
+    let mut state = self.state.borrow_mut();
+    if let Some(camera) = state.take_camera_change() {
+      camera.apply(&mut ctx.camera);
+    }
+    if self.revision != Some(state.revision()) {
+      self.revision = Some(state.revision());
+      ctx.camera.set_colliders(static_colliders(&state.scene));
+      let [r, g, b, a] = state.scene.background;
+      ctx.clear_color = wgpu::Color { r: r as f64, g: g as f64, b: b as f64, a: a as f64 };
+    }
+    state.set_viewport(CameraDesc::from_camera(&ctx.camera), ctx.vw as f32, ctx.vh as f32);
+  }
+
+  fn handle_input(&mut self, _ctx: &mut WgpuCtx, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::CursorMoved { position, .. } => {
+        self.cursor = (position.x as f32, position.y as f32);
+        false
+      },
+      WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
+        click(&self.state, self.cursor.0, self.cursor.1).is_some()
+      },
+      _ => false,
+    }
+  }
+
+  fn build_render_list(&mut self, ctx: &mut WgpuCtx) -> Vec<Vertex> {
+    let (vertex_list, batches) = self.state.borrow().scene.render_list();
+    ctx.batches.extend(batches);
+    vertex_list
+  }
+}
@@ -0,0 +1,51 @@
+use winit::event::WindowEvent;
+
+use crate::physics::world::static_colliders;
+use crate::render::{vertex::Vertex, wgpu_ctx::WgpuCtx};
+use crate::scene::{file::load_scene, model::Scene};
+
+/// 视图，例如首页、编辑器、浏览器，由 `ViewRegistry` 管理切换
+///
+/// `C` 是传给各个回调的上下文，测试中可以换成不需要 GPU 的类型。
+pub trait View<C = WgpuCtx<'static>> {
+  /// 视图名称，对应 `App` 的 `scene` 字段
+  fn name(&self) -> &str;
+
+  /// 切换到该视图时调用
+  fn on_enter(&mut self, _ctx: &mut C) {}
+
+  /// 离开该视图时调用
+  fn on_exit(&mut self, _ctx: &mut C) {}
+
+  /// 每帧更新，`dt` 为距上一帧的秒数
+  fn update(&mut self, _ctx: &mut C, _dt: f32) {}
+
+  /// 处理窗口输入，返回 true 表示输入已被消费，不再交给相机
+  fn handle_input(&mut self, _ctx: &mut C, _event: &WindowEvent) -> bool {
+    false
+  }
+
+  /// 生成本帧要绘制的顶点，调试图元和文字可以直接加到 `ctx` 上
+  fn build_render_list(&mut self, ctx: &mut C) -> Vec<Vertex>;
+}
+
+/// 按顺序读取 `paths` 中第一个可用的场景文件，应用其中的相机、背景色和行走碰撞体；都不可用时使用 `fallback` 生成的场景
+///
+/// 不存在的文件直接跳过，例如还没有保存过的用户场景。
+pub fn enter_scene(ctx: &mut WgpuCtx, paths: &[&str], fallback: fn() -> Scene) -> Scene {
+  let scene = paths.iter().filter(|path| std::path::Path::new(path).exists()).find_map(|path| match load_scene(path) {
+    Ok(scene) => Some(scene),
+    Err(e) => {
+      println!("{}", e);
+      None
+    },
+  }).unwrap_or_else(|| {
+    println!("没有可用的场景文件，使用内置场景");
+    fallback()
+  });
+  scene.camera.apply(&mut ctx.camera);
+  ctx.camera.set_colliders(static_colliders(&scene));
+  let [r, g, b, a] = scene.background;
+  ctx.clear_color = wgpu::Color { r: r as f64, g: g as f64, b: b as f64, a: a as f64 };
+  scene
+}
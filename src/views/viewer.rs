@@ -0,0 +1,234 @@
+use nalgebra::{Matrix4, UnitQuaternion, Vector3};
+
+use crate::anim::clip::{AnimationClip, Channel, Target};
+use crate::anim::easing::Easing;
+use crate::anim::morph::{MorphMesh, MorphTarget};
+use crate::anim::player::{AnimationPlayer, LoopMode};
+use crate::anim::skeleton::{skin_vertices, JointChannel, JointTransform, SkeletalClip, Skeleton, SkinnedMesh};
+use crate::anim::track::{Interpolation, Keyframe, Track};
+use crate::constants::{HOME_SCENE_PATH, USER_HOME_SCENE_PATH};
+use crate::element::cube::Cube;
+use crate::physics::world::{static_colliders, update_colliders};
+use crate::render::{vertex::{SkinnedVertex, Vertex}, wgpu_ctx::WgpuCtx};
+use crate::scene::model::Scene;
+
+use super::home::home_scene;
+use super::view::{enter_scene, View};
+
+// 环绕速度（弧度/秒）
+const ORBIT_SPEED: f32 = 0.3;
+
+/// 浏览模式：相机自动环绕场景中心，点击窗口激活相机后暂停环绕；场景中的元素播放演示动画
+#[derive(Default)]
+pub struct ViewerView {
+  scene: Option<Scene>,
+  angle: f32,
+  center: Vector3<f32>,
+  distance: f32,
+  player: Option<AnimationPlayer>,
+  animated: Vec<u32>, // 演示动画改变变换的元素，每帧只更新它们的碰撞体
+  arm: Option<(SkinnedMesh, SkeletalClip)>, // 蒙皮演示：两节关节的摆臂
+  time: f32,
+  product: Option<(MorphMesh, AnimationPlayer)>, // 变形演示：宽度和高度可调的盒子
+  product_handle: Option<usize>, // 变形网格上传到 GPU 后的句柄，不支持 GPU 变形时为空
+}
+
+/// 演示动画：第一个元素上下浮动并旋转，第二个元素来回缩放，其材质颜色渐变
+fn demo_clip(scene: &Scene) -> AnimationClip {
+  let mut clip = AnimationClip::new("demo");
+  if let Some(el) = scene.elements.first() {
+    let [x, y, z] = el.transform.position;
+    let position = Track::new(vec![
+      Keyframe::eased(0.0, [x, y, z], Easing::SineInOut),
+      Keyframe::eased(1.0, [x, y + 150.0, z], Easing::SineInOut),
+      Keyframe::new(2.0, [x, y, z]),
+    ], Interpolation::Linear);
+    let rotation = Track::new(vec![
+      Keyframe::new(0.0, [0.0, 0.0, 0.0]),
+      Keyframe::new(1.0, [0.0, 90.0, 30.0]),
+      Keyframe::new(2.0, [0.0, 0.0, 0.0]),
+    ], Interpolation::Slerp);
+    clip = clip
+      .track(Target::Element { id: el.id }, Channel::Position { track: position })
+      .track(Target::Element { id: el.id }, Channel::Rotation { track: rotation });
+  }
+  if let Some(el) = scene.elements.get(1) {
+    let scale = Track::new(vec![
+      Keyframe::new(0.0, 1.0),
+      Keyframe::eased(0.5, 1.5, Easing::BounceOut),
+      Keyframe::new(2.0, 1.0),
+    ], Interpolation::Cubic);
+    clip = clip.track(Target::Element { id: el.id }, Channel::Float { property: "scale".to_string(), track: scale });
+    if let Some(material) = scene.material(&el.material) {
+      let color = Track::new(vec![
+        Keyframe::new(0.0, material.color),
+        Keyframe::new(2.0, [1.0, 1.0, 1.0]),
+      ], Interpolation::Linear);
+      clip = clip.track(Target::Material { name: material.name.clone() }, Channel::Color { track: color });
+    }
+  }
+  clip
+}
+
+// 摆臂的节数和每节的边长
+const ARM_SEGMENTS: usize = 6;
+const ARM_SEGMENT_SIZE: f32 = 50.0;
+
+/// 蒙皮演示：由立方体叠成的柱子，下半部分跟随根关节，上半部分跟随弯曲的第二个关节
+fn demo_arm(origin: Vector3<f32>) -> (SkinnedMesh, SkeletalClip) {
+  let height = ARM_SEGMENTS as f32 * ARM_SEGMENT_SIZE;
+  let mut skeleton = Skeleton::default();
+  let root = skeleton.add_joint("root", None, JointTransform::default());
+  let elbow = skeleton.add_joint("elbow", Some(root), JointTransform {
+    translation: Vector3::new(0.0, height / 2.0, 0.0),
+    ..Default::default()
+  });
+
+  let mut vertices = vec![];
+  for i in 0..ARM_SEGMENTS {
+    let cy = (i as f32 + 0.5) * ARM_SEGMENT_SIZE;
+    for v in Cube::new(0.0, cy, 0.0, ARM_SEGMENT_SIZE, ARM_SEGMENT_SIZE, ARM_SEGMENT_SIZE, 1.0).pos {
+      // 在中间一节内从根关节过渡到第二个关节
+      let t = ((v.position[1] - height / 2.0) / ARM_SEGMENT_SIZE + 0.5).clamp(0.0, 1.0);
+      vertices.push(SkinnedVertex {
+        position: v.position,
+        color: [0.4 + 0.5 * t, 0.8 - 0.4 * t, 0.3, 1.0],
+        tex_coords: v.tex_coords,
+        joints: [root as u32, elbow as u32, 0, 0],
+        weights: [1.0 - t, t, 0.0, 0.0],
+      });
+    }
+  }
+
+  let bend = |deg: f32| {
+    let q = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), deg.to_radians());
+    [q.i, q.j, q.k, q.w]
+  };
+  let rotation = Track::new(vec![
+    Keyframe::eased(0.0, bend(-45.0), Easing::SineInOut),
+    Keyframe::eased(1.5, bend(45.0), Easing::SineInOut),
+    Keyframe::new(3.0, bend(-45.0)),
+  ], Interpolation::Slerp);
+  let clip = SkeletalClip {
+    name: "swing".to_string(),
+    channels: vec![JointChannel { joint: elbow, rotation: Some(rotation), ..Default::default() }],
+  };
+  let mesh = SkinnedMesh { vertices, skeleton, transform: Matrix4::new_translation(&origin) };
+  (mesh, clip)
+}
+
+// 变形演示盒子的边长
+const PRODUCT_SIZE: f32 = 100.0;
+
+/// 变形演示：盒子带 "width"、"height" 两个变形目标，由动画平滑地调整尺寸
+fn demo_product(origin: Vector3<f32>) -> (MorphMesh, AnimationPlayer) {
+  let vertices = Cube::new(0.0, 0.0, 0.0, PRODUCT_SIZE, PRODUCT_SIZE, PRODUCT_SIZE, 1.0).pos.into_iter()
+    .map(|v| Vertex { color: [0.85, 0.85, 0.9, 1.0], ..v })
+    .collect::<Vec<_>>();
+  let half = PRODUCT_SIZE / 2.0;
+  let width = MorphTarget {
+    name: "width".to_string(),
+    position_deltas: vertices.iter().map(|v| [v.position[0].signum() * half, 0.0, 0.0]).collect(),
+    normal_deltas: vec![],
+  };
+  let height = MorphTarget {
+    name: "height".to_string(),
+    position_deltas: vertices.iter().map(|v| [0.0, if v.position[1] > 0.0 { PRODUCT_SIZE } else { 0.0 }, 0.0]).collect(),
+    normal_deltas: vec![],
+  };
+  let mut mesh = MorphMesh::new("product", vertices);
+  mesh.transform = Matrix4::new_translation(&origin);
+  for target in [width, height] {
+    if let Err(e) = mesh.add_target(target) {
+      println!("{}", e);
+    }
+  }
+
+  let target = Target::Mesh { name: mesh.name.clone() };
+  let weight = |keys: Vec<Keyframe<f32>>| Track::new(keys, Interpolation::Linear);
+  let clip = AnimationClip::new("configure")
+    .track(target.clone(), Channel::Float { property: "width".to_string(), track: weight(vec![
+      Keyframe::eased(0.0, 0.0, Easing::CubicInOut),
+      Keyframe::new(2.0, 1.0),
+    ]) })
+    .track(target, Channel::Float { property: "height".to_string(), track: weight(vec![
+      Keyframe::new(0.0, 0.0),
+      Keyframe::eased(1.0, 0.0, Easing::BackOut),
+      Keyframe::new(3.0, 1.0),
+    ]) });
+  (mesh, AnimationPlayer::new(clip, LoopMode::PingPong))
+}
+
+impl View for ViewerView {
+  fn name(&self) -> &str {
+    "viewer"
+  }
+
+  fn on_enter(&mut self, ctx: &mut WgpuCtx) {
+    let scene = enter_scene(ctx, &[USER_HOME_SCENE_PATH, HOME_SCENE_PATH], home_scene);
+    let (min, max) = scene.bounds().unwrap_or((Vector3::zeros(), Vector3::zeros()));
+    self.center = (min + max) / 2.0;
+    self.distance = ((max - min).norm() * 2.0).max(500.0);
+    self.angle = 0.0;
+    let clip = demo_clip(&scene);
+    self.animated = clip.tracks.iter().filter_map(|t| match t.target {
+      Target::Element { id } => Some(id),
+      _ => None,
+    }).collect();
+    self.animated.sort();
+    self.animated.dedup();
+    ctx.camera.set_colliders(static_colliders(&scene));
+    self.player = Some(AnimationPlayer::new(clip, LoopMode::PingPong));
+    self.arm = Some(demo_arm(Vector3::new(self.center.x, min.y, max.z + 200.0)));
+    self.time = 0.0;
+    let (product, player) = demo_product(Vector3::new(self.center.x + 300.0, min.y, max.z + 200.0));
+    // 网格结构不变，只在第一次进入时上传
+    if self.product_handle.is_none() {
+      self.product_handle = ctx.morph_renderer.as_mut().map(|morph_renderer| morph_renderer.upload(&ctx.device, &product));
+    }
+    self.product = Some((product, player));
+    self.scene = Some(scene);
+  }
+
+  fn update(&mut self, ctx: &mut WgpuCtx, dt: f32) {
+    self.time += dt;
+    if let (Some(player), Some(scene)) = (self.player.as_mut(), self.scene.as_mut()) {
+      player.advance(dt);
+      player.apply(scene);
+      update_colliders(scene, ctx.camera.colliders_mut(), &self.animated);
+    }
+    if let Some((mesh, player)) = self.product.as_mut() {
+      player.advance(dt);
+      for (_, value) in player.clip.sample(player.local_time()) {
+        mesh.apply_value(&value);
+      }
+    }
+    if ctx.camera.is_active() {
+      return
+    }
+    self.angle += ORBIT_SPEED * dt;
+    let forward = Vector3::new(self.angle.sin(), 0.0, self.angle.cos());
+    ctx.camera.set_pose(self.center - forward * self.distance, self.angle, 0.0);
+  }
+
+  fn build_render_list(&mut self, ctx: &mut WgpuCtx) -> Vec<Vertex> {
+    let mut vertices = self.scene.as_ref().map(|s| s.vertices()).unwrap_or_default();
+    if let Some((mesh, clip)) = self.arm.as_ref() {
+      let time = self.time.rem_euclid(clip.duration().max(f32::EPSILON));
+      let pose = clip.sample(&mesh.skeleton, time);
+      match ctx.skinned_renderer.as_mut() {
+        Some(skinned_renderer) => skinned_renderer.queue(&mesh.vertices, &mesh.joint_matrices(&pose)),
+        // 没有 GPU 蒙皮时在 CPU 上蒙皮，和场景一起绘制
+        None => vertices.extend(skin_vertices(&mesh.vertices, &mesh.joint_matrices(&pose))),
+      }
+    }
+    if let Some((mesh, _)) = self.product.as_ref() {
+      match (ctx.morph_renderer.as_mut(), self.product_handle) {
+        (Some(morph_renderer), Some(handle)) => morph_renderer.queue(&ctx.queue, handle, mesh),
+        // 没有 GPU 变形时在 CPU 上变形，和场景一起绘制
+        _ => vertices.extend(mesh.morphed_vertices()),
+      }
+    }
+    vertices
+  }
+}
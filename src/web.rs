@@ -0,0 +1,217 @@
+//! 浏览器入口：通过 wasm-bindgen 导出给 JS 调用，TypeScript 类型由 wasm-bindgen 一起生成
+
+use std::rc::Rc;
+
+use js_sys::{ArrayBuffer, Function, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlCanvasElement;
+use winit::event_loop::EventLoop;
+use winit::platform::web::EventLoopExtWebSys;
+
+use crate::app::App;
+use crate::scene::file::scene_to_json;
+use crate::scene::model::CameraDesc;
+use crate::scene::pick::Hit;
+use crate::views::script::{ScriptState, SharedScript};
+
+#[wasm_bindgen(typescript_custom_section)]
+const PICK_CALLBACK: &str = r#"
+export type PickCallback = (event: PickEvent) => void;
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+  /// 拾取事件的回调函数
+  #[wasm_bindgen(typescript_type = "PickCallback")]
+  pub type PickCallback;
+}
+
+/// 在 id 为 `canvas_id` 的画布上启动渲染器，返回用于修改场景的对象，之后由浏览器驱动事件循环
+///
+/// 页面中需要先有这个 canvas 元素；wgpu 上下文异步创建，创建完成前画布保持空白，但场景可以立即修改。
+#[wasm_bindgen]
+pub fn start(canvas_id: &str) -> Result<SceneApi, JsValue> {
+  // panic 信息输出到浏览器控制台，否则只能看到 unreachable
+  std::panic::set_hook(Box::new(|info| web_sys::console::error_1(&info.to_string().into())));
+
+  let canvas = web_sys::window()
+    .and_then(|window| window.document())
+    .and_then(|document| document.get_element_by_id(canvas_id))
+    .ok_or_else(|| JsValue::from_str(&format!("找不到画布 #{}", canvas_id)))?
+    .dyn_into::<HtmlCanvasElement>()
+    .map_err(|_| JsValue::from_str(&format!("#{} 不是 canvas 元素", canvas_id)))?;
+
+  let api = SceneApi::new();
+  let event_loop = EventLoop::with_user_event().build().map_err(|e| JsValue::from_str(&e.to_string()))?;
+  let app = App::with_canvas(canvas, event_loop.create_proxy(), api.shared());
+  event_loop.spawn_app(app);
+  Ok(api)
+}
+
+/// 拾取事件：点击画布时命中的元素
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct PickEvent {
+  pub id: u32, // 元素 id
+  pub distance: f32, // 相机到交点的距离
+  pub x: f32, // 交点的世界坐标
+  pub y: f32,
+  pub z: f32,
+}
+
+impl From<&Hit> for PickEvent {
+  fn from(hit: &Hit) -> Self {
+    Self { id: hit.id, distance: hit.distance, x: hit.point.x, y: hit.point.y, z: hit.point.z }
+  }
+}
+
+/// JS 中修改场景的接口，角度都使用度，坐标使用世界坐标
+///
+/// 修改在下一帧绘制；找不到元素等错误以异常抛出。
+#[wasm_bindgen]
+pub struct SceneApi {
+  state: SharedScript,
+}
+
+impl Default for SceneApi {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[wasm_bindgen]
+impl SceneApi {
+  /// 创建空场景，不绘制，`start` 返回的对象才和画布关联
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Self {
+    Self { state: ScriptState::shared() }
+  }
+
+  /// 添加以 (x, y, z) 为中心的立方体，返回元素 id
+  #[wasm_bindgen(js_name = addCube)]
+  pub fn add_cube(&self, x: f32, y: f32, z: f32, width: f32, height: f32, depth: f32) -> u32 {
+    self.state.borrow_mut().add_cube([x, y, z], [width, height, depth])
+  }
+
+  /// 添加三角形列表组成的网格，`positions` 每 9 个数为一个三角形，返回元素 id
+  #[wasm_bindgen(js_name = addMesh)]
+  pub fn add_mesh(&self, name: &str, positions: &[f32]) -> Result<u32, JsValue> {
+    if !positions.len().is_multiple_of(3) {
+      return Err(JsValue::from_str("positions 的长度需要是 3 的倍数"));
+    }
+    let positions = positions.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect();
+    self.state.borrow_mut().add_mesh(name, positions).map_err(|e| JsValue::from_str(&e))
+  }
+
+  /// 从 OBJ 或 STL 文件内容（例如 fetch 得到的 ArrayBuffer）导入模型，返回元素 id
+  #[wasm_bindgen(js_name = loadModel)]
+  pub fn load_model(&self, name: &str, data: &ArrayBuffer) -> Result<u32, JsValue> {
+    let data = Uint8Array::new(data).to_vec();
+    self.state.borrow_mut().load_model(name, &data).map_err(|e| JsValue::from_str(&e))
+  }
+
+  /// 用场景 JSON 替换当前场景
+  #[wasm_bindgen(js_name = loadScene)]
+  pub fn load_scene(&self, json: &str) -> Result<(), JsValue> {
+    self.state.borrow_mut().load_scene(json).map_err(|e| JsValue::from_str(&e))
+  }
+
+  /// 当前场景的 JSON，与场景文件格式相同
+  #[wasm_bindgen(js_name = toJson)]
+  pub fn to_json(&self) -> Result<String, JsValue> {
+    scene_to_json(&self.state.borrow().scene).map_err(|e| JsValue::from_str(&e))
+  }
+
+  /// 删除元素，找不到时返回 false
+  pub fn remove(&self, id: u32) -> bool {
+    self.state.borrow_mut().remove(id)
+  }
+
+  /// 所有元素的 id
+  #[wasm_bindgen(js_name = elementIds)]
+  pub fn element_ids(&self) -> Vec<u32> {
+    self.state.borrow().scene.elements.iter().map(|e| e.id).collect()
+  }
+
+  /// 元素的位置、旋转（欧拉角）和缩放，依次 9 个数
+  pub fn transform(&self, id: u32) -> Result<Vec<f32>, JsValue> {
+    let state = self.state.borrow();
+    let element = state.scene.element(id).ok_or_else(|| JsValue::from_str(&format!("元素 {} 不存在", id)))?;
+    let t = element.transform;
+    Ok([t.position, t.rotation, t.scale].concat())
+  }
+
+  #[wasm_bindgen(js_name = setPosition)]
+  pub fn set_position(&self, id: u32, x: f32, y: f32, z: f32) -> Result<(), JsValue> {
+    self.state.borrow_mut().update_transform(id, |t| t.position = [x, y, z]).map_err(|e| JsValue::from_str(&e))
+  }
+
+  /// 按 x、y、z 顺序旋转的欧拉角（度）
+  #[wasm_bindgen(js_name = setRotation)]
+  pub fn set_rotation(&self, id: u32, x: f32, y: f32, z: f32) -> Result<(), JsValue> {
+    self.state.borrow_mut().update_transform(id, |t| t.rotation = [x, y, z]).map_err(|e| JsValue::from_str(&e))
+  }
+
+  #[wasm_bindgen(js_name = setScale)]
+  pub fn set_scale(&self, id: u32, x: f32, y: f32, z: f32) -> Result<(), JsValue> {
+    self.state.borrow_mut().update_transform(id, |t| t.scale = [x, y, z]).map_err(|e| JsValue::from_str(&e))
+  }
+
+  /// 颜色分量为 0~1，`opacity` 小于 1 时按半透明绘制
+  #[wasm_bindgen(js_name = setColor)]
+  pub fn set_color(&self, id: u32, r: f32, g: f32, b: f32, opacity: f32) -> Result<(), JsValue> {
+    self.state.borrow_mut().set_color(id, [r, g, b], opacity).map_err(|e| JsValue::from_str(&e))
+  }
+
+  /// 设置相机位置和朝向（度），偏航角 0 朝向 +z
+  #[wasm_bindgen(js_name = setCamera)]
+  pub fn set_camera(&self, x: f32, y: f32, z: f32, yaw: f32, pitch: f32) {
+    let mut state = self.state.borrow_mut();
+    let camera = state.camera();
+    state.set_camera(CameraDesc { position: [x, y, z], yaw, pitch, ..camera });
+  }
+
+  /// 设置视场角（度）
+  #[wasm_bindgen(js_name = setFov)]
+  pub fn set_fov(&self, fov: f32) {
+    let mut state = self.state.borrow_mut();
+    let camera = state.camera();
+    state.set_camera(CameraDesc { fov, ..camera });
+  }
+
+  /// 相机的位置、偏航角和俯仰角（度），依次 5 个数
+  pub fn camera(&self) -> Vec<f32> {
+    let camera = self.state.borrow().camera();
+    [camera.position.as_slice(), &[camera.yaw, camera.pitch]].concat()
+  }
+
+  /// 拾取画布上像素坐标（左上角为原点，不是 CSS 像素）处最近的元素，没有命中时返回 undefined
+  pub fn pick(&self, x: f32, y: f32) -> Option<PickEvent> {
+    self.state.borrow().pick(x, y).as_ref().map(PickEvent::from)
+  }
+
+  /// 点击画布命中元素时调用 `callback`，返回用于 `offPick` 的 id
+  #[wasm_bindgen(js_name = onPick)]
+  pub fn on_pick(&self, callback: PickCallback) -> u32 {
+    let callback: Function = callback.unchecked_into();
+    self.state.borrow_mut().add_listener(Rc::new(move |hit| {
+      if let Err(e) = callback.call1(&JsValue::NULL, &PickEvent::from(hit).into()) {
+        web_sys::console::error_1(&e);
+      }
+    }))
+  }
+
+  /// 取消监听，id 不存在时返回 false
+  #[wasm_bindgen(js_name = offPick)]
+  pub fn off_pick(&self, id: u32) -> bool {
+    self.state.borrow_mut().remove_listener(id)
+  }
+}
+
+impl SceneApi {
+  /// 与脚本视图共享的状态
+  pub fn shared(&self) -> SharedScript {
+    self.state.clone()
+  }
+}
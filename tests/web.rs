@@ -0,0 +1,149 @@
+//! JS 场景接口中不需要 GPU 的部分，在 Node 中运行：wasm-pack test --node
+
+#![cfg(target_arch = "wasm32")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{ArrayBuffer, Uint8Array};
+use kidar_rust_3d::render::draw::{grow_capacity, INITIAL_VERTEX_CAPACITY};
+use kidar_rust_3d::views::script::click;
+use kidar_rust_3d::web::{PickCallback, PickEvent, SceneApi};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn array_buffer(bytes: &[u8]) -> ArrayBuffer {
+  Uint8Array::from(bytes).buffer()
+}
+
+// 1x1x1 的立方体，8 个顶点 6 个四边形
+const CUBE_OBJ: &str = "
+v -0.5 -0.5 -0.5
+v 0.5 -0.5 -0.5
+v 0.5 0.5 -0.5
+v -0.5 0.5 -0.5
+v -0.5 -0.5 0.5
+v 0.5 -0.5 0.5
+v 0.5 0.5 0.5
+v -0.5 0.5 0.5
+f 1 2 3 4
+f 5 8 7 6
+f 1 5 6 2
+f 2 6 7 3
+f 3 7 8 4
+f 5 1 4 8
+";
+
+#[wasm_bindgen_test]
+fn add_and_remove_elements() {
+  let api = SceneApi::new();
+  let a = api.add_cube(0.0, 0.0, 0.0, 100.0, 100.0, 100.0);
+  let b = api.add_cube(200.0, 0.0, 0.0, 50.0, 50.0, 50.0);
+  assert_ne!(a, b);
+  assert_eq!(api.element_ids(), vec![a, b]);
+  assert!(api.remove(a));
+  assert!(!api.remove(a));
+  assert_eq!(api.element_ids(), vec![b]);
+}
+
+#[wasm_bindgen_test]
+fn many_cubes_grow_vertex_buffer() {
+  let api = SceneApi::new();
+  for i in 0..40 {
+    api.add_cube(i as f32 * 20.0, 0.0, 0.0, 10.0, 10.0, 10.0);
+  }
+  let (vertices, _) = api.shared().borrow().scene.render_list();
+  assert_eq!(vertices.len(), 40 * 36);
+  // 超过初始大小，扩容到 2 的幂后能放下，之后大小不变时不再扩容
+  let size = std::mem::size_of_val(vertices.as_slice()) as u64;
+  assert!(size > INITIAL_VERTEX_CAPACITY);
+  let capacity = grow_capacity(INITIAL_VERTEX_CAPACITY, size);
+  assert!(capacity >= size && capacity.is_power_of_two());
+  assert_eq!(grow_capacity(capacity, size), capacity);
+}
+
+#[wasm_bindgen_test]
+fn transforms_and_colors() {
+  let api = SceneApi::new();
+  let id = api.add_cube(0.0, 0.0, 0.0, 10.0, 10.0, 10.0);
+  api.set_position(id, 1.0, 2.0, 3.0).unwrap();
+  api.set_rotation(id, 0.0, 90.0, 0.0).unwrap();
+  api.set_scale(id, 2.0, 2.0, 2.0).unwrap();
+  assert_eq!(api.transform(id).unwrap(), vec![1.0, 2.0, 3.0, 0.0, 90.0, 0.0, 2.0, 2.0, 2.0]);
+  api.set_color(id, 1.0, 0.0, 0.0, 0.5).unwrap();
+  let json = api.to_json().unwrap();
+  assert!(json.contains("\"opacity\": 0.5"));
+  assert!(api.set_position(id + 1, 0.0, 0.0, 0.0).is_err());
+  assert!(api.set_color(id + 1, 0.0, 0.0, 0.0, 1.0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn camera_changes() {
+  let api = SceneApi::new();
+  api.set_camera(1.0, 2.0, 3.0, 45.0, -10.0);
+  api.set_fov(60.0);
+  assert_eq!(api.camera(), vec![1.0, 2.0, 3.0, 45.0, -10.0]);
+  let camera = api.shared().borrow_mut().take_camera_change().unwrap();
+  assert_eq!(camera.fov, 60.0);
+  assert!(api.shared().borrow_mut().take_camera_change().is_none());
+}
+
+#[wasm_bindgen_test]
+fn load_obj_model() {
+  let api = SceneApi::new();
+  let id = api.load_model("cube", &array_buffer(CUBE_OBJ.as_bytes())).unwrap();
+  let json = api.to_json().unwrap();
+  // 6 个四边形拆成 12 个三角形
+  let scene: serde_json::Value = serde_json::from_str(&json).unwrap();
+  let element = &scene["elements"][0];
+  assert_eq!(element["id"], id);
+  assert_eq!(element["shape"]["positions"].as_array().unwrap().len(), 36);
+  assert!(api.load_model("broken", &array_buffer(b"f 1 2 3")).is_err());
+}
+
+#[wasm_bindgen_test]
+fn load_binary_stl_model() {
+  let mut data = vec![0u8; 80];
+  data.extend(1u32.to_le_bytes());
+  for value in [0.0f32, 0.0, 1.0, 10.0, 10.0, 0.0, 30.0, 10.0, 0.0, 10.0, 30.0, 0.0] {
+    data.extend(value.to_le_bytes());
+  }
+  data.extend([0, 0]);
+  let api = SceneApi::new();
+  let id = api.load_model("triangle", &array_buffer(&data)).unwrap();
+  // 元素位置为包围盒中心
+  assert_eq!(api.transform(id).unwrap()[..3], [20.0, 20.0, 0.0]);
+}
+
+#[wasm_bindgen_test]
+fn pick_and_listeners() {
+  let api = SceneApi::new();
+  let near = api.add_cube(0.0, 0.0, 500.0, 100.0, 100.0, 100.0);
+  let far = api.add_cube(0.0, 0.0, 800.0, 100.0, 100.0, 100.0);
+  api.set_camera(0.0, 0.0, 0.0, 0.0, 0.0);
+  let state = api.shared();
+  let camera = state.borrow().camera();
+  state.borrow_mut().set_viewport(camera, 800.0, 600.0);
+
+  let hit = api.pick(400.0, 300.0).unwrap();
+  assert_eq!(hit.id, near);
+  assert!((hit.z - 450.0).abs() < 1.0);
+  assert!(api.pick(0.0, 0.0).is_none());
+
+  // 监听函数中删除命中的元素，下一次点击命中后面的元素
+  let picked = Rc::new(RefCell::new(vec![]));
+  let callback = {
+    let (picked, state) = (picked.clone(), state.clone());
+    Closure::<dyn Fn(PickEvent)>::new(move |event: PickEvent| {
+      picked.borrow_mut().push(event.id);
+      state.borrow_mut().remove(event.id);
+    })
+  };
+  let listener = api.on_pick(callback.as_ref().clone().unchecked_into::<PickCallback>());
+  click(&state, 400.0, 300.0);
+  click(&state, 400.0, 300.0);
+  assert_eq!(*picked.borrow(), vec![near, far]);
+  assert!(api.off_pick(listener));
+  assert!(!api.off_pick(listener));
+}